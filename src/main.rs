@@ -12,6 +12,8 @@ use env_logger::Builder;
 use log::Record;
 use std::env;
 use std::io::Write;
+use std::process;
+use t_rex_service::mvt_service::DumpFormat;
 use t_rex_webserver as webserver;
 use tile_grid::Extent;
 use time;
@@ -44,21 +46,8 @@ fn init_logger(args: &ArgMatches<'_>) {
     builder.init();
 }
 
-fn generate(args: &ArgMatches<'_>) {
-    let config = webserver::config_from_args(&args);
-    let mut service = webserver::service_from_args(&config, &args);
-    config
-        .cache
-        .expect("Missing configuration entry base in [cache.file]");
-    let tileset = args.value_of("tileset");
-    let minzoom = args.value_of("minzoom").map(|s| {
-        s.parse::<u8>()
-            .expect("Error parsing 'minzoom' as integer value")
-    });
-    let maxzoom = args.value_of("maxzoom").map(|s| {
-        s.parse::<u8>()
-            .expect("Error parsing 'maxzoom' as integer value")
-    });
+/// Parse `extent` argument with optional SRID
+fn extent_arg(args: &ArgMatches<'_>) -> (Option<Extent>, Option<i32>) {
     let extent = args.value_of("extent").and_then(|numlist| {
         let arr: Vec<f64> = numlist
             .split(",")
@@ -88,6 +77,25 @@ fn generate(args: &ArgMatches<'_>) {
             _ => None,
         }
     });
+    (extent, extent_srid)
+}
+
+fn generate(args: &ArgMatches<'_>) {
+    let config = webserver::config_from_args(&args);
+    let mut service = webserver::service_from_args(&config, &args);
+    config
+        .cache
+        .expect("Missing configuration entry base in [cache.file] or argument --output-dir");
+    let tileset = args.value_of("tileset");
+    let minzoom = args.value_of("minzoom").map(|s| {
+        s.parse::<u8>()
+            .expect("Error parsing 'minzoom' as integer value")
+    });
+    let maxzoom = args.value_of("maxzoom").map(|s| {
+        s.parse::<u8>()
+            .expect("Error parsing 'maxzoom' as integer value")
+    });
+    let (extent, extent_srid) = extent_arg(args);
     let nodes = args.value_of("nodes").map(|s| {
         s.parse::<u8>()
             .expect("Error parsing 'nodes' as integer value")
@@ -104,8 +112,24 @@ fn generate(args: &ArgMatches<'_>) {
         s.parse::<bool>()
             .expect("Error parsing 'overwrite' as boolean value")
     });
+    let threads = args
+        .value_of("threads")
+        .map(|s| {
+            s.parse::<usize>()
+                .expect("Error parsing 'threads' as integer value")
+        })
+        .or(config.service.mvt.generate_threads);
+    if threads == Some(0) {
+        println!("Number of generate threads must be at least 1");
+        process::exit(1)
+    }
     service.prepare_feature_queries();
-    service.generate(
+    if let Err(err) = service.check_srids() {
+        println!("{}", err);
+        process::exit(1)
+    }
+    service.check_versions();
+    let summaries = service.generate(
         tileset,
         minzoom,
         maxzoom,
@@ -115,10 +139,23 @@ fn generate(args: &ArgMatches<'_>) {
         progress,
         overwrite,
         extent_srid,
+        threads,
     );
+    // Signal incomplete generation to scripts
+    if summaries
+        .iter()
+        .any(|summary| summary.interrupted.is_some())
+    {
+        process::exit(1)
+    }
 }
 
 fn drilldown(args: &ArgMatches<'_>) {
+    let format = args.value_of("format").unwrap_or("csv");
+    if format != "csv" && format != "json" {
+        println!("Unsupported output format '{}'", format);
+        process::exit(1)
+    }
     let config = webserver::config_from_args(&args);
     let mut service = webserver::service_from_args(&config, &args);
     let tileset = args.value_of("tileset");
@@ -146,9 +183,121 @@ fn drilldown(args: &ArgMatches<'_>) {
         s.parse::<bool>()
             .expect("Error parsing 'progress' as boolean value")
     });
+    let count_only = args.value_of("count-only").map_or(false, |s| {
+        s.parse::<bool>()
+            .expect("Error parsing 'count-only' as boolean value")
+    });
+    service.prepare_feature_queries();
+    let stats = service.drilldown(tileset, minzoom, maxzoom, points, progress, count_only);
+    if format == "json" {
+        let json = stats.as_json().expect("Error serializing statistics");
+        println!("{:#}", json);
+    } else {
+        print!("{}", stats.as_csv());
+    }
+}
+
+fn estimate(args: &ArgMatches<'_>) {
+    let config = webserver::config_from_args(&args);
+    let mut service = webserver::service_from_args(&config, &args);
+    let tileset = args.value_of("tileset").expect("Missing 'tileset'");
+    let minzoom = args.value_of("minzoom").map(|s| {
+        s.parse::<u8>()
+            .expect("Error parsing 'minzoom' as integer value")
+    });
+    let maxzoom = args.value_of("maxzoom").map(|s| {
+        s.parse::<u8>()
+            .expect("Error parsing 'maxzoom' as integer value")
+    });
+    let (extent, extent_srid) = extent_arg(args);
+    let samples = args.value_of("samples").map_or(100, |s| {
+        s.parse::<u64>()
+            .expect("Error parsing 'samples' as integer value")
+    });
     service.prepare_feature_queries();
-    let stats = service.drilldown(tileset, minzoom, maxzoom, points, progress);
-    print!("{}", stats.as_csv());
+    match service.estimate(tileset, minzoom, maxzoom, extent, extent_srid, samples) {
+        Some(estimate) => println!(
+            "Tileset '{}': {} tiles, estimated cache size {:.1} MB ({} samples)",
+            estimate.tileset,
+            estimate.tiles,
+            estimate.total_bytes as f64 / 1_000_000.0,
+            estimate.samples
+        ),
+        None => {
+            println!("Tileset '{}' not found", tileset);
+            process::exit(1)
+        }
+    }
+}
+
+fn purge(args: &ArgMatches<'_>) {
+    let config = webserver::config_from_args(&args);
+    let service = webserver::service_from_args(&config, &args);
+    config
+        .cache
+        .expect("Missing configuration entry base in [cache.file]");
+    let tileset = args.value_of("tileset").expect("Missing 'tileset'");
+    let minzoom = args.value_of("minzoom").map(|s| {
+        s.parse::<u8>()
+            .expect("Error parsing 'minzoom' as integer value")
+    });
+    let maxzoom = args.value_of("maxzoom").map(|s| {
+        s.parse::<u8>()
+            .expect("Error parsing 'maxzoom' as integer value")
+    });
+    let (extent, extent_srid) = extent_arg(args);
+    match service.purge(tileset, minzoom, maxzoom, extent, extent_srid) {
+        Some(removed) => println!("Tileset '{}': {} tiles removed", tileset, removed),
+        None => {
+            println!("Tileset '{}' not found", tileset);
+            process::exit(1)
+        }
+    }
+}
+
+fn dump(args: &ArgMatches<'_>) {
+    let config = webserver::config_from_args(&args);
+    let mut service = webserver::service_from_args(&config, &args);
+    let tileset = args.value_of("tileset").expect("Missing 'tileset'");
+    let zxy: Vec<u32> = args
+        .value_of("zxy")
+        .map(|tile| {
+            tile.split("/")
+                .map(|v| {
+                    v.parse()
+                        .expect("Error parsing 'zxy' as z/x/y integer values")
+                })
+                .collect()
+        })
+        .expect("Missing 'zxy'");
+    if zxy.len() != 3 {
+        println!(
+            "Invalid tile coordinates '{}'",
+            args.value_of("zxy").unwrap()
+        );
+        process::exit(1)
+    }
+    let format = match args.value_of("format").unwrap_or("geojson") {
+        "geojson" => DumpFormat::GeoJson {
+            antimeridian_split: args.value_of("antimeridian-split").map_or(false, |s| {
+                s.parse::<bool>()
+                    .expect("Error parsing 'antimeridian-split' as boolean value")
+            }),
+        },
+        "wkt" => DumpFormat::Wkt,
+        format => {
+            println!("Unsupported output format '{}'", format);
+            process::exit(1)
+        }
+    };
+    service.prepare_feature_queries();
+    match service.dump_tile(tileset, zxy[1], zxy[2], zxy[0] as u8, format) {
+        Ok(dump) => println!("{}", dump),
+        Err(err) => {
+            println!("{}", err);
+            process::exit(1)
+        }
+    }
 }
 
 #[cfg(feature = "with-gdal")]
@@ -178,6 +327,7 @@ fn main() {
                         .args_from_usage("--dbconn=[SPEC] 'PostGIS connection postgresql://USER@HOST/DBNAME'
                                               --datasource=[FILE_OR_GDAL_DS] 'GDAL datasource specification'
                                               --detect-geometry-types=[true|false] 'Detect geometry types when undefined'
+                                              --detect-views=[true|false] 'Detect geometry columns of views missing in geometry_columns'
                                               --qgs=[FILE] 'QGIS project file'
                                               --loglevel=[error|warn|info|debug|trace] 'Log level (Default: info)'
                                               --simplify=[true|false] 'Simplify geometries'
@@ -193,6 +343,7 @@ fn main() {
                         .args_from_usage("--dbconn=[SPEC] 'PostGIS connection postgresql://USER@HOST/DBNAME'
                                               --datasource=[FILE_OR_GDAL_DS] 'GDAL datasource specification'
                                               --detect-geometry-types=[true|false] 'Detect geometry types when undefined'
+                                              --detect-views=[true|false] 'Detect geometry columns of views missing in geometry_columns'
                                               --qgs=[FILE] 'QGIS project file'
                                               --loglevel=[error|warn|info|debug|trace] 'Log level (Default: info)'
                                               --simplify=[true|false] 'Simplify geometries'
@@ -210,7 +361,9 @@ fn main() {
                                               --nodes=[NUM] 'Number of generator nodes'
                                               --nodeno=[NUM] 'Number of this nodes (0 <= n < nodes)'
                                               --progress=[true|false] 'Show progress bar'
-                                              --overwrite=[false|true] 'Overwrite previously cached tiles'")
+                                              --overwrite=[false|true] 'Overwrite previously cached tiles'
+                                              --threads=[NUM] 'Number of concurrent generator tasks'
+                                              --output-dir=[DIR] 'Base directory of file cache (overrides [cache.file])'")
                         .about("Generate tiles for cache"))
         .subcommand(SubCommand::with_name("drilldown")
                         .setting(AppSettings::AllowLeadingHyphen)
@@ -220,8 +373,37 @@ fn main() {
                                               --minzoom=[LEVEL] 'Minimum zoom level'
                                               --maxzoom=[LEVEL] 'Maximum zoom level'
                                               --points=[x1,y1,x2,y2,..] 'Drilldown points'
-                                              --progress=[true|false] 'Show progress bar'")
-                        .about("Tile layer statistics"));
+                                              --progress=[true|false] 'Show progress bar'
+                                              --format=[csv|json] 'Output format (Default: csv)'
+                                              --count-only=[false|true] 'Count features without building tiles'")
+                        .about("Tile layer statistics"))
+        .subcommand(SubCommand::with_name("estimate")
+                        .setting(AppSettings::AllowLeadingHyphen)
+                        .args_from_usage("-c, --config=<FILE> 'Load from custom config file'
+                                              --loglevel=[error|warn|info|debug|trace] 'Log level (Default: info)'
+                                              --tileset=<NAME> 'Tileset name'
+                                              --minzoom=[LEVEL] 'Minimum zoom level'
+                                              --maxzoom=[LEVEL] 'Maximum zoom level'
+                                              --extent=[minx,miny,maxx,maxy[,srid]] 'Extent of tiles'
+                                              --samples=[NUM] 'Number of sample tiles (Default: 100)'")
+                        .about("Estimate tile cache size from sample tiles"))
+        .subcommand(SubCommand::with_name("purge")
+                        .setting(AppSettings::AllowLeadingHyphen)
+                        .args_from_usage("-c, --config=<FILE> 'Load from custom config file'
+                                              --loglevel=[error|warn|info|debug|trace] 'Log level (Default: info)'
+                                              --tileset=<NAME> 'Tileset name'
+                                              --minzoom=[LEVEL] 'Minimum zoom level'
+                                              --maxzoom=[LEVEL] 'Maximum zoom level'
+                                              --extent=[minx,miny,maxx,maxy[,srid]] 'Extent of tiles'")
+                        .about("Remove tiles from cache"))
+        .subcommand(SubCommand::with_name("dump")
+                        .args_from_usage("-c, --config=<FILE> 'Load from custom config file'
+                                              --loglevel=[error|warn|info|debug|trace] 'Log level (Default: info)'
+                                              --tileset=<NAME> 'Tileset name'
+                                              --zxy=<Z/X/Y> 'Tile coordinates'
+                                              --format=[geojson|wkt] 'Output format (Default: geojson)'
+                                              --antimeridian-split=[true|false] 'Split GeoJSON lines at the antimeridian (WGS84 grid)'")
+                        .about("Print decoded features of a tile"));
 
     match app.get_matches_from_safe_borrow(env::args()) {
         //app.get_matches() prohibits later call of app.print_help()
@@ -245,6 +427,18 @@ fn main() {
                 init_logger(sub_m);
                 drilldown(sub_m);
             }
+            ("estimate", Some(sub_m)) => {
+                init_logger(sub_m);
+                estimate(sub_m);
+            }
+            ("purge", Some(sub_m)) => {
+                init_logger(sub_m);
+                purge(sub_m);
+            }
+            ("dump", Some(sub_m)) => {
+                init_logger(sub_m);
+                dump(sub_m);
+            }
             _ => {
                 let _ = app.print_help();
                 println!("");
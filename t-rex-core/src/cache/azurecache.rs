@@ -0,0 +1,353 @@
+//
+// Copyright (c) Pirmin Kalberer. All rights reserved.
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+//
+
+use crate::cache::objectstore::{http_request, ObjectStore, PutObject, COMPONENT, OBJECT_PATH};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use percent_encoding::utf8_percent_encode;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+const STORAGE_VERSION: &str = "2020-04-08";
+const IDENTITY_TOKEN_URL: &str = "http://169.254.169.254/metadata/identity/oauth2/token?api-version=2018-02-01&resource=https%3A%2F%2Fstorage.azure.com%2F";
+
+/// Authentication of storage requests
+enum AzureAuth {
+    /// Shared key authorization with account key
+    SharedKey { account_key: Vec<u8> },
+    /// Bearer token of managed identity with expiration time
+    ManagedIdentity {
+        token: Mutex<Option<(String, Instant)>>,
+    },
+}
+
+/// Azure Blob Storage container
+pub struct AzureBlobContainer {
+    account: String,
+    container: String,
+    /// Blob service endpoint (e.g. `https://account.blob.core.windows.net`)
+    endpoint: String,
+    auth: AzureAuth,
+}
+
+impl AzureBlobContainer {
+    /// Container accessed with the account key of a storage connection string
+    pub fn from_connection_string(
+        container: &str,
+        connection_string: &str,
+    ) -> Result<AzureBlobContainer, String> {
+        let setting = |name: &str| {
+            connection_string
+                .split(';')
+                .filter_map(|part| {
+                    let mut kv = part.splitn(2, '=');
+                    match (kv.next(), kv.next()) {
+                        (Some(key), Some(value)) if key.trim() == name => {
+                            Some(value.trim().to_string())
+                        }
+                        _ => None,
+                    }
+                })
+                .next()
+        };
+        let account =
+            setting("AccountName").ok_or("Missing AccountName in connection string".to_string())?;
+        let account_key = setting("AccountKey")
+            .ok_or("Missing AccountKey in connection string".to_string())
+            .and_then(|key| {
+                base64::decode(&key).map_err(|e| format!("Invalid AccountKey: {}", e))
+            })?;
+        let endpoint = match setting("BlobEndpoint") {
+            Some(endpoint) => endpoint.trim_end_matches('/').to_string(),
+            None => format!(
+                "{}://{}.blob.{}",
+                setting("DefaultEndpointsProtocol").unwrap_or("https".to_string()),
+                account,
+                setting("EndpointSuffix").unwrap_or("core.windows.net".to_string())
+            ),
+        };
+        Ok(AzureBlobContainer {
+            account,
+            container: container.to_string(),
+            endpoint,
+            auth: AzureAuth::SharedKey { account_key },
+        })
+    }
+
+    /// Container accessed with the managed identity of the host
+    pub fn with_managed_identity(account: &str, container: &str) -> AzureBlobContainer {
+        AzureBlobContainer {
+            account: account.to_string(),
+            container: container.to_string(),
+            endpoint: format!("https://{}.blob.core.windows.net", account),
+            auth: AzureAuth::ManagedIdentity {
+                token: Mutex::new(None),
+            },
+        }
+    }
+
+    /// Access token of managed identity, renewed before expiration
+    fn identity_token(token: &Mutex<Option<(String, Instant)>>) -> Result<String, String> {
+        let mut token = token.lock().unwrap();
+        if let Some((ref access_token, expires)) = *token {
+            if Instant::now() < expires {
+                return Ok(access_token.clone());
+            }
+        }
+        let headers = vec!["Metadata: true".to_string()];
+        let (status, data) = http_request("GET", IDENTITY_TOKEN_URL, &headers, None)?;
+        if status != 200 {
+            return Err(format!("Token request failed with status {}", status));
+        }
+        let response: serde_json::Value =
+            serde_json::from_slice(&data).map_err(|e| e.to_string())?;
+        let access_token = response["access_token"]
+            .as_str()
+            .ok_or("Missing access_token in token response".to_string())?
+            .to_string();
+        // expires_in is returned as string
+        let expires_in = match response["expires_in"] {
+            serde_json::Value::String(ref secs) => secs.parse().ok(),
+            ref value => value.as_u64(),
+        }
+        .unwrap_or(3600);
+        let expires = Instant::now() + Duration::from_secs(expires_in.saturating_sub(60));
+        *token = Some((access_token.clone(), expires));
+        Ok(access_token)
+    }
+
+    /// Authorized request to blob service
+    fn request(
+        &self,
+        method: &str,
+        key: Option<&str>,
+        query: &[(&str, &str)],
+        mut headers: Vec<(String, String)>,
+        body: Option<&[u8]>,
+    ) -> Result<(u32, Vec<u8>), String> {
+        let mut path = format!("/{}", self.container);
+        if let Some(key) = key {
+            path.push_str(&format!("/{}", utf8_percent_encode(key, OBJECT_PATH)));
+        }
+        let mut url = format!("{}{}", self.endpoint, path);
+        for (i, (name, value)) in query.iter().enumerate() {
+            url.push(if i == 0 { '?' } else { '&' });
+            url.push_str(&format!(
+                "{}={}",
+                name,
+                utf8_percent_encode(value, COMPONENT)
+            ));
+        }
+        headers.push((
+            "x-ms-date".to_string(),
+            httpdate::fmt_http_date(SystemTime::now()),
+        ));
+        headers.push(("x-ms-version".to_string(), STORAGE_VERSION.to_string()));
+        let authorization = match self.auth {
+            AzureAuth::SharedKey { ref account_key } => {
+                let content_length = body.map_or(0, |b| b.len());
+                let string_to_sign = string_to_sign(
+                    method,
+                    &self.account,
+                    &path,
+                    query,
+                    &headers,
+                    content_length,
+                );
+                format!(
+                    "SharedKey {}:{}",
+                    self.account,
+                    sign(account_key, &string_to_sign)?
+                )
+            }
+            AzureAuth::ManagedIdentity { ref token } => {
+                format!("Bearer {}", Self::identity_token(token)?)
+            }
+        };
+        headers.push(("Authorization".to_string(), authorization));
+        let headers: Vec<String> = headers
+            .iter()
+            .map(|(name, value)| format!("{}: {}", name, value))
+            .collect();
+        http_request(method, &url, &headers, body)
+    }
+}
+
+/// String to sign for Shared Key authorization
+pub(crate) fn string_to_sign(
+    method: &str,
+    account: &str,
+    path: &str,
+    query: &[(&str, &str)],
+    headers: &[(String, String)],
+    content_length: usize,
+) -> String {
+    let header = |name: &str| {
+        headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map_or("", |(_, value)| value.as_str())
+    };
+    let content_length = if content_length > 0 {
+        content_length.to_string()
+    } else {
+        String::new()
+    };
+    let mut ms_headers: Vec<(String, &str)> = headers
+        .iter()
+        .filter(|(name, _)| name.to_lowercase().starts_with("x-ms-"))
+        .map(|(name, value)| (name.to_lowercase(), value.trim()))
+        .collect();
+    ms_headers.sort();
+    let mut query: Vec<(String, &str)> = query
+        .iter()
+        .map(|(name, value)| (name.to_lowercase(), *value))
+        .collect();
+    query.sort();
+    let mut resource = format!("/{}{}", account, path);
+    for (name, value) in query {
+        resource.push_str(&format!("\n{}:{}", name, value));
+    }
+    let mut lines = vec![
+        method.to_string(),
+        header("Content-Encoding").to_string(),
+        header("Content-Language").to_string(),
+        content_length,
+        header("Content-MD5").to_string(),
+        header("Content-Type").to_string(),
+        header("Date").to_string(),
+        header("If-Modified-Since").to_string(),
+        header("If-Match").to_string(),
+        header("If-None-Match").to_string(),
+        header("If-Unmodified-Since").to_string(),
+        header("Range").to_string(),
+    ];
+    lines.extend(
+        ms_headers
+            .iter()
+            .map(|(name, value)| format!("{}:{}", name, value)),
+    );
+    lines.push(resource);
+    lines.join("\n")
+}
+
+/// Base64 encoded HMAC-SHA256 signature
+fn sign(account_key: &[u8], string_to_sign: &str) -> Result<String, String> {
+    PKey::hmac(account_key)
+        .and_then(|pkey| {
+            let mut signer = Signer::new(MessageDigest::sha256(), &pkey)?;
+            signer.update(string_to_sign.as_bytes())?;
+            signer.sign_to_vec()
+        })
+        .map(|signature| base64::encode(&signature))
+        .map_err(|e| format!("Error signing request: {}", e))
+}
+
+/// Text content of all XML elements with name `tag`
+pub(crate) fn xml_elements(xml: &str, tag: &str) -> Vec<String> {
+    let start_tag = format!("<{}>", tag);
+    let end_tag = format!("</{}>", tag);
+    let mut elements = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&start_tag) {
+        rest = &rest[start + start_tag.len()..];
+        match rest.find(&end_tag) {
+            Some(end) => {
+                let text = rest[..end]
+                    .replace("&lt;", "<")
+                    .replace("&gt;", ">")
+                    .replace("&quot;", "\"")
+                    .replace("&apos;", "'")
+                    .replace("&amp;", "&");
+                elements.push(text);
+                rest = &rest[end + end_tag.len()..];
+            }
+            None => break,
+        }
+    }
+    elements
+}
+
+impl ObjectStore for AzureBlobContainer {
+    fn get(&self, key: &str) -> Option<Vec<u8>> {
+        match self.request("GET", Some(key), &[], Vec::new(), None) {
+            Ok((200, data)) => Some(data),
+            Ok(_) => None,
+            Err(e) => {
+                error!("{}", e);
+                None
+            }
+        }
+    }
+
+    fn put(&self, object: PutObject) -> Result<(), String> {
+        let mut headers = vec![
+            ("x-ms-blob-type".to_string(), "BlockBlob".to_string()),
+            ("Content-Type".to_string(), object.content_type),
+        ];
+        if let Some(content_encoding) = object.content_encoding {
+            headers.push(("Content-Encoding".to_string(), content_encoding));
+        }
+        if let Some(cache_control) = object.cache_control {
+            headers.push(("x-ms-blob-cache-control".to_string(), cache_control));
+        }
+        match self.request("PUT", Some(&object.key), &[], headers, Some(&object.body))? {
+            (201, _) => Ok(()),
+            (status, data) => Err(format!(
+                "Upload of {} failed with status {}: {}",
+                object.key,
+                status,
+                String::from_utf8_lossy(&data)
+            )),
+        }
+    }
+
+    fn head(&self, key: &str) -> bool {
+        match self.request("HEAD", Some(key), &[], Vec::new(), None) {
+            Ok((status, _)) => status == 200,
+            Err(e) => {
+                error!("{}", e);
+                false
+            }
+        }
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>, String> {
+        let mut keys = Vec::new();
+        let mut marker = String::new();
+        loop {
+            let mut query = vec![
+                ("restype", "container"),
+                ("comp", "list"),
+                ("prefix", prefix),
+            ];
+            if !marker.is_empty() {
+                query.push(("marker", &marker));
+            }
+            let (status, data) = self.request("GET", None, &query, Vec::new(), None)?;
+            if status != 200 {
+                return Err(format!("Listing failed with status {}", status));
+            }
+            let xml = String::from_utf8_lossy(&data);
+            keys.extend(xml_elements(&xml, "Name"));
+            marker = xml_elements(&xml, "NextMarker").pop().unwrap_or_default();
+            if marker.is_empty() {
+                break;
+            }
+        }
+        Ok(keys)
+    }
+
+    fn delete(&self, key: &str) -> bool {
+        match self.request("DELETE", Some(key), &[], Vec::new(), None) {
+            Ok((status, _)) => status == 202,
+            Err(e) => {
+                error!("{}", e);
+                false
+            }
+        }
+    }
+}
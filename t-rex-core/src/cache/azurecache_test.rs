@@ -0,0 +1,111 @@
+//
+// Copyright (c) Pirmin Kalberer. All rights reserved.
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+//
+
+use crate::cache::azurecache::{string_to_sign, xml_elements, AzureBlobContainer};
+use crate::cache::cache::Cache;
+use crate::cache::objectstore::{ObjectCacheSettings, ObjectStoreCache};
+use crate::cache::s3cache_test::MemoryStore;
+use std::sync::Arc;
+
+#[test]
+fn test_azurecache_fake_store() {
+    let store = Arc::new(MemoryStore::default());
+    let settings = ObjectCacheSettings {
+        key_prefix: Some("my-prefix".to_string()),
+        gzip_header_enabled: Some(false),
+        ..Default::default()
+    };
+    let cache = ObjectStoreCache::new("azure: account/tiles", store.clone(), settings);
+    assert_eq!(cache.info(), "Tile cache azure: account/tiles");
+    assert_eq!(cache.tile_compression(), "gzip");
+
+    // Same paths as file cache
+    let path = "tileset/3/4/2.pbf";
+    assert_eq!(cache.read(path, |_| {}), false);
+    assert!(!cache.exists(path));
+
+    cache.write(path, b"0123456789").unwrap();
+    assert!(store
+        .objects
+        .lock()
+        .unwrap()
+        .contains_key("my-prefix/tileset/3/4/2.pbf"));
+    assert!(cache.exists(path));
+
+    let mut s = String::new();
+    assert!(cache.read(path, |f| {
+        let _ = f.read_to_string(&mut s);
+    }));
+    assert_eq!(&s, "0123456789");
+
+    cache.write("tileset/3/4/3.pbf", b"0123456789").unwrap();
+    assert_eq!(
+        cache.exists_batch(&[path.to_string(), "tileset/3/4/4.pbf".to_string()]),
+        vec![true, false]
+    );
+    assert_eq!(cache.purge("tileset/"), 2);
+    assert!(!cache.exists(path));
+}
+
+#[test]
+fn test_azure_connection_string() {
+    let key = base64::encode(b"secret");
+    let connection_string = format!(
+        "DefaultEndpointsProtocol=https;AccountName=account;AccountKey={};EndpointSuffix=core.windows.net",
+        key
+    );
+    assert!(AzureBlobContainer::from_connection_string("tiles", &connection_string).is_ok());
+    assert_eq!(
+        AzureBlobContainer::from_connection_string("tiles", "AccountName=account").err(),
+        Some("Missing AccountKey in connection string".to_string())
+    );
+    assert!(
+        AzureBlobContainer::from_connection_string("tiles", "AccountName=a;AccountKey=%%").is_err()
+    );
+}
+
+#[test]
+fn test_azure_string_to_sign() {
+    let headers = vec![
+        ("x-ms-version".to_string(), "2020-04-08".to_string()),
+        ("Content-Type".to_string(), "application/json".to_string()),
+        (
+            "x-ms-date".to_string(),
+            "Fri, 26 Jun 2015 23:39:12 GMT".to_string(),
+        ),
+        ("x-ms-blob-type".to_string(), "BlockBlob".to_string()),
+    ];
+    assert_eq!(
+        string_to_sign("PUT", "account", "/tiles/a/b.json", &[], &headers, 2),
+        "PUT\n\n\n2\n\napplication/json\n\n\n\n\n\n\n\
+         x-ms-blob-type:BlockBlob\n\
+         x-ms-date:Fri, 26 Jun 2015 23:39:12 GMT\n\
+         x-ms-version:2020-04-08\n\
+         /account/tiles/a/b.json"
+    );
+    let query = [("restype", "container"), ("comp", "list"), ("prefix", "a/")];
+    assert_eq!(
+        string_to_sign("GET", "account", "/tiles", &query, &[], 0),
+        "GET\n\n\n\n\n\n\n\n\n\n\n\n/account/tiles\ncomp:list\nprefix:a/\nrestype:container"
+    );
+}
+
+#[test]
+fn test_azure_list_response() {
+    let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<EnumerationResults ServiceEndpoint="https://account.blob.core.windows.net/" ContainerName="tiles">
+  <Prefix>tileset/</Prefix>
+  <Blobs>
+    <Blob><Name>tileset/0/0/0.pbf</Name><Properties /></Blob>
+    <Blob><Name>tileset/a&amp;b.json</Name><Properties /></Blob>
+  </Blobs>
+  <NextMarker />
+</EnumerationResults>"#;
+    assert_eq!(
+        xml_elements(xml, "Name"),
+        vec!["tileset/0/0/0.pbf", "tileset/a&b.json"]
+    );
+    assert!(xml_elements(xml, "NextMarker").is_empty());
+}
@@ -0,0 +1,121 @@
+//
+// Copyright (c) Pirmin Kalberer. All rights reserved.
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+//
+
+use crate::cache::cache::Cache;
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Cache buffering written objects in memory and writing them in batches
+#[derive(Clone)]
+pub struct BufferedCache<C> {
+    pub backing: C,
+    /// Number of buffered objects triggering a flush
+    pub flush_interval: usize,
+    buffer: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+}
+
+impl<C: Cache> BufferedCache<C> {
+    pub fn new(backing: C, flush_interval: usize) -> BufferedCache<C> {
+        BufferedCache {
+            backing,
+            flush_interval,
+            buffer: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+    /// Number of objects not written yet
+    pub fn buffered(&self) -> usize {
+        self.buffer.lock().unwrap().len()
+    }
+    fn write_buffer(&self, buffer: &mut HashMap<String, Vec<u8>>) -> Result<(), io::Error> {
+        debug!("BufferedCache.flush {} objects", buffer.len());
+        let mut result = Ok(());
+        for (path, obj) in buffer.drain() {
+            if let Err(e) = self.backing.write(&path, &obj) {
+                error!("Error writing {}: {}", path, e);
+                result = Err(e);
+            }
+        }
+        result
+    }
+}
+
+impl<C: Cache> Cache for BufferedCache<C> {
+    fn info(&self) -> String {
+        format!(
+            "{} (write buffer: {} tiles)",
+            self.backing.info(),
+            self.flush_interval
+        )
+    }
+    fn baseurl(&self) -> String {
+        self.backing.baseurl()
+    }
+    fn tile_compression(&self) -> &'static str {
+        self.backing.tile_compression()
+    }
+    fn read<F>(&self, path: &str, mut read: F) -> bool
+    where
+        F: FnMut(&mut dyn Read),
+    {
+        let buffered = self.buffer.lock().unwrap().get(path).cloned();
+        match buffered {
+            Some(data) => {
+                read(&mut &data[..]);
+                true
+            }
+            None => self.backing.read(path, read),
+        }
+    }
+    fn write(&self, path: &str, obj: &[u8]) -> Result<(), io::Error> {
+        // Keep buffer locked while flushing, so that readers never miss an object
+        let mut buffer = self.buffer.lock().unwrap();
+        buffer.insert(path.to_string(), obj.to_vec());
+        if buffer.len() >= self.flush_interval {
+            self.write_buffer(&mut buffer)
+        } else {
+            Ok(())
+        }
+    }
+    fn exists(&self, path: &str) -> bool {
+        self.buffer.lock().unwrap().contains_key(path) || self.backing.exists(path)
+    }
+    fn exists_batch(&self, paths: &[String]) -> Vec<bool> {
+        let buffer = self.buffer.lock().unwrap();
+        self.backing
+            .exists_batch(paths)
+            .into_iter()
+            .zip(paths)
+            .map(|(exists, path)| exists || buffer.contains_key(path))
+            .collect()
+    }
+    fn remove(&self, path: &str) -> bool {
+        let buffered = self.buffer.lock().unwrap().remove(path).is_some();
+        self.backing.remove(path) || buffered
+    }
+    fn modified(&self, path: &str) -> Option<SystemTime> {
+        self.backing.modified(path)
+    }
+    fn list(&self, prefix: &str) -> Vec<String> {
+        let mut paths = self.backing.list(prefix);
+        paths.extend(
+            self.buffer
+                .lock()
+                .unwrap()
+                .keys()
+                .filter(|path| path.starts_with(prefix))
+                .cloned(),
+        );
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+    fn flush(&self) -> Result<(), io::Error> {
+        let mut buffer = self.buffer.lock().unwrap();
+        self.write_buffer(&mut buffer)?;
+        self.backing.flush()
+    }
+}
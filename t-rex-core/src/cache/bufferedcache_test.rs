@@ -0,0 +1,66 @@
+//
+// Copyright (c) Pirmin Kalberer. All rights reserved.
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+//
+
+use crate::cache::bufferedcache::BufferedCache;
+use crate::cache::cache::Cache;
+use crate::cache::filecache::Filecache;
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn test_bufferedcache() {
+    use std::env;
+
+    let mut dir = env::temp_dir();
+    dir.push("t_rex_test_buffered");
+    let basepath = format!("{}", &dir.display());
+    let _ = fs::remove_dir_all(&basepath);
+
+    let cache = BufferedCache::new(
+        Filecache {
+            basepath: basepath.clone(),
+            baseurl: None,
+            shard: false,
+        },
+        3,
+    );
+    let fullpath = |path: &str| format!("{}/{}", basepath, path);
+
+    let _ = cache.write("tileset/0/0/0.pbf", "0000".as_bytes());
+    let _ = cache.write("tileset/1/0/0.pbf", "1000".as_bytes());
+    assert_eq!(cache.buffered(), 2);
+    assert!(!Path::new(&fullpath("tileset/0/0/0.pbf")).exists());
+
+    // Buffered objects are visible
+    assert!(cache.exists("tileset/1/0/0.pbf"));
+    let mut s = String::new();
+    assert!(cache.read("tileset/1/0/0.pbf", |f| {
+        let _ = f.read_to_string(&mut s);
+    }));
+    assert_eq!(&s, "1000");
+
+    // Flush at configured interval
+    let _ = cache.write("tileset/1/0/1.pbf", "1010".as_bytes());
+    assert_eq!(cache.buffered(), 0);
+    for path in &[
+        "tileset/0/0/0.pbf",
+        "tileset/1/0/0.pbf",
+        "tileset/1/0/1.pbf",
+    ] {
+        assert!(Path::new(&fullpath(path)).exists());
+    }
+    let mut s = String::new();
+    assert!(cache.read("tileset/1/0/1.pbf", |f| {
+        let _ = f.read_to_string(&mut s);
+    }));
+    assert_eq!(&s, "1010");
+
+    // Explicit flush
+    let _ = cache.write("tileset/1/1/0.pbf", "1100".as_bytes());
+    assert_eq!(cache.buffered(), 1);
+    assert!(cache.flush().is_ok());
+    assert_eq!(cache.buffered(), 0);
+    assert!(Path::new(&fullpath("tileset/1/1/0.pbf")).exists());
+}
@@ -5,6 +5,7 @@
 
 use std::io;
 use std::io::Read;
+use std::time::SystemTime;
 
 pub trait Cache {
     fn info(&self) -> String;
@@ -15,7 +16,35 @@ pub trait Cache {
         F: FnMut(&mut dyn Read);
     fn write(&self, path: &str, obj: &[u8]) -> Result<(), io::Error>;
     fn exists(&self, path: &str) -> bool;
+    /// Existence of multiple objects. Caches can override this with a cheaper lookup.
+    fn exists_batch(&self, paths: &[String]) -> Vec<bool> {
+        paths.iter().map(|path| self.exists(path)).collect()
+    }
     fn remove(&self, path: &str) -> bool;
+    /// Modification time of a cached object, if supported by the cache
+    fn modified(&self, _path: &str) -> Option<SystemTime> {
+        None
+    }
+    /// Paths of cached objects starting with `prefix`, if supported by the cache
+    fn list(&self, _prefix: &str) -> Vec<String> {
+        Vec::new()
+    }
+    /// Remove all cached objects starting with `prefix`. Returns number of removed objects.
+    fn purge(&self, prefix: &str) -> usize {
+        self.list(prefix)
+            .iter()
+            .filter(|path| self.remove(path))
+            .count()
+    }
+    /// Write buffered objects
+    fn flush(&self) -> Result<(), io::Error> {
+        Ok(())
+    }
+    /// Compression of tiles delivered from cache storage ("gzip" or "none").
+    /// Tiles are stored gzip compressed.
+    fn tile_compression(&self) -> &'static str {
+        "gzip"
+    }
 }
 
 #[derive(Clone)]
@@ -4,14 +4,96 @@
 //
 
 use crate::cache::cache::Cache;
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
 use std::fs::{self, File};
 use std::io::{self, Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
 
 #[derive(Clone)]
 pub struct Filecache {
     pub basepath: String,
     pub baseurl: Option<String>,
+    /// Insert a hash prefix directory into tile paths (e.g. `3f/tileset/0/1/2.pbf`).
+    /// Metadata files like `tileset/metadata.json` are not sharded.
+    pub shard: bool,
+}
+
+impl Filecache {
+    /// File system path of cache object
+    pub fn fullpath(&self, path: &str) -> String {
+        if self.shard && is_tile_path(path) {
+            format!("{}/{:02x}/{}", self.basepath, shard_hash(path), path)
+        } else {
+            format!("{}/{}", self.basepath, path)
+        }
+    }
+}
+
+/// Counter for unique temporary file names
+static TMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Write into a temporary file, which is renamed to `fullpath` when complete.
+/// Interrupted or failed writes never leave a partial file at `fullpath`.
+pub(crate) fn write_atomic<F>(fullpath: &str, write: F) -> Result<(), io::Error>
+where
+    F: FnOnce(&mut File) -> Result<(), io::Error>,
+{
+    let p = Path::new(fullpath);
+    fs::create_dir_all(p.parent().unwrap())?;
+    let tmppath = format!(
+        "{}.{}-{}.tmp",
+        fullpath,
+        process::id(),
+        TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+    let result = File::create(&tmppath)
+        .and_then(|mut f| write(&mut f))
+        .and_then(|_| fs::rename(&tmppath, fullpath));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmppath);
+    }
+    result
+}
+
+/// Collect all files below `dir`
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if path.is_dir() {
+                collect_files(&path, files);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+}
+
+/// Tiles are sharded, other cache objects are stored at their path
+fn is_tile_path(path: &str) -> bool {
+    path.ends_with(".pbf")
+}
+
+/// Temporary file left by an interrupted `write_atomic`
+fn is_tmp_path(path: &str) -> bool {
+    path.ends_with(".tmp")
+}
+
+/// Hash prefix directory name of sharded tiles
+fn is_shard_dir(name: &str) -> bool {
+    name.len() == 2 && name.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Stable 8-bit hash of a path (FNV-1a folded)
+fn shard_hash(path: &str) -> u8 {
+    let hash = path.bytes().fold(0x811c9dc5u32, |hash, b| {
+        (hash ^ b as u32).wrapping_mul(0x01000193)
+    });
+    (hash ^ (hash >> 8) ^ (hash >> 16) ^ (hash >> 24)) as u8
 }
 
 impl Cache for Filecache {
@@ -27,7 +109,7 @@ impl Cache for Filecache {
     where
         F: FnMut(&mut dyn Read),
     {
-        let fullpath = format!("{}/{}", self.basepath, path);
+        let fullpath = self.fullpath(path);
         debug!("Filecache.read {}", fullpath);
         match File::open(&fullpath) {
             Ok(mut f) => {
@@ -38,24 +120,105 @@ impl Cache for Filecache {
         }
     }
     fn write(&self, path: &str, obj: &[u8]) -> Result<(), io::Error> {
-        let fullpath = format!("{}/{}", self.basepath, path);
+        let fullpath = self.fullpath(path);
         debug!("Filecache.write {}", fullpath);
-        let p = Path::new(&fullpath);
-        fs::create_dir_all(p.parent().unwrap())?;
-        let mut f = File::create(&fullpath)?;
-        f.write_all(obj)
+        write_atomic(&fullpath, |f| f.write_all(obj))
     }
 
     fn exists(&self, path: &str) -> bool {
-        let fullpath = format!("{}/{}", self.basepath, path);
+        let fullpath = self.fullpath(path);
         Path::new(&fullpath).exists()
     }
 
+    fn exists_batch(&self, paths: &[String]) -> Vec<bool> {
+        // Read each directory listing only once
+        let mut dirs: HashMap<PathBuf, HashSet<OsString>> = HashMap::new();
+        paths
+            .iter()
+            .map(|path| {
+                let fullpath = PathBuf::from(self.fullpath(path));
+                let (dir, name) = match (fullpath.parent(), fullpath.file_name()) {
+                    (Some(dir), Some(name)) => (dir, name),
+                    _ => return false,
+                };
+                dirs.entry(dir.to_path_buf())
+                    .or_insert_with(|| match fs::read_dir(dir) {
+                        Ok(entries) => entries
+                            .filter_map(|e| e.ok())
+                            .map(|e| e.file_name())
+                            .collect(),
+                        Err(_) => HashSet::new(),
+                    })
+                    .contains(name)
+            })
+            .collect()
+    }
+
     fn remove(&self, path: &str) -> bool {
-        let fullpath = format!("{}/{}", self.basepath, path);
+        let fullpath = self.fullpath(path);
         match fs::remove_file(fullpath) {
             Ok(_) => true,
             Err(_) => false,
         }
     }
+
+    fn modified(&self, path: &str) -> Option<SystemTime> {
+        let fullpath = self.fullpath(path);
+        fs::metadata(fullpath).and_then(|m| m.modified()).ok()
+    }
+
+    fn list(&self, prefix: &str) -> Vec<String> {
+        // Roots with a flag for the kind of files stored there (None: all files)
+        let mut roots = vec![(PathBuf::from(&self.basepath), None)];
+        if self.shard {
+            roots[0].1 = Some(false);
+            if let Ok(entries) = fs::read_dir(&self.basepath) {
+                roots.extend(
+                    entries
+                        .filter_map(|e| e.ok())
+                        .filter(|e| is_shard_dir(&e.file_name().to_string_lossy()))
+                        .map(|e| e.path())
+                        .filter(|p| p.is_dir())
+                        .map(|p| (p, Some(true))),
+                );
+            }
+        }
+        // Only walk the directory containing the prefix
+        let prefixdir = match prefix.rfind('/') {
+            Some(pos) => &prefix[..pos],
+            None => "",
+        };
+        let mut paths = Vec::new();
+        for (root, tiles) in roots {
+            let mut files = Vec::new();
+            collect_files(&root.join(prefixdir), &mut files);
+            for file in files {
+                if let Ok(relpath) = file.strip_prefix(&root) {
+                    let path = relpath.to_string_lossy().replace('\\', "/");
+                    let stored_here = match tiles {
+                        Some(tiles) => is_tile_path(&path) == tiles,
+                        None => true,
+                    };
+                    if stored_here && !is_tmp_path(&path) && path.starts_with(prefix) {
+                        paths.push(path);
+                    }
+                }
+            }
+        }
+        paths.sort();
+        paths
+    }
+
+    fn purge(&self, prefix: &str) -> usize {
+        let paths = self.list(prefix);
+        if !self.shard && prefix.ends_with('/') {
+            // Remove whole directory including empty subdirectories
+            match fs::remove_dir_all(self.fullpath(prefix)) {
+                Ok(_) => paths.len(),
+                Err(_) => 0,
+            }
+        } else {
+            paths.iter().filter(|path| self.remove(path)).count()
+        }
+    }
 }
@@ -20,17 +20,23 @@ fn test_dircache() {
     let cache = Filecache {
         basepath: basepath,
         baseurl: Some("http://localhost:6767".to_string()),
+        shard: false,
     };
     let path = "tileset/0/1/2.pbf";
     let fullpath = format!("{}/{}", cache.basepath, path);
     let obj = "0123456789";
 
+    // Tiles are stored gzip compressed
+    assert_eq!(cache.tile_compression(), "gzip");
+
     // Cache miss
     assert_eq!(cache.read(path, |_| {}), false);
 
     // Write into cache
     let _ = cache.write(path, obj.as_bytes());
     assert!(Path::new(&fullpath).exists());
+    assert!(cache.modified(path).is_some());
+    assert!(cache.modified("tileset/0/0/0.pbf").is_none());
 
     // Cache hit
     assert_eq!(cache.read(path, |_| {}), true);
@@ -42,3 +48,234 @@ fn test_dircache() {
     });
     assert_eq!(&s, "0123456789");
 }
+
+#[test]
+fn test_sharded_dircache() {
+    use std::env;
+
+    let mut dir = env::temp_dir();
+    dir.push("t_rex_test_sharded");
+    let basepath = format!("{}", &dir.display());
+    let _ = fs::remove_dir_all(&basepath);
+
+    let cache = Filecache {
+        basepath: basepath.clone(),
+        baseurl: None,
+        shard: true,
+    };
+    let path = "tileset/0/1/2.pbf";
+    let flatpath = format!("{}/{}", basepath, path);
+    let fullpath = cache.fullpath(path);
+    assert_ne!(fullpath, flatpath);
+    assert!(fullpath.ends_with(path));
+    // Hash prefix is stable
+    assert_eq!(fullpath, cache.fullpath(path));
+
+    let _ = cache.write(path, b"0123456789");
+    assert!(Path::new(&fullpath).exists());
+    assert!(!Path::new(&flatpath).exists());
+    assert!(cache.exists(path));
+
+    let mut s = String::new();
+    assert!(cache.read(path, |f| {
+        let _ = f.read_to_string(&mut s);
+    }));
+    assert_eq!(&s, "0123456789");
+
+    assert!(cache.remove(path));
+    assert!(!cache.exists(path));
+
+    // Metadata is stored at its path
+    let _ = cache.write("tileset/metadata.json", b"{}");
+    assert!(Path::new(&format!("{}/tileset/metadata.json", basepath)).exists());
+    let _ = cache.write(path, b"0123456789");
+    assert_eq!(
+        cache.list("tileset/"),
+        vec!["tileset/0/1/2.pbf", "tileset/metadata.json"]
+    );
+}
+
+#[test]
+fn test_shard_with_baseurl() {
+    use crate::cache::Tilecache;
+    use crate::core::{parse_config, ApplicationCfg, Config};
+
+    let toml = r#"
+        datasource = []
+        tileset = []
+        [service.mvt]
+        viewer = false
+        [grid]
+        predefined = "web_mercator"
+        [cache.file]
+        base = "/tmp/t_rex_test_shard_baseurl"
+        baseurl = "http://example.com/tiles"
+        shard = true
+        [webserver]
+        port = 6767
+        "#;
+    let config: ApplicationCfg = parse_config(toml.to_string(), "").unwrap();
+    assert_eq!(
+        Tilecache::from_config(&config).err(),
+        Some("Cache option 'shard' is not supported with 'baseurl'".to_string())
+    );
+}
+
+#[test]
+fn test_list_dircache() {
+    use std::env;
+
+    for shard in &[false, true] {
+        let mut dir = env::temp_dir();
+        dir.push(format!("t_rex_test_list_{}", shard));
+        let basepath = format!("{}", &dir.display());
+        let _ = fs::remove_dir_all(&basepath);
+
+        let cache = Filecache {
+            basepath: basepath,
+            baseurl: None,
+            shard: *shard,
+        };
+        for path in &[
+            "tileset/0/0/0.pbf",
+            "tileset/1/0/1.pbf",
+            "tileset/1/1/1.pbf",
+            "other/0/0/0.pbf",
+        ] {
+            let _ = cache.write(path, b"0123456789");
+        }
+        // Leftover of an interrupted atomic write
+        let _ = cache.write("tileset/1/0/1.pbf.1234-0.tmp", b"01234");
+
+        assert_eq!(
+            cache.list("tileset/"),
+            vec![
+                "tileset/0/0/0.pbf",
+                "tileset/1/0/1.pbf",
+                "tileset/1/1/1.pbf"
+            ]
+        );
+        assert_eq!(
+            cache.list("tileset/1/"),
+            vec!["tileset/1/0/1.pbf", "tileset/1/1/1.pbf"]
+        );
+        assert_eq!(cache.list("").len(), 4);
+        assert!(cache.list("missing/").is_empty());
+    }
+}
+
+#[test]
+fn test_exists_batch() {
+    use std::env;
+
+    for shard in &[false, true] {
+        let mut dir = env::temp_dir();
+        dir.push(format!("t_rex_test_exists_batch_{}", shard));
+        let basepath = format!("{}", &dir.display());
+        let _ = fs::remove_dir_all(&basepath);
+
+        let cache = Filecache {
+            basepath: basepath,
+            baseurl: None,
+            shard: *shard,
+        };
+        let mut paths = Vec::new();
+        for x in 0..3 {
+            for y in 0..4 {
+                let path = format!("tileset/2/{}/{}.pbf", x, y);
+                if (x + y) % 2 == 0 {
+                    let _ = cache.write(&path, b"0123456789");
+                }
+                paths.push(path);
+            }
+        }
+        paths.push("tileset/3/0/0.pbf".to_string());
+        paths.push("missing.pbf".to_string());
+
+        let expected: Vec<bool> = paths.iter().map(|path| cache.exists(path)).collect();
+        assert_eq!(expected.iter().filter(|e| **e).count(), 6);
+        assert_eq!(cache.exists_batch(&paths), expected);
+    }
+}
+
+#[test]
+fn test_atomic_write() {
+    use crate::cache::filecache::write_atomic;
+    use std::env;
+    use std::io::{self, Write};
+
+    let mut dir = env::temp_dir();
+    dir.push("t_rex_test_atomic_write");
+    let basepath = format!("{}", &dir.display());
+    let _ = fs::remove_dir_all(&basepath);
+    let cache = Filecache {
+        basepath: basepath.clone(),
+        baseurl: None,
+        shard: false,
+    };
+    let fullpath = cache.fullpath("tileset/0/0/0.pbf");
+
+    // Write failing after the first bytes leaves no file
+    let result = write_atomic(&fullpath, |f| {
+        f.write_all(b"0123")?;
+        Err(io::Error::new(io::ErrorKind::Interrupted, "interrupted"))
+    });
+    assert!(result.is_err());
+    assert!(!Path::new(&fullpath).exists());
+    assert_eq!(
+        fs::read_dir(format!("{}/tileset/0/0", basepath))
+            .unwrap()
+            .count(),
+        0
+    );
+
+    // Failed overwrite keeps the previous content
+    cache.write("tileset/0/0/0.pbf", b"0123456789").unwrap();
+    let result = write_atomic(&fullpath, |f| {
+        f.write_all(b"abc")?;
+        Err(io::Error::new(io::ErrorKind::Interrupted, "interrupted"))
+    });
+    assert!(result.is_err());
+    assert_eq!(fs::read(&fullpath).unwrap(), b"0123456789");
+    assert_eq!(cache.list("tileset/"), vec!["tileset/0/0/0.pbf"]);
+}
+
+#[test]
+fn test_concurrent_writes() {
+    use std::env;
+    use std::thread;
+
+    let mut dir = env::temp_dir();
+    dir.push("t_rex_test_concurrent_writes");
+    let basepath = format!("{}", &dir.display());
+    let _ = fs::remove_dir_all(&basepath);
+    let cache = Filecache {
+        basepath: basepath.clone(),
+        baseurl: None,
+        shard: false,
+    };
+    let path = "tileset/0/0/0.pbf";
+
+    // Readers only ever see a complete tile
+    let objects: Vec<Vec<u8>> = (0..8u8).map(|i| vec![i; 64 * 1024]).collect();
+    let writers: Vec<_> = objects
+        .iter()
+        .cloned()
+        .map(|obj| {
+            let cache = cache.clone();
+            thread::spawn(move || cache.write(path, &obj).unwrap())
+        })
+        .collect();
+    for _ in 0..20 {
+        if let Ok(data) = fs::read(cache.fullpath(path)) {
+            assert!(objects.contains(&data));
+        }
+    }
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    let data = fs::read(cache.fullpath(path)).unwrap();
+    assert!(objects.contains(&data));
+    // No temporary files left
+    assert_eq!(cache.list("tileset/"), vec![path]);
+}
@@ -0,0 +1,225 @@
+//
+// Copyright (c) Pirmin Kalberer. All rights reserved.
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+//
+
+use crate::cache::objectstore::{http_request, ObjectStore, PutObject, COMPONENT, OBJECT_PATH};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use percent_encoding::utf8_percent_encode;
+use std::env;
+use std::fs;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const STORAGE_URL: &str = "https://storage.googleapis.com";
+const STORAGE_SCOPE: &str = "https://www.googleapis.com/auth/devstorage.read_write";
+
+/// Service account key file
+#[derive(Deserialize)]
+struct ServiceAccountKey {
+    client_email: String,
+    private_key: String,
+    token_uri: String,
+}
+
+/// Google Cloud Storage bucket accessed with service account credentials
+pub struct GcsBucket {
+    bucket_name: String,
+    key: ServiceAccountKey,
+    /// Access token and its expiration time
+    token: Mutex<Option<(String, Instant)>>,
+}
+
+impl GcsBucket {
+    /// Bucket with service account credentials from `GOOGLE_APPLICATION_CREDENTIALS`
+    pub fn from_env(bucket_name: &str) -> Result<GcsBucket, String> {
+        let path = env::var("GOOGLE_APPLICATION_CREDENTIALS")
+            .map_err(|_| "GOOGLE_APPLICATION_CREDENTIALS not set".to_string())?;
+        let json = fs::read_to_string(&path)
+            .map_err(|e| format!("Error reading credentials '{}': {}", path, e))?;
+        let key = serde_json::from_str(&json)
+            .map_err(|e| format!("Error parsing credentials '{}': {}", path, e))?;
+        Ok(GcsBucket {
+            bucket_name: bucket_name.to_string(),
+            key,
+            token: Mutex::new(None),
+        })
+    }
+
+    /// Signed JWT for requesting an access token
+    fn jwt_assertion(&self) -> Result<String, String> {
+        let iat = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| e.to_string())?
+            .as_secs();
+        let header = json!({"alg": "RS256", "typ": "JWT"});
+        let claims = json!({
+            "iss": self.key.client_email,
+            "scope": STORAGE_SCOPE,
+            "aud": self.key.token_uri,
+            "iat": iat,
+            "exp": iat + 3600,
+        });
+        let encode = |data: &[u8]| base64::encode_config(data, base64::URL_SAFE_NO_PAD);
+        let message = format!(
+            "{}.{}",
+            encode(header.to_string().as_bytes()),
+            encode(claims.to_string().as_bytes())
+        );
+        let signature = PKey::private_key_from_pem(self.key.private_key.as_bytes())
+            .and_then(|pkey| {
+                let mut signer = Signer::new(MessageDigest::sha256(), &pkey)?;
+                signer.update(message.as_bytes())?;
+                signer.sign_to_vec()
+            })
+            .map_err(|e| format!("Error signing token request: {}", e))?;
+        Ok(format!("{}.{}", message, encode(&signature)))
+    }
+
+    /// Access token, renewed before expiration
+    fn access_token(&self) -> Result<String, String> {
+        let mut token = self.token.lock().unwrap();
+        if let Some((ref access_token, expires)) = *token {
+            if Instant::now() < expires {
+                return Ok(access_token.clone());
+            }
+        }
+        let body = format!(
+            "grant_type={}&assertion={}",
+            utf8_percent_encode("urn:ietf:params:oauth:grant-type:jwt-bearer", COMPONENT),
+            self.jwt_assertion()?
+        );
+        let headers = vec!["Content-Type: application/x-www-form-urlencoded".to_string()];
+        let (status, data) =
+            http_request("POST", &self.key.token_uri, &headers, Some(body.as_bytes()))?;
+        if status != 200 {
+            return Err(format!("Token request failed with status {}", status));
+        }
+        let response: serde_json::Value =
+            serde_json::from_slice(&data).map_err(|e| e.to_string())?;
+        let access_token = response["access_token"]
+            .as_str()
+            .ok_or("Missing access_token in token response".to_string())?
+            .to_string();
+        let expires_in = response["expires_in"].as_u64().unwrap_or(3600);
+        let expires = Instant::now() + Duration::from_secs(expires_in.saturating_sub(60));
+        *token = Some((access_token.clone(), expires));
+        Ok(access_token)
+    }
+
+    fn object_url(&self, key: &str) -> String {
+        format!(
+            "{}/{}/{}",
+            STORAGE_URL,
+            self.bucket_name,
+            utf8_percent_encode(key, OBJECT_PATH)
+        )
+    }
+
+    /// Authorized request to storage API
+    fn request(
+        &self,
+        method: &str,
+        url: &str,
+        mut headers: Vec<String>,
+        body: Option<&[u8]>,
+    ) -> Result<(u32, Vec<u8>), String> {
+        headers.push(format!("Authorization: Bearer {}", self.access_token()?));
+        http_request(method, url, &headers, body)
+    }
+}
+
+impl ObjectStore for GcsBucket {
+    fn get(&self, key: &str) -> Option<Vec<u8>> {
+        // Return stored content without decompressive transcoding
+        let headers = vec!["Accept-Encoding: gzip".to_string()];
+        match self.request("GET", &self.object_url(key), headers, None) {
+            Ok((200, data)) => Some(data),
+            Ok(_) => None,
+            Err(e) => {
+                error!("{}", e);
+                None
+            }
+        }
+    }
+
+    fn put(&self, object: PutObject) -> Result<(), String> {
+        let mut headers = vec![format!("Content-Type: {}", object.content_type)];
+        if let Some(content_encoding) = object.content_encoding {
+            headers.push(format!("Content-Encoding: {}", content_encoding));
+        }
+        if let Some(cache_control) = object.cache_control {
+            headers.push(format!("Cache-Control: {}", cache_control));
+        }
+        let url = self.object_url(&object.key);
+        match self.request("PUT", &url, headers, Some(&object.body))? {
+            (200, _) => Ok(()),
+            (status, data) => Err(format!(
+                "Upload of {} failed with status {}: {}",
+                object.key,
+                status,
+                String::from_utf8_lossy(&data)
+            )),
+        }
+    }
+
+    fn head(&self, key: &str) -> bool {
+        match self.request("HEAD", &self.object_url(key), Vec::new(), None) {
+            Ok((status, _)) => status == 200,
+            Err(e) => {
+                error!("{}", e);
+                false
+            }
+        }
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>, String> {
+        let mut keys = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut url = format!(
+                "{}/storage/v1/b/{}/o?prefix={}&fields=items(name),nextPageToken",
+                STORAGE_URL,
+                self.bucket_name,
+                utf8_percent_encode(prefix, COMPONENT)
+            );
+            if let Some(ref token) = page_token {
+                url.push_str(&format!(
+                    "&pageToken={}",
+                    utf8_percent_encode(token, COMPONENT)
+                ));
+            }
+            let (status, data) = self.request("GET", &url, Vec::new(), None)?;
+            if status != 200 {
+                return Err(format!("Listing failed with status {}", status));
+            }
+            let response: serde_json::Value =
+                serde_json::from_slice(&data).map_err(|e| e.to_string())?;
+            if let Some(items) = response["items"].as_array() {
+                keys.extend(
+                    items
+                        .iter()
+                        .filter_map(|item| item["name"].as_str())
+                        .map(|name| name.to_string()),
+                );
+            }
+            page_token = response["nextPageToken"].as_str().map(|t| t.to_string());
+            if page_token.is_none() {
+                break;
+            }
+        }
+        Ok(keys)
+    }
+
+    fn delete(&self, key: &str) -> bool {
+        match self.request("DELETE", &self.object_url(key), Vec::new(), None) {
+            Ok((status, _)) => status == 204 || status == 200,
+            Err(e) => {
+                error!("{}", e);
+                false
+            }
+        }
+    }
+}
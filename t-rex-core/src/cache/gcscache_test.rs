@@ -0,0 +1,74 @@
+//
+// Copyright (c) Pirmin Kalberer. All rights reserved.
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+//
+
+use crate::cache::cache::Cache;
+use crate::cache::objectstore::{ObjectCacheSettings, ObjectStoreCache};
+use crate::cache::s3cache_test::MemoryStore;
+use crate::core::{parse_config, ApplicationCfg};
+use std::sync::Arc;
+
+#[test]
+fn test_gcscache_fake_store() {
+    let store = Arc::new(MemoryStore::default());
+    let settings = ObjectCacheSettings {
+        key_prefix: Some("my-prefix".to_string()),
+        cache_control: Some("max-age=3600".to_string()),
+        ..Default::default()
+    };
+    let cache = ObjectStoreCache::new("gcs: trex", store.clone(), settings);
+    let path = "tileset/0/1/2.pbf";
+    assert_eq!(cache.info(), "Tile cache gcs: trex");
+    assert_eq!(cache.tile_compression(), "none");
+
+    // Cache miss
+    assert_eq!(cache.read(path, |_| {}), false);
+    assert!(!cache.exists(path));
+
+    // Write gzip compressed tile into cache
+    let tilegz = [0x1f, 0x8b, 0x08, 0x00];
+    cache.write(path, &tilegz).unwrap();
+    assert!(cache.exists(path));
+    {
+        let objects = store.objects.lock().unwrap();
+        let object = &objects["my-prefix/tileset/0/1/2.pbf"];
+        assert_eq!(object.content_type, "application/vnd.mapbox-vector-tile");
+        assert_eq!(object.content_encoding, Some("gzip".to_string()));
+        assert_eq!(object.cache_control, Some("max-age=3600".to_string()));
+    }
+
+    // Read from cache
+    let mut data = Vec::new();
+    assert!(cache.read(path, |f| {
+        let _ = f.read_to_end(&mut data);
+    }));
+    assert_eq!(data, tilegz);
+
+    assert_eq!(cache.list("tileset/"), vec![path]);
+    assert!(cache.remove(path));
+    assert!(!cache.exists(path));
+}
+
+#[test]
+fn test_gcscache_config() {
+    let toml = r#"
+        datasource = []
+        tileset = []
+        [service.mvt]
+        viewer = false
+        [grid]
+        predefined = "web_mercator"
+        [cache.gcs]
+        bucket = "trex"
+        key_prefix = "tiles"
+        gzip_header_enabled = false
+        [webserver]
+        port = 6767
+        "#;
+    let config: ApplicationCfg = parse_config(toml.to_string(), "").unwrap();
+    let gcs = config.cache.unwrap().gcs.unwrap();
+    assert_eq!(gcs.bucket, "trex");
+    assert_eq!(gcs.key_prefix, Some("tiles".to_string()));
+    assert_eq!(gcs.gzip_header_enabled, Some(false));
+}
@@ -0,0 +1,206 @@
+//
+// Copyright (c) Pirmin Kalberer. All rights reserved.
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+//
+
+use crate::cache::cache::Cache;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Read};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Entries of the LRU cache with their last access tick
+struct LruState {
+    entries: HashMap<String, (Vec<u8>, u64)>,
+    /// Paths ordered by last access
+    usage: BTreeMap<u64, String>,
+    tick: u64,
+    size: usize,
+}
+
+impl LruState {
+    fn touch(&mut self, path: &str) -> Option<&Vec<u8>> {
+        self.tick += 1;
+        let tick = self.tick;
+        match self.entries.get_mut(path) {
+            Some(entry) => {
+                self.usage.remove(&entry.1);
+                self.usage.insert(tick, path.to_string());
+                entry.1 = tick;
+                Some(&entry.0)
+            }
+            None => None,
+        }
+    }
+    fn remove(&mut self, path: &str) -> bool {
+        match self.entries.remove(path) {
+            Some((data, tick)) => {
+                self.usage.remove(&tick);
+                self.size -= data.len();
+                true
+            }
+            None => false,
+        }
+    }
+    fn insert(&mut self, path: &str, obj: &[u8], max_bytes: usize) {
+        self.remove(path);
+        if obj.len() > max_bytes {
+            return;
+        }
+        // Evict least recently used entries
+        while self.size + obj.len() > max_bytes {
+            let oldest = self.usage.keys().next().cloned();
+            match oldest.and_then(|tick| self.usage.remove(&tick)) {
+                Some(oldest_path) => {
+                    if let Some((data, _)) = self.entries.remove(&oldest_path) {
+                        self.size -= data.len();
+                    }
+                }
+                None => break,
+            }
+        }
+        self.tick += 1;
+        self.usage.insert(self.tick, path.to_string());
+        self.entries
+            .insert(path.to_string(), (obj.to_vec(), self.tick));
+        self.size += obj.len();
+    }
+}
+
+/// In-memory tile cache with LRU eviction
+#[derive(Clone)]
+pub struct MemoryCache {
+    /// Maximal total size of cached objects
+    pub max_bytes: usize,
+    state: Arc<Mutex<LruState>>,
+}
+
+impl MemoryCache {
+    pub fn new(max_bytes: usize) -> MemoryCache {
+        MemoryCache {
+            max_bytes,
+            state: Arc::new(Mutex::new(LruState {
+                entries: HashMap::new(),
+                usage: BTreeMap::new(),
+                tick: 0,
+                size: 0,
+            })),
+        }
+    }
+    /// Total size of cached objects
+    pub fn size(&self) -> usize {
+        self.state.lock().unwrap().size
+    }
+}
+
+impl Cache for MemoryCache {
+    fn info(&self) -> String {
+        format!("Memory cache: {} bytes", self.max_bytes)
+    }
+    fn baseurl(&self) -> String {
+        "http://localhost:6767".to_string()
+    }
+    fn read<F>(&self, path: &str, mut read: F) -> bool
+    where
+        F: FnMut(&mut dyn Read),
+    {
+        let mut state = self.state.lock().unwrap();
+        match state.touch(path) {
+            Some(data) => {
+                debug!("MemoryCache.read {}", path);
+                read(&mut &data[..]);
+                true
+            }
+            None => false,
+        }
+    }
+    fn write(&self, path: &str, obj: &[u8]) -> Result<(), io::Error> {
+        let mut state = self.state.lock().unwrap();
+        state.insert(path, obj, self.max_bytes);
+        Ok(())
+    }
+    fn exists(&self, path: &str) -> bool {
+        self.state.lock().unwrap().entries.contains_key(path)
+    }
+    fn remove(&self, path: &str) -> bool {
+        self.state.lock().unwrap().remove(path)
+    }
+}
+
+/// Memory cache in front of a backing cache
+#[derive(Clone)]
+pub struct MemoryCached<C> {
+    pub memory: MemoryCache,
+    pub backing: C,
+}
+
+impl<C: Cache> MemoryCached<C> {
+    // Not generic over the reader closure to keep monomorphization of
+    // nested Tilecache reads finite
+    fn read_backing(&self, path: &str) -> Option<Vec<u8>> {
+        let mut data: Option<Vec<u8>> = None;
+        self.backing.read(path, |f| {
+            let mut buf = Vec::new();
+            if f.read_to_end(&mut buf).is_ok() {
+                data = Some(buf);
+            }
+        });
+        data
+    }
+}
+
+impl<C: Cache> Cache for MemoryCached<C> {
+    fn info(&self) -> String {
+        format!("{} ({})", self.backing.info(), self.memory.info())
+    }
+    fn baseurl(&self) -> String {
+        self.backing.baseurl()
+    }
+    fn read<F>(&self, path: &str, mut read: F) -> bool
+    where
+        F: FnMut(&mut dyn Read),
+    {
+        if self.memory.read(path, &mut read) {
+            return true;
+        }
+        match self.read_backing(path) {
+            Some(data) => {
+                let _ = self.memory.write(path, &data);
+                read(&mut &data[..]);
+                true
+            }
+            None => false,
+        }
+    }
+    fn write(&self, path: &str, obj: &[u8]) -> Result<(), io::Error> {
+        self.backing.write(path, obj)?;
+        self.memory.write(path, obj)
+    }
+    fn exists(&self, path: &str) -> bool {
+        self.memory.exists(path) || self.backing.exists(path)
+    }
+    fn exists_batch(&self, paths: &[String]) -> Vec<bool> {
+        self.backing
+            .exists_batch(paths)
+            .into_iter()
+            .zip(paths)
+            .map(|(exists, path)| exists || self.memory.exists(path))
+            .collect()
+    }
+    fn remove(&self, path: &str) -> bool {
+        self.memory.remove(path);
+        self.backing.remove(path)
+    }
+    fn modified(&self, path: &str) -> Option<SystemTime> {
+        self.backing.modified(path)
+    }
+    fn list(&self, prefix: &str) -> Vec<String> {
+        self.backing.list(prefix)
+    }
+    fn flush(&self) -> Result<(), io::Error> {
+        self.backing.flush()
+    }
+    fn tile_compression(&self) -> &'static str {
+        self.backing.tile_compression()
+    }
+}
@@ -0,0 +1,105 @@
+//
+// Copyright (c) Pirmin Kalberer. All rights reserved.
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+//
+
+use crate::cache::cache::Cache;
+use crate::cache::memorycache::{MemoryCache, MemoryCached};
+use std::cell::Cell;
+use std::io::{self, Read};
+
+/// Backing cache counting read accesses
+struct SpyCache {
+    reads: Cell<u32>,
+}
+
+impl Cache for SpyCache {
+    fn info(&self) -> String {
+        "Spy cache".to_string()
+    }
+    fn baseurl(&self) -> String {
+        "http://localhost:6767".to_string()
+    }
+    fn read<F>(&self, _path: &str, mut read: F) -> bool
+    where
+        F: FnMut(&mut dyn Read),
+    {
+        self.reads.set(self.reads.get() + 1);
+        read(&mut "0123456789".as_bytes());
+        true
+    }
+    fn write(&self, _path: &str, _obj: &[u8]) -> Result<(), io::Error> {
+        Ok(())
+    }
+    fn exists(&self, _path: &str) -> bool {
+        true
+    }
+    fn remove(&self, _path: &str) -> bool {
+        false
+    }
+}
+
+#[test]
+fn test_memorycache() {
+    let cache = MemoryCache::new(20);
+    let path = "tileset/0/1/2.pbf";
+
+    // Cache miss
+    assert_eq!(cache.read(path, |_| {}), false);
+
+    let _ = cache.write(path, "0123456789".as_bytes());
+    assert!(cache.exists(path));
+    let mut s = String::new();
+    assert!(cache.read(path, |f| {
+        let _ = f.read_to_string(&mut s);
+    }));
+    assert_eq!(&s, "0123456789");
+    assert_eq!(cache.size(), 10);
+
+    // Objects larger than the cache are not stored
+    let _ = cache.write("tileset/0/0/0.pbf", &[0; 21]);
+    assert!(!cache.exists("tileset/0/0/0.pbf"));
+
+    assert!(cache.remove(path));
+    assert_eq!(cache.size(), 0);
+}
+
+#[test]
+fn test_lru_eviction() {
+    let cache = MemoryCache::new(20);
+    let _ = cache.write("a", &[0; 8]);
+    let _ = cache.write("b", &[0; 8]);
+    // Access "a" to make "b" the least recently used entry
+    assert!(cache.read("a", |_| {}));
+    let _ = cache.write("c", &[0; 8]);
+    assert!(cache.exists("a"));
+    assert!(!cache.exists("b"));
+    assert!(cache.exists("c"));
+    assert_eq!(cache.size(), 16);
+}
+
+#[test]
+fn test_memory_cached() {
+    let cache = MemoryCached {
+        memory: MemoryCache::new(1000),
+        backing: SpyCache {
+            reads: Cell::new(0),
+        },
+    };
+    let path = "tileset/0/1/2.pbf";
+
+    let mut s = String::new();
+    assert!(cache.read(path, |f| {
+        let _ = f.read_to_string(&mut s);
+    }));
+    assert_eq!(&s, "0123456789");
+    assert_eq!(cache.backing.reads.get(), 1);
+
+    // Second read is served from memory
+    let mut s = String::new();
+    assert!(cache.read(path, |f| {
+        let _ = f.read_to_string(&mut s);
+    }));
+    assert_eq!(&s, "0123456789");
+    assert_eq!(cache.backing.reads.get(), 1);
+}
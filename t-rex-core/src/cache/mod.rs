@@ -3,29 +3,55 @@
 // Licensed under the MIT License. See LICENSE file in the project root for full license information.
 //
 
+pub mod azurecache;
+pub mod bufferedcache;
 pub mod cache;
 pub mod filecache;
+pub mod gcscache;
+pub mod memorycache;
+pub mod objectstore;
 pub mod s3cache;
+pub mod tilebitmap;
 
+#[cfg(test)]
+mod azurecache_test;
+#[cfg(test)]
+mod bufferedcache_test;
 #[cfg(test)]
 mod filecache_test;
 #[cfg(test)]
+mod gcscache_test;
+#[cfg(test)]
+mod memorycache_test;
+#[cfg(test)]
 mod s3cache_test;
+#[cfg(test)]
+mod tilebitmap_test;
 
+pub use self::azurecache::AzureBlobContainer;
+pub use self::bufferedcache::BufferedCache;
 pub use self::cache::Cache;
 pub use self::cache::Nocache;
 pub use self::filecache::Filecache;
-pub use self::s3cache::S3Cache;
+pub use self::gcscache::GcsBucket;
+pub use self::memorycache::{MemoryCache, MemoryCached};
+pub use self::objectstore::{ObjectCacheSettings, ObjectStore, ObjectStoreCache, PutObject};
+pub use self::s3cache::S3Bucket;
+pub use self::tilebitmap::TileBitmap;
 use crate::core::ApplicationCfg;
 use crate::core::Config;
 use std::io;
 use std::io::Read;
+use std::sync::Arc;
+use std::time::SystemTime;
 
 #[derive(Clone)]
 pub enum Tilecache {
     Nocache(Nocache),
     Filecache(Filecache),
-    S3Cache(S3Cache),
+    BufferedFilecache(BufferedCache<Filecache>),
+    ObjectStoreCache(ObjectStoreCache),
+    MemoryCached(Box<MemoryCached<Tilecache>>),
 }
 
 impl Cache for Tilecache {
@@ -33,14 +59,18 @@ impl Cache for Tilecache {
         match self {
             &Tilecache::Nocache(ref cache) => cache.info(),
             &Tilecache::Filecache(ref cache) => cache.info(),
-            &Tilecache::S3Cache(ref cache) => cache.info(),
+            &Tilecache::BufferedFilecache(ref cache) => cache.info(),
+            &Tilecache::ObjectStoreCache(ref cache) => cache.info(),
+            &Tilecache::MemoryCached(ref cache) => cache.info(),
         }
     }
     fn baseurl(&self) -> String {
         match self {
             &Tilecache::Nocache(ref cache) => cache.baseurl(),
             &Tilecache::Filecache(ref cache) => cache.baseurl(),
-            &Tilecache::S3Cache(ref cache) => cache.baseurl(),
+            &Tilecache::BufferedFilecache(ref cache) => cache.baseurl(),
+            &Tilecache::ObjectStoreCache(ref cache) => cache.baseurl(),
+            &Tilecache::MemoryCached(ref cache) => cache.baseurl(),
         }
     }
     fn read<F>(&self, path: &str, read: F) -> bool
@@ -50,21 +80,36 @@ impl Cache for Tilecache {
         match self {
             &Tilecache::Nocache(ref cache) => cache.read(path, read),
             &Tilecache::Filecache(ref cache) => cache.read(path, read),
-            &Tilecache::S3Cache(ref cache) => cache.read(path, read),
+            &Tilecache::BufferedFilecache(ref cache) => cache.read(path, read),
+            &Tilecache::ObjectStoreCache(ref cache) => cache.read(path, read),
+            &Tilecache::MemoryCached(ref cache) => cache.read(path, read),
         }
     }
     fn write(&self, path: &str, obj: &[u8]) -> Result<(), io::Error> {
         match self {
             &Tilecache::Nocache(ref cache) => cache.write(path, obj),
             &Tilecache::Filecache(ref cache) => cache.write(path, obj),
-            &Tilecache::S3Cache(ref cache) => cache.write(path, obj),
+            &Tilecache::BufferedFilecache(ref cache) => cache.write(path, obj),
+            &Tilecache::ObjectStoreCache(ref cache) => cache.write(path, obj),
+            &Tilecache::MemoryCached(ref cache) => cache.write(path, obj),
         }
     }
     fn exists(&self, path: &str) -> bool {
         match self {
             &Tilecache::Nocache(ref cache) => cache.exists(path),
             &Tilecache::Filecache(ref cache) => cache.exists(path),
-            &Tilecache::S3Cache(ref cache) => cache.exists(path),
+            &Tilecache::BufferedFilecache(ref cache) => cache.exists(path),
+            &Tilecache::ObjectStoreCache(ref cache) => cache.exists(path),
+            &Tilecache::MemoryCached(ref cache) => cache.exists(path),
+        }
+    }
+    fn exists_batch(&self, paths: &[String]) -> Vec<bool> {
+        match self {
+            &Tilecache::Nocache(ref cache) => cache.exists_batch(paths),
+            &Tilecache::Filecache(ref cache) => cache.exists_batch(paths),
+            &Tilecache::BufferedFilecache(ref cache) => cache.exists_batch(paths),
+            &Tilecache::ObjectStoreCache(ref cache) => cache.exists_batch(paths),
+            &Tilecache::MemoryCached(ref cache) => cache.exists_batch(paths),
         }
     }
 
@@ -72,7 +117,54 @@ impl Cache for Tilecache {
         match self {
             &Tilecache::Nocache(ref cache) => cache.remove(path),
             &Tilecache::Filecache(ref cache) => cache.remove(path),
-            &Tilecache::S3Cache(ref cache) => cache.remove(path),
+            &Tilecache::BufferedFilecache(ref cache) => cache.remove(path),
+            &Tilecache::ObjectStoreCache(ref cache) => cache.remove(path),
+            &Tilecache::MemoryCached(ref cache) => cache.remove(path),
+        }
+    }
+    fn modified(&self, path: &str) -> Option<SystemTime> {
+        match self {
+            &Tilecache::Nocache(ref cache) => cache.modified(path),
+            &Tilecache::Filecache(ref cache) => cache.modified(path),
+            &Tilecache::BufferedFilecache(ref cache) => cache.modified(path),
+            &Tilecache::ObjectStoreCache(ref cache) => cache.modified(path),
+            &Tilecache::MemoryCached(ref cache) => cache.modified(path),
+        }
+    }
+    fn list(&self, prefix: &str) -> Vec<String> {
+        match self {
+            &Tilecache::Nocache(ref cache) => cache.list(prefix),
+            &Tilecache::Filecache(ref cache) => cache.list(prefix),
+            &Tilecache::BufferedFilecache(ref cache) => cache.list(prefix),
+            &Tilecache::ObjectStoreCache(ref cache) => cache.list(prefix),
+            &Tilecache::MemoryCached(ref cache) => cache.list(prefix),
+        }
+    }
+    fn purge(&self, prefix: &str) -> usize {
+        match self {
+            &Tilecache::Nocache(ref cache) => cache.purge(prefix),
+            &Tilecache::Filecache(ref cache) => cache.purge(prefix),
+            &Tilecache::BufferedFilecache(ref cache) => cache.purge(prefix),
+            &Tilecache::ObjectStoreCache(ref cache) => cache.purge(prefix),
+            &Tilecache::MemoryCached(ref cache) => cache.purge(prefix),
+        }
+    }
+    fn flush(&self) -> Result<(), io::Error> {
+        match self {
+            &Tilecache::Nocache(ref cache) => cache.flush(),
+            &Tilecache::Filecache(ref cache) => cache.flush(),
+            &Tilecache::BufferedFilecache(ref cache) => cache.flush(),
+            &Tilecache::ObjectStoreCache(ref cache) => cache.flush(),
+            &Tilecache::MemoryCached(ref cache) => cache.flush(),
+        }
+    }
+    fn tile_compression(&self) -> &'static str {
+        match self {
+            &Tilecache::Nocache(ref cache) => cache.tile_compression(),
+            &Tilecache::Filecache(ref cache) => cache.tile_compression(),
+            &Tilecache::BufferedFilecache(ref cache) => cache.tile_compression(),
+            &Tilecache::ObjectStoreCache(ref cache) => cache.tile_compression(),
+            &Tilecache::MemoryCached(ref cache) => cache.tile_compression(),
         }
     }
 }
@@ -86,29 +178,103 @@ impl<'a> Config<'a, ApplicationCfg> for Tilecache {
                 .cache
                 .as_ref()
                 .map(|cache| {
-                    if let Some(file_cache_cfg) = cache.file.as_ref() {
+                    let tilecache = if let Some(file_cache_cfg) = cache.file.as_ref() {
+                        // Tile URLs below baseurl don't contain the hash prefix
+                        if file_cache_cfg.shard && file_cache_cfg.baseurl.is_some() {
+                            return Err(
+                                "Cache option 'shard' is not supported with 'baseurl'".to_string()
+                            );
+                        }
                         let fc = Filecache {
                             basepath: file_cache_cfg.base.clone(),
                             baseurl: file_cache_cfg.baseurl.clone(),
+                            shard: file_cache_cfg.shard,
                         };
-                        Tilecache::Filecache(fc)
+                        match file_cache_cfg.flush_interval {
+                            Some(flush_interval) => {
+                                Tilecache::BufferedFilecache(BufferedCache::new(fc, flush_interval))
+                            }
+                            None => Tilecache::Filecache(fc),
+                        }
                     } else if let Some(s3_cache_cfg) = cache.s3.as_ref() {
-                        let s3c = S3Cache::new(
-                            &s3_cache_cfg.endpoint.clone(),
-                            &s3_cache_cfg.bucket.clone(),
-                            &s3_cache_cfg.access_key.clone(),
-                            &s3_cache_cfg.secret_key.clone(),
-                            &s3_cache_cfg.region.clone(),
-                            s3_cache_cfg.baseurl.clone(),
-                            s3_cache_cfg.key_prefix.clone(),
-                            s3_cache_cfg.gzip_header_enabled.clone(),
+                        let store = S3Bucket::new(
+                            &s3_cache_cfg.endpoint,
+                            &s3_cache_cfg.bucket,
+                            &s3_cache_cfg.access_key,
+                            &s3_cache_cfg.secret_key,
+                            &s3_cache_cfg.region,
+                        );
+                        let settings = ObjectCacheSettings {
+                            baseurl: s3_cache_cfg.baseurl.clone(),
+                            key_prefix: s3_cache_cfg.key_prefix.clone(),
+                            gzip_header_enabled: s3_cache_cfg.gzip_header_enabled,
+                            content_type: s3_cache_cfg.content_type.clone(),
+                            cache_control: s3_cache_cfg.cache_control.clone(),
+                        };
+                        let location =
+                            format!("s3: {}/{}", s3_cache_cfg.endpoint, s3_cache_cfg.bucket);
+                        Tilecache::ObjectStoreCache(ObjectStoreCache::new(
+                            &location,
+                            Arc::new(store),
+                            settings,
+                        ))
+                    } else if let Some(gcs_cache_cfg) = cache.gcs.as_ref() {
+                        let store = GcsBucket::from_env(&gcs_cache_cfg.bucket)
+                            .map_err(|e| format!("GCS cache: {}", e))?;
+                        let settings = ObjectCacheSettings {
+                            baseurl: gcs_cache_cfg.baseurl.clone(),
+                            key_prefix: gcs_cache_cfg.key_prefix.clone(),
+                            gzip_header_enabled: gcs_cache_cfg.gzip_header_enabled,
+                            content_type: gcs_cache_cfg.content_type.clone(),
+                            cache_control: gcs_cache_cfg.cache_control.clone(),
+                        };
+                        Tilecache::ObjectStoreCache(ObjectStoreCache::new(
+                            &format!("gcs: {}", gcs_cache_cfg.bucket),
+                            Arc::new(store),
+                            settings,
+                        ))
+                    } else if let Some(azure_cache_cfg) = cache.azure.as_ref() {
+                        let store = match azure_cache_cfg.connection_string {
+                            Some(ref connection_string) => {
+                                AzureBlobContainer::from_connection_string(
+                                    &azure_cache_cfg.container,
+                                    connection_string,
+                                )
+                                .map_err(|e| format!("Azure cache: {}", e))?
+                            }
+                            None => AzureBlobContainer::with_managed_identity(
+                                &azure_cache_cfg.account,
+                                &azure_cache_cfg.container,
+                            ),
+                        };
+                        let settings = ObjectCacheSettings {
+                            baseurl: azure_cache_cfg.baseurl.clone(),
+                            key_prefix: azure_cache_cfg.key_prefix.clone(),
+                            gzip_header_enabled: azure_cache_cfg.gzip_header_enabled,
+                            content_type: azure_cache_cfg.content_type.clone(),
+                            cache_control: azure_cache_cfg.cache_control.clone(),
+                        };
+                        let location = format!(
+                            "azure: {}/{}",
+                            azure_cache_cfg.account, azure_cache_cfg.container
                         );
-                        Tilecache::S3Cache(s3c)
+                        Tilecache::ObjectStoreCache(ObjectStoreCache::new(
+                            &location,
+                            Arc::new(store),
+                            settings,
+                        ))
                     } else {
                         Tilecache::Nocache(Nocache)
-                    }
+                    };
+                    Ok(match cache.memory {
+                        Some(ref memory_cfg) => Tilecache::MemoryCached(Box::new(MemoryCached {
+                            memory: MemoryCache::new(memory_cfg.max_bytes),
+                            backing: tilecache,
+                        })),
+                        None => tilecache,
+                    })
                 })
-                .ok_or("No Application Config found".to_string())
+                .ok_or("No Application Config found".to_string())?
         }
     }
     fn gen_config() -> String {
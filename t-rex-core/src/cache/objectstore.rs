@@ -0,0 +1,309 @@
+//
+// Copyright (c) Pirmin Kalberer. All rights reserved.
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+//
+
+use crate::cache::cache::Cache;
+use crate::mvt::tile::Tile;
+use curl::easy::{Easy, List};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Characters escaped in URL components (RFC 3986 unreserved characters are kept)
+pub(crate) const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+/// Characters escaped in object paths
+pub(crate) const OBJECT_PATH: &AsciiSet = &COMPONENT.remove(b'/');
+
+/// Timeout for establishing connections to the object store
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Timeout for a complete request including the transfer of the response
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Object with headers written into an object store
+#[derive(Clone, Debug, PartialEq)]
+pub struct PutObject {
+    pub key: String,
+    pub body: Vec<u8>,
+    pub content_type: String,
+    pub content_encoding: Option<String>,
+    pub cache_control: Option<String>,
+}
+
+impl PutObject {
+    /// Object with headers derived from the path extension.
+    /// `content_type` overrides the content type of tiles.
+    pub fn new(
+        key: String,
+        path: &str,
+        obj: &[u8],
+        gzip_header_enabled: bool,
+        content_type: Option<&String>,
+        cache_control: Option<&String>,
+    ) -> PutObject {
+        let extension = Path::new(path)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("");
+        let is_tile = extension == "mvt" || extension == "pbf";
+        let content_type = match extension {
+            "mvt" | "pbf" => content_type
+                .cloned()
+                .unwrap_or("application/vnd.mapbox-vector-tile".to_string()),
+            "json" => "application/json".to_string(),
+            _ => "application/octet-stream".to_string(),
+        };
+        let mut content_encoding: Option<String> = None;
+        if gzip_header_enabled && Tile::is_gzip(obj) && is_tile {
+            content_encoding = Some(String::from("gzip"));
+        }
+        PutObject {
+            key,
+            body: obj.to_vec(),
+            content_type,
+            content_encoding,
+            cache_control: cache_control.cloned(),
+        }
+    }
+}
+
+/// Object storage operations used by object store caches
+pub trait ObjectStore: Send + Sync {
+    fn get(&self, key: &str) -> Option<Vec<u8>>;
+    fn put(&self, object: PutObject) -> Result<(), String>;
+    fn head(&self, key: &str) -> bool;
+    /// Keys of all objects starting with `prefix`
+    fn list(&self, prefix: &str) -> Result<Vec<String>, String>;
+    fn delete(&self, key: &str) -> bool;
+}
+
+/// Tile cache settings shared by all object stores
+#[derive(Clone, Debug, Default)]
+pub struct ObjectCacheSettings {
+    pub baseurl: Option<String>,
+    pub key_prefix: Option<String>,
+    /// Set `Content-Encoding: gzip` on compressed tiles (Default: true)
+    pub gzip_header_enabled: Option<bool>,
+    /// Content-Type of tile objects (Default: application/vnd.mapbox-vector-tile)
+    pub content_type: Option<String>,
+    pub cache_control: Option<String>,
+}
+
+/// Tile cache in an object store (S3, Google Cloud Storage or Azure Blob Storage)
+#[derive(Clone)]
+pub struct ObjectStoreCache {
+    /// Kind and location of the store (e.g. `s3: http://localhost:9000/trex`)
+    location: String,
+    store: Arc<dyn ObjectStore>,
+    settings: ObjectCacheSettings,
+}
+
+impl ObjectStoreCache {
+    pub fn new(
+        location: &str,
+        store: Arc<dyn ObjectStore>,
+        settings: ObjectCacheSettings,
+    ) -> ObjectStoreCache {
+        ObjectStoreCache {
+            location: location.to_string(),
+            store,
+            settings,
+        }
+    }
+
+    fn key_prefix(&self) -> String {
+        self.settings.key_prefix.clone().unwrap_or_default()
+    }
+
+    fn gzip_header_enabled(&self) -> bool {
+        self.settings.gzip_header_enabled.unwrap_or(true)
+    }
+
+    fn full_path(&self, path: &str) -> String {
+        object_key(&self.key_prefix(), path)
+    }
+}
+
+impl Cache for ObjectStoreCache {
+    fn info(&self) -> String {
+        format!("Tile cache {}", self.location)
+    }
+
+    fn baseurl(&self) -> String {
+        self.settings
+            .baseurl
+            .clone()
+            .unwrap_or("http://localhost:6767".to_string())
+    }
+
+    fn tile_compression(&self) -> &'static str {
+        // Content-Encoding header lets HTTP clients decompress transparently
+        if self.gzip_header_enabled() {
+            "none"
+        } else {
+            "gzip"
+        }
+    }
+
+    fn read<F>(&self, path: &str, mut read: F) -> bool
+    where
+        F: FnMut(&mut dyn Read),
+    {
+        let key = self.full_path(path);
+        if key.is_empty() {
+            return false;
+        }
+        match self.store.get(&key) {
+            Some(data) => {
+                read(&mut &data[..]);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn write(&self, path: &str, obj: &[u8]) -> Result<(), io::Error> {
+        let key = self.full_path(path);
+        if key.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "failed to join key_prefix with path",
+            ));
+        }
+        let object = PutObject::new(
+            key,
+            path,
+            obj,
+            self.gzip_header_enabled(),
+            self.settings.content_type.as_ref(),
+            self.settings.cache_control.as_ref(),
+        );
+        self.store
+            .put(object)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        let key = self.full_path(path);
+        if key.is_empty() {
+            return false;
+        }
+        self.store.head(&key)
+    }
+
+    fn exists_batch(&self, paths: &[String]) -> Vec<bool> {
+        exists_batch_listed(self, paths)
+    }
+
+    fn remove(&self, path: &str) -> bool {
+        let key = self.full_path(path);
+        if key.is_empty() {
+            return false;
+        }
+        self.store.delete(&key)
+    }
+
+    /// Object keys starting with `key_prefix`/`prefix`, relative to `key_prefix`
+    fn list(&self, prefix: &str) -> Vec<String> {
+        let key_prefix = self.key_prefix();
+        let full_prefix = self.full_path(prefix);
+        match self.store.list(&full_prefix) {
+            Ok(keys) => keys
+                .iter()
+                .map(|key| cache_path(&key_prefix, key))
+                .collect(),
+            Err(err) => {
+                error!("Error listing {}: {}", full_prefix, err);
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// Object key of cache path
+fn object_key(key_prefix: &str, path: &str) -> String {
+    match Path::new(key_prefix).join(path).to_str() {
+        None => String::new(),
+        Some(result) => result.to_string(),
+    }
+}
+
+/// Cache path of object key
+fn cache_path(key_prefix: &str, key: &str) -> String {
+    let relkey = key.get(key_prefix.len()..).unwrap_or(key);
+    relkey.trim_start_matches('/').to_string()
+}
+
+/// Existence of objects with one listing per folder instead of a request per object
+fn exists_batch_listed<C: Cache>(cache: &C, paths: &[String]) -> Vec<bool> {
+    let mut folders: HashMap<String, HashSet<String>> = HashMap::new();
+    paths
+        .iter()
+        .map(|path| {
+            let folder = match path.rfind('/') {
+                Some(pos) => &path[..=pos],
+                None => "",
+            };
+            folders
+                .entry(folder.to_string())
+                .or_insert_with(|| cache.list(folder).into_iter().collect())
+                .contains(path)
+        })
+        .collect()
+}
+
+/// HTTP request returning status code and response body
+pub(crate) fn http_request(
+    method: &str,
+    url: &str,
+    headers: &[String],
+    body: Option<&[u8]>,
+) -> Result<(u32, Vec<u8>), String> {
+    let mut data = Vec::new();
+    let status = curl_request(method, url, headers, body, &mut data)
+        .map_err(|e| format!("{} {}: {}", method, url, e))?;
+    Ok((status, data))
+}
+
+fn curl_request(
+    method: &str,
+    url: &str,
+    headers: &[String],
+    body: Option<&[u8]>,
+    data: &mut Vec<u8>,
+) -> Result<u32, curl::Error> {
+    let mut easy = Easy::new();
+    easy.url(url)?;
+    easy.connect_timeout(CONNECT_TIMEOUT)?;
+    easy.timeout(REQUEST_TIMEOUT)?;
+    match method {
+        "GET" => {}
+        "HEAD" => easy.nobody(true)?,
+        "POST" => easy.post(true)?,
+        method => easy.custom_request(method)?,
+    }
+    if let Some(body) = body {
+        easy.post_fields_copy(body)?;
+    }
+    let mut list = List::new();
+    for header in headers {
+        list.append(header)?;
+    }
+    easy.http_headers(list)?;
+    {
+        let mut transfer = easy.transfer();
+        transfer.write_function(|chunk| {
+            data.extend_from_slice(chunk);
+            Ok(chunk.len())
+        })?;
+        transfer.perform()?;
+    }
+    easy.response_code()
+}
@@ -3,177 +3,122 @@
 // Licensed under the MIT License. See LICENSE file in the project root for full license information.
 //
 
-use crate::cache::cache::Cache;
+use crate::cache::objectstore::{ObjectStore, PutObject};
 use rusoto_core::{Client, HttpClient, Region};
 use rusoto_credential::StaticProvider;
 use rusoto_s3::{
-    DeleteObjectRequest, GetObjectRequest, HeadObjectRequest, PutObjectRequest, S3Client, S3,
+    DeleteObjectRequest, GetObjectRequest, HeadObjectRequest, ListObjectsV2Request,
+    PutObjectRequest, S3Client, S3,
 };
-use std::io::{self, Read};
-use std::path::Path;
+use std::io::Read;
 
-#[derive(Clone)]
-pub struct S3Cache {
-    baseurl: Option<String>,
+/// S3 bucket accessed with rusoto client
+pub struct S3Bucket {
     client: S3Client,
-    endpoint: String,
     bucket_name: String,
-    key_prefix: Option<String>,
-    gzip_header_enabled: Option<bool>,
 }
 
-impl S3Cache {
-    pub fn new(
-        endpoint: &str,
-        bucket_name: &str,
-        access_key: &str,
-        secret_key: &str,
-        region: &str,
-        baseurl: Option<String>,
-        key_prefix: Option<String>,
-        gzip_header_enabled: Option<bool>,
-    ) -> S3Cache {
-        let region_object = Region::Custom {
-            name: region.to_string(),
-            endpoint: endpoint.to_string(),
-        };
-        let client = S3Client::new_with_client(
-            Client::new_with(
-                StaticProvider::new(access_key.to_string(), secret_key.to_string(), None, None),
-                HttpClient::new().expect("Could not instantiate a new http client??"),
-            ),
-            region_object.clone(),
-        );
-        S3Cache {
-            client: client,
-            baseurl: baseurl,
-            endpoint: endpoint.to_string(),
-            bucket_name: bucket_name.to_string(),
-            key_prefix: key_prefix,
-            gzip_header_enabled: gzip_header_enabled,
-        }
-    }
-
-    fn key_prefix(&self) -> String {
-        self.key_prefix.clone().unwrap_or("".to_string())
-    }
-
-    fn gzip_header_enabled(&self) -> bool {
-        self.gzip_header_enabled.clone().unwrap_or(true)
-    }
-
-    fn full_path(&self, path: &str) -> String {
-        let key_prefix = &self.key_prefix();
-        match Path::new(key_prefix).join(path).to_str() {
-            None => String::new(),
-            Some(result) => result.to_string(),
-        }
-    }
-}
-
-impl Cache for S3Cache {
-    fn info(&self) -> String {
-        format!("Tile cache s3: {}/{}", self.endpoint, self.bucket_name)
-    }
-
-    fn baseurl(&self) -> String {
-        self.baseurl
-            .clone()
-            .unwrap_or("http://localhost:6767".to_string())
-    }
-
-    fn read<F>(&self, path: &str, mut read: F) -> bool
-    where
-        F: FnMut(&mut dyn Read),
-    {
-        let key = self.full_path(path);
-        if key.is_empty() {
-            return false;
-        }
+impl ObjectStore for S3Bucket {
+    fn get(&self, key: &str) -> Option<Vec<u8>> {
         let request = GetObjectRequest {
             bucket: self.bucket_name.to_owned(),
             key: key.to_owned(),
             ..Default::default()
         };
-        let client = self.client.clone();
-        let response = client.get_object(request).sync();
-        match response {
-            Ok(mut result) => {
-                let body = result.body.take().expect("The object has no body");
-                read(&mut body.into_blocking_read());
-                true
-            }
-            Err(_) => false,
-        }
+        let mut result = self.client.get_object(request).sync().ok()?;
+        let body = result.body.take().expect("The object has no body");
+        let mut data = Vec::new();
+        body.into_blocking_read().read_to_end(&mut data).ok()?;
+        Some(data)
     }
 
-    fn write(&self, path: &str, obj: &[u8]) -> Result<(), io::Error> {
-        let key = self.full_path(path);
-        if key.is_empty() {
-            return Err(io::Error::new(
-                io::ErrorKind::Other,
-                "failed to join key_prefix with path",
-            ));
-        }
-        let extension = Path::new(path).extension().unwrap().to_str().unwrap();
-        let content_type = match extension {
-            "mvt" => Some("application/vnd.mapbox-vector-tile".to_string()),
-            "pbf" => Some("application/vnd.mapbox-vector-tile".to_string()),
-            "json" => Some("application/json".to_string()),
-            _ => Some("application/octet-stream".to_string()),
-        };
-        let mut content_encoding: Option<String> = None;
-        if self.gzip_header_enabled()
-            && content_type == Some("application/vnd.mapbox-vector-tile".to_string())
-        {
-            content_encoding = Some(String::from("gzip"));
-        }
+    fn put(&self, object: PutObject) -> Result<(), String> {
         let request = PutObjectRequest {
             bucket: self.bucket_name.to_owned(),
-            key: key.to_owned(),
-            content_type: content_type,
-            content_encoding: content_encoding,
-            body: Some(obj.to_vec().into()),
+            key: object.key,
+            content_type: Some(object.content_type),
+            content_encoding: object.content_encoding,
+            cache_control: object.cache_control,
+            body: Some(object.body.into()),
             ..Default::default()
         };
-        let response = self.client.put_object(request).sync();
-        match response {
+        match self.client.put_object(request).sync() {
             Ok(_) => Ok(()),
-            Err(err) => Err(io::Error::new(io::ErrorKind::Other, err.to_string())),
+            Err(err) => Err(err.to_string()),
         }
     }
 
-    fn exists(&self, path: &str) -> bool {
-        let key = self.full_path(path);
-        if key.is_empty() {
-            return false;
-        }
+    fn head(&self, key: &str) -> bool {
         let request = HeadObjectRequest {
             bucket: self.bucket_name.to_owned(),
             key: key.to_owned(),
             ..Default::default()
         };
-        let response = self.client.head_object(request).sync();
-        match response {
-            Ok(_) => true,
-            Err(_) => false,
-        }
+        self.client.head_object(request).sync().is_ok()
     }
 
-    fn remove(&self, path: &str) -> bool {
-        let key = self.full_path(path);
-        if key.is_empty() {
-            return false;
+    fn list(&self, prefix: &str) -> Result<Vec<String>, String> {
+        let mut keys = Vec::new();
+        let mut continuation_token = None;
+        loop {
+            let request = ListObjectsV2Request {
+                bucket: self.bucket_name.to_owned(),
+                prefix: Some(prefix.to_string()),
+                continuation_token: continuation_token.take(),
+                ..Default::default()
+            };
+            let response = self
+                .client
+                .list_objects_v2(request)
+                .sync()
+                .map_err(|e| e.to_string())?;
+            keys.extend(
+                response
+                    .contents
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|object| object.key),
+            );
+            if response.is_truncated != Some(true) || response.next_continuation_token.is_none() {
+                break;
+            }
+            continuation_token = response.next_continuation_token;
         }
+        Ok(keys)
+    }
+
+    fn delete(&self, key: &str) -> bool {
         let request = DeleteObjectRequest {
             bucket: self.bucket_name.to_owned(),
             key: key.to_owned(),
             ..Default::default()
         };
-        let response = self.client.delete_object(request).sync();
-        match response {
-            Ok(_) => true,
-            Err(_) => false,
+        self.client.delete_object(request).sync().is_ok()
+    }
+}
+
+impl S3Bucket {
+    pub fn new(
+        endpoint: &str,
+        bucket_name: &str,
+        access_key: &str,
+        secret_key: &str,
+        region: &str,
+    ) -> S3Bucket {
+        let region_object = Region::Custom {
+            name: region.to_string(),
+            endpoint: endpoint.to_string(),
+        };
+        let client = S3Client::new_with_client(
+            Client::new_with(
+                StaticProvider::new(access_key.to_string(), secret_key.to_string(), None, None),
+                HttpClient::new().expect("Could not instantiate a new http client??"),
+            ),
+            region_object,
+        );
+        S3Bucket {
+            client,
+            bucket_name: bucket_name.to_string(),
         }
     }
 }
@@ -3,10 +3,13 @@
 // Licensed under the MIT License. See LICENSE file in the project root for full license information.
 //
 use crate::cache::cache::Cache;
-use crate::cache::s3cache::S3Cache;
+use crate::cache::objectstore::{ObjectCacheSettings, ObjectStore, ObjectStoreCache, PutObject};
+use crate::cache::s3cache::S3Bucket;
 use curl::easy::Easy;
+use std::collections::BTreeMap;
 use std::env;
 use std::str;
+use std::sync::{Arc, Mutex};
 
 #[test]
 #[ignore]
@@ -15,16 +18,20 @@ fn test_s3cache() {
         return;
     }
 
-    let cache = S3Cache::new(
-        "http://localhost:9000",
-        "trex",
-        "miniostorage",
-        "miniostorage",
-        "my-region",
-        Some("http://localhost:6767".to_string()),
-        None,
-        None,
-    );
+    let minio_cache = |settings| {
+        let store = S3Bucket::new(
+            "http://localhost:9000",
+            "trex",
+            "miniostorage",
+            "miniostorage",
+            "my-region",
+        );
+        ObjectStoreCache::new("s3: http://localhost:9000/trex", Arc::new(store), settings)
+    };
+    let cache = minio_cache(ObjectCacheSettings {
+        baseurl: Some("http://localhost:6767".to_string()),
+        ..Default::default()
+    });
     let path = "tileset/0/1/2.pbf";
     let obj = "01234567910";
 
@@ -54,36 +61,41 @@ fn test_s3cache() {
     });
     assert_eq!(&s, obj);
 
-    // check if Content-Encoding header set by default
-    let mut handle = Easy::new();
-    let mut headers = Vec::new();
-    handle
-        .url("http://localhost:9000/trex/tileset/0/1/2.pbf")
-        .unwrap();
-    {
-        let mut transfer = handle.transfer();
-        transfer
-            .header_function(|header| {
-                headers.push(str::from_utf8(header).unwrap().to_string());
-                true
-            })
-            .unwrap();
-        transfer.perform().unwrap();
-    }
-    dbg!(&headers);
+    // Uncompressed tiles are stored without Content-Encoding header
+    let object_headers = |url: &str| {
+        let mut handle = Easy::new();
+        let mut headers = Vec::new();
+        handle.url(url).unwrap();
+        {
+            let mut transfer = handle.transfer();
+            transfer
+                .header_function(|header| {
+                    headers.push(str::from_utf8(header).unwrap().to_string());
+                    true
+                })
+                .unwrap();
+            transfer.perform().unwrap();
+        }
+        headers
+    };
+    let headers = object_headers("http://localhost:9000/trex/tileset/0/1/2.pbf");
+    assert!(!headers
+        .iter()
+        .any(|h| h.to_lowercase().starts_with("content-encoding:")));
+
+    // Content-Encoding header set by default for gzip compressed tiles
+    let tilegz = [0x1f, 0x8b, 0x08, 0x00];
+    cache.write("tileset/0/1/3.pbf", &tilegz).unwrap();
+    let headers = object_headers("http://localhost:9000/trex/tileset/0/1/3.pbf");
     assert!(headers.contains(&"Content-Encoding: gzip\r\n".to_string()));
 
     // test key_prefix
-    let cache_prefix = S3Cache::new(
-        "http://localhost:9000",
-        "trex",
-        "miniostorage",
-        "miniostorage",
-        "my-region",
-        Some("http://localhost:6767".to_string()),
-        Some("my-prefix".to_string()),
-        Some(false),
-    );
+    let cache_prefix = minio_cache(ObjectCacheSettings {
+        baseurl: Some("http://localhost:6767".to_string()),
+        key_prefix: Some("my-prefix".to_string()),
+        gzip_header_enabled: Some(false),
+        ..Default::default()
+    });
 
     // Cache miss
     assert_eq!(cache_prefix.read(path, |_| {}), false);
@@ -111,21 +123,149 @@ fn test_s3cache() {
     });
     assert_eq!(&s, obj);
 
-    // check if Content-Encoding header not set
-    let mut handle = Easy::new();
-    let mut headers = Vec::new();
-    handle
-        .url("http://localhost:9000/trex/my-prefix/tileset/0/1/2.pbf")
-        .unwrap();
-    {
-        let mut transfer = handle.transfer();
-        transfer
-            .header_function(|header| {
-                headers.push(str::from_utf8(header).unwrap().to_string());
-                true
-            })
-            .unwrap();
-        transfer.perform().unwrap();
-    }
+    // Content-Encoding header not set for gzip compressed tiles
+    cache_prefix.write("tileset/0/1/3.pbf", &tilegz).unwrap();
+    let headers = object_headers("http://localhost:9000/trex/my-prefix/tileset/0/1/3.pbf");
     assert!(!headers.contains(&"Content-Encoding: gzip\r\n".to_string()));
 }
+
+#[test]
+fn test_s3cache_tile_compression() {
+    let s3cache = |gzip_header_enabled| {
+        let settings = ObjectCacheSettings {
+            gzip_header_enabled,
+            ..Default::default()
+        };
+        ObjectStoreCache::new("s3: trex", Arc::new(MemoryStore::default()), settings)
+    };
+    assert_eq!(s3cache(None).tile_compression(), "none");
+    assert_eq!(s3cache(Some(true)).tile_compression(), "none");
+    assert_eq!(s3cache(Some(false)).tile_compression(), "gzip");
+}
+
+/// In-memory object store
+#[derive(Default)]
+pub(super) struct MemoryStore {
+    pub objects: Mutex<BTreeMap<String, PutObject>>,
+}
+
+impl ObjectStore for MemoryStore {
+    fn get(&self, key: &str) -> Option<Vec<u8>> {
+        self.objects
+            .lock()
+            .unwrap()
+            .get(key)
+            .map(|object| object.body.clone())
+    }
+    fn put(&self, object: PutObject) -> Result<(), String> {
+        self.objects
+            .lock()
+            .unwrap()
+            .insert(object.key.clone(), object);
+        Ok(())
+    }
+    fn head(&self, key: &str) -> bool {
+        self.objects.lock().unwrap().contains_key(key)
+    }
+    fn list(&self, prefix: &str) -> Result<Vec<String>, String> {
+        Ok(self
+            .objects
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|key| key.starts_with(prefix))
+            .cloned()
+            .collect())
+    }
+    fn delete(&self, key: &str) -> bool {
+        self.objects.lock().unwrap().remove(key).is_some()
+    }
+}
+
+fn fake_s3cache(
+    content_type: Option<String>,
+    cache_control: Option<String>,
+) -> (ObjectStoreCache, Arc<MemoryStore>) {
+    let store = Arc::new(MemoryStore::default());
+    let settings = ObjectCacheSettings {
+        key_prefix: Some("my-prefix".to_string()),
+        content_type,
+        cache_control,
+        ..Default::default()
+    };
+    let cache = ObjectStoreCache::new("s3: trex", store.clone(), settings);
+    (cache, store)
+}
+
+#[test]
+fn test_s3cache_fake_store() {
+    let (cache, store) = fake_s3cache(None, None);
+    let path = "tileset/0/1/2.pbf";
+
+    // Cache miss
+    assert_eq!(cache.read(path, |_| {}), false);
+    assert!(!cache.exists(path));
+
+    // Write into cache
+    cache.write(path, b"0123456789").unwrap();
+    assert!(store.head("my-prefix/tileset/0/1/2.pbf"));
+    assert!(cache.exists(path));
+
+    // Read from cache
+    let mut s = String::new();
+    assert!(cache.read(path, |f| {
+        let _ = f.read_to_string(&mut s);
+    }));
+    assert_eq!(&s, "0123456789");
+
+    cache.write("tileset/0/1/3.pbf", b"0123456789").unwrap();
+    assert_eq!(
+        cache.list("tileset/0/"),
+        vec!["tileset/0/1/2.pbf", "tileset/0/1/3.pbf"]
+    );
+    assert_eq!(
+        cache.exists_batch(&[path.to_string(), "tileset/0/1/4.pbf".to_string()]),
+        vec![true, false]
+    );
+
+    assert!(cache.remove(path));
+    assert!(!cache.exists(path));
+    assert_eq!(cache.purge("tileset/"), 1);
+    assert!(cache.list("").is_empty());
+}
+
+#[test]
+fn test_s3cache_put_headers() {
+    let tilegz = [0x1f, 0x8b, 0x08, 0x00];
+    let stored = |store: &MemoryStore, key: &str| store.objects.lock().unwrap()[key].clone();
+
+    // Defaults
+    let (cache, store) = fake_s3cache(None, None);
+    cache.write("tileset/0/1/2.pbf", &tilegz).unwrap();
+    let object = stored(&store, "my-prefix/tileset/0/1/2.pbf");
+    assert_eq!(object.content_type, "application/vnd.mapbox-vector-tile");
+    assert_eq!(object.content_encoding, Some("gzip".to_string()));
+    assert_eq!(object.cache_control, None);
+
+    // Uncompressed tile
+    cache.write("tileset/0/1/3.pbf", b"tile").unwrap();
+    let object = stored(&store, "my-prefix/tileset/0/1/3.pbf");
+    assert_eq!(object.content_encoding, None);
+
+    let (cache, store) = fake_s3cache(
+        Some("application/x-protobuf".to_string()),
+        Some("max-age=86400".to_string()),
+    );
+    cache.write("tileset/0/1/2.pbf", &tilegz).unwrap();
+    let object = stored(&store, "my-prefix/tileset/0/1/2.pbf");
+    assert_eq!(object.content_type, "application/x-protobuf");
+    assert_eq!(object.content_encoding, Some("gzip".to_string()));
+    assert_eq!(object.cache_control, Some("max-age=86400".to_string()));
+
+    // Metadata
+    cache.write("tileset.json", b"{}").unwrap();
+    let object = stored(&store, "my-prefix/tileset.json");
+    assert_eq!(object.content_type, "application/json");
+    assert_eq!(object.content_encoding, None);
+    assert_eq!(object.cache_control, Some("max-age=86400".to_string()));
+}
@@ -0,0 +1,191 @@
+//
+// Copyright (c) Pirmin Kalberer. All rights reserved.
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+//
+
+//! Tile presence bitmap for answering cache existence checks in memory
+
+use std::collections::BTreeMap;
+use tile_grid::ExtentInt;
+
+const MAGIC: &[u8] = b"TREXBM1";
+
+/// Presence bits for the tile limits of one zoom level
+#[derive(Clone, Debug)]
+struct BitmapLevel {
+    limits: ExtentInt,
+    bits: Vec<u8>,
+}
+
+impl BitmapLevel {
+    fn new(limits: &ExtentInt) -> BitmapLevel {
+        let mut level = BitmapLevel {
+            limits: limits.clone(),
+            bits: Vec::new(),
+        };
+        level.bits = vec![0; level.len().div_ceil(8)];
+        level
+    }
+    fn width(&self) -> usize {
+        self.limits.maxx.saturating_sub(self.limits.minx) as usize
+    }
+    fn height(&self) -> usize {
+        self.limits.maxy.saturating_sub(self.limits.miny) as usize
+    }
+    fn len(&self) -> usize {
+        self.width() * self.height()
+    }
+    /// Bit index of a tile within limits
+    fn index(&self, xtile: u32, ytile: u32) -> Option<usize> {
+        if xtile < self.limits.minx
+            || xtile >= self.limits.maxx
+            || ytile < self.limits.miny
+            || ytile >= self.limits.maxy
+        {
+            return None;
+        }
+        let col = (xtile - self.limits.minx) as usize;
+        let row = (ytile - self.limits.miny) as usize;
+        Some(col * self.height() + row)
+    }
+    /// Tiles marked as present
+    fn tiles(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        let height = self.height();
+        (0..self.len())
+            .filter(move |idx| self.bits[idx / 8] & (1 << (idx % 8)) != 0)
+            .map(move |idx| {
+                (
+                    self.limits.minx + (idx / height) as u32,
+                    self.limits.miny + (idx % height) as u32,
+                )
+            })
+    }
+}
+
+/// Tile presence bitmap keyed by z/x/y (grid adressing scheme)
+#[derive(Clone, Debug, Default)]
+pub struct TileBitmap {
+    levels: BTreeMap<u8, BitmapLevel>,
+}
+
+impl TileBitmap {
+    pub fn new() -> TileBitmap {
+        TileBitmap {
+            levels: BTreeMap::new(),
+        }
+    }
+    /// Add zoom level covering the given tile limits. All tiles are marked as absent.
+    pub fn add_level(&mut self, zoom: u8, limits: &ExtentInt) {
+        self.levels.insert(zoom, BitmapLevel::new(limits));
+    }
+    /// Add tiles of another bitmap outside the limits of this bitmap.
+    /// Levels are extended to cover the limits of both bitmaps.
+    pub fn merge(&mut self, other: &TileBitmap) {
+        for (zoom, other_level) in &other.levels {
+            let limits = match self.levels.get(zoom) {
+                Some(level) => ExtentInt {
+                    minx: level.limits.minx.min(other_level.limits.minx),
+                    miny: level.limits.miny.min(other_level.limits.miny),
+                    maxx: level.limits.maxx.max(other_level.limits.maxx),
+                    maxy: level.limits.maxy.max(other_level.limits.maxy),
+                },
+                None => other_level.limits.clone(),
+            };
+            let mut merged = BitmapLevel::new(&limits);
+            let level = self.levels.get(zoom);
+            let tiles = level.into_iter().flat_map(|level| level.tiles());
+            // Tiles within the limits of this bitmap were regenerated or removed
+            let other_tiles = other_level.tiles().filter(|&(xtile, ytile)| {
+                level.and_then(|level| level.index(xtile, ytile)).is_none()
+            });
+            for (xtile, ytile) in tiles.chain(other_tiles) {
+                if let Some(idx) = merged.index(xtile, ytile) {
+                    merged.bits[idx / 8] |= 1 << (idx % 8);
+                }
+            }
+            self.levels.insert(*zoom, merged);
+        }
+    }
+    /// Mark tile as present
+    pub fn set(&mut self, zoom: u8, xtile: u32, ytile: u32) {
+        if let Some(level) = self.levels.get_mut(&zoom) {
+            if let Some(idx) = level.index(xtile, ytile) {
+                level.bits[idx / 8] |= 1 << (idx % 8);
+            }
+        }
+    }
+    /// Tile presence. Returns None for tiles not covered by the bitmap.
+    pub fn contains(&self, zoom: u8, xtile: u32, ytile: u32) -> Option<bool> {
+        self.levels.get(&zoom).and_then(|level| {
+            level
+                .index(xtile, ytile)
+                .map(|idx| level.bits[idx / 8] & (1 << (idx % 8)) != 0)
+        })
+    }
+    /// Number of tiles marked as present
+    pub fn count(&self) -> u64 {
+        self.levels
+            .values()
+            .map(|level| {
+                level
+                    .bits
+                    .iter()
+                    .map(|b| b.count_ones() as u64)
+                    .sum::<u64>()
+            })
+            .sum()
+    }
+    /// Serialize into binary format
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut data = MAGIC.to_vec();
+        for (zoom, level) in &self.levels {
+            data.push(*zoom);
+            for val in &[
+                level.limits.minx,
+                level.limits.miny,
+                level.limits.maxx,
+                level.limits.maxy,
+            ] {
+                data.extend_from_slice(&val.to_le_bytes());
+            }
+            data.extend_from_slice(&level.bits);
+        }
+        data
+    }
+    /// Deserialize from binary format
+    pub fn from_bytes(data: &[u8]) -> Result<TileBitmap, String> {
+        if !data.starts_with(MAGIC) {
+            return Err("Invalid tile bitmap header".to_string());
+        }
+        let mut bitmap = TileBitmap::new();
+        let mut pos = MAGIC.len();
+        while pos < data.len() {
+            if pos + 17 > data.len() {
+                return Err("Truncated tile bitmap".to_string());
+            }
+            let zoom = data[pos];
+            let mut vals = [0u32; 4];
+            for (i, val) in vals.iter_mut().enumerate() {
+                let start = pos + 1 + i * 4;
+                let mut bytes = [0u8; 4];
+                bytes.copy_from_slice(&data[start..start + 4]);
+                *val = u32::from_le_bytes(bytes);
+            }
+            pos += 17;
+            let mut level = BitmapLevel::new(&ExtentInt {
+                minx: vals[0],
+                miny: vals[1],
+                maxx: vals[2],
+                maxy: vals[3],
+            });
+            let nbytes = level.bits.len();
+            if pos + nbytes > data.len() {
+                return Err("Truncated tile bitmap".to_string());
+            }
+            level.bits.copy_from_slice(&data[pos..pos + nbytes]);
+            pos += nbytes;
+            bitmap.levels.insert(zoom, level);
+        }
+        Ok(bitmap)
+    }
+}
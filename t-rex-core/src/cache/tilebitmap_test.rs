@@ -0,0 +1,116 @@
+//
+// Copyright (c) Pirmin Kalberer. All rights reserved.
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+//
+
+use crate::cache::tilebitmap::TileBitmap;
+use tile_grid::ExtentInt;
+
+#[test]
+fn test_tile_bitmap() {
+    let mut bitmap = TileBitmap::new();
+    bitmap.add_level(
+        2,
+        &ExtentInt {
+            minx: 1,
+            miny: 0,
+            maxx: 3,
+            maxy: 4,
+        },
+    );
+    bitmap.add_level(
+        3,
+        &ExtentInt {
+            minx: 2,
+            miny: 2,
+            maxx: 7,
+            maxy: 5,
+        },
+    );
+    bitmap.set(2, 1, 3);
+    bitmap.set(3, 6, 4);
+    bitmap.set(3, 2, 2);
+    // Outside limits
+    bitmap.set(3, 7, 4);
+
+    assert_eq!(bitmap.contains(2, 1, 3), Some(true));
+    assert_eq!(bitmap.contains(2, 2, 3), Some(false));
+    assert_eq!(bitmap.contains(3, 6, 4), Some(true));
+    assert_eq!(bitmap.contains(3, 2, 2), Some(true));
+    assert_eq!(bitmap.contains(3, 2, 3), Some(false));
+    assert_eq!(bitmap.count(), 3);
+
+    // Tiles not covered by bitmap
+    assert_eq!(bitmap.contains(3, 7, 4), None);
+    assert_eq!(bitmap.contains(2, 0, 0), None);
+    assert_eq!(bitmap.contains(4, 12, 8), None);
+
+    // Serialization roundtrip
+    let bitmap = TileBitmap::from_bytes(&bitmap.to_bytes()).unwrap();
+    assert_eq!(bitmap.contains(2, 1, 3), Some(true));
+    assert_eq!(bitmap.contains(2, 2, 3), Some(false));
+    assert_eq!(bitmap.contains(3, 6, 4), Some(true));
+    assert_eq!(bitmap.contains(3, 7, 4), None);
+    assert_eq!(bitmap.count(), 3);
+
+    assert_eq!(
+        TileBitmap::from_bytes(b"0123").err(),
+        Some("Invalid tile bitmap header".to_string())
+    );
+    let mut data = bitmap.to_bytes();
+    data.pop();
+    assert_eq!(
+        TileBitmap::from_bytes(&data).err(),
+        Some("Truncated tile bitmap".to_string())
+    );
+}
+
+#[test]
+fn test_merge_tile_bitmap() {
+    let mut existing = TileBitmap::new();
+    existing.add_level(
+        2,
+        &ExtentInt {
+            minx: 0,
+            miny: 0,
+            maxx: 4,
+            maxy: 4,
+        },
+    );
+    existing.add_level(
+        3,
+        &ExtentInt {
+            minx: 0,
+            miny: 0,
+            maxx: 6,
+            maxy: 6,
+        },
+    );
+    existing.set(2, 3, 1);
+    existing.set(3, 1, 1);
+    // Tile removed by the run
+    existing.set(3, 4, 3);
+
+    // Bitmap of a run limited to zoom level 3 and a smaller extent
+    let mut bitmap = TileBitmap::new();
+    bitmap.add_level(
+        3,
+        &ExtentInt {
+            minx: 4,
+            miny: 2,
+            maxx: 6,
+            maxy: 5,
+        },
+    );
+    bitmap.set(3, 5, 4);
+    bitmap.merge(&existing);
+
+    assert_eq!(bitmap.contains(2, 3, 1), Some(true));
+    assert_eq!(bitmap.contains(2, 0, 0), Some(false));
+    assert_eq!(bitmap.contains(3, 1, 1), Some(true));
+    assert_eq!(bitmap.contains(3, 5, 4), Some(true));
+    assert_eq!(bitmap.contains(3, 4, 1), Some(false));
+    assert_eq!(bitmap.contains(3, 4, 3), Some(false));
+    assert_eq!(bitmap.contains(3, 6, 4), None);
+    assert_eq!(bitmap.count(), 3);
+}
@@ -4,14 +4,15 @@
 //
 
 use crate::core::gridcfg::ExtentCfg;
-use regex::Regex;
+use regex::{Captures, Regex};
 use serde::Deserialize;
 use std;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
 use std::error::Error;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::prelude::*;
+use std::path::Path;
 use tera::{Context, Tera};
 use toml::Value;
 
@@ -34,10 +35,16 @@ pub struct ApplicationCfg {
     pub service: ServiceCfg,
     pub datasource: Vec<DatasourceCfg>,
     pub grid: GridCfg,
+    /// Additional grids referenced by tilesets
+    #[serde(default)]
+    pub grids: BTreeMap<String, GridCfg>,
     #[serde(rename = "tileset")]
     pub tilesets: Vec<TilesetCfg>,
     pub cache: Option<CacheCfg>,
     pub webserver: WebserverCfg,
+    /// Treat configuration warnings as errors
+    #[serde(default)]
+    pub strict_config: bool,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -48,6 +55,27 @@ pub struct ServiceCfg {
 #[derive(Deserialize, Clone, Debug)]
 pub struct ServiceMvtCfg {
     pub viewer: bool,
+    /// HTML list of tilesets at `/`
+    #[serde(default)]
+    pub index_page: bool,
+    /// Maximal number of concurrent tile generation tasks
+    pub generate_threads: Option<usize>,
+    /// Handling of tiles without features (skip or store)
+    #[serde(default)]
+    pub empty_tiles: EmptyTiles,
+    /// Maximal duration of a tile request in milliseconds
+    pub tile_timeout_ms: Option<u64>,
+}
+
+/// Handling of tiles without features
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum EmptyTiles {
+    /// Neither cached nor served
+    #[default]
+    Skip,
+    /// Cached and served as empty tile
+    Store,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -58,14 +86,64 @@ pub struct DatasourceCfg {
     pub dbconn: Option<String>,
     pub pool: Option<u16>,
     pub connection_timeout: Option<u64>,
+    /// Check existence of grid and layer SRIDs at startup
+    pub srid_check: Option<SridCheck>,
+    /// Maximal number of concurrent feature queries
+    pub max_concurrent_queries: Option<usize>,
+    /// Statement timeout of feature queries in milliseconds
+    pub statement_timeout_ms: Option<u64>,
+    /// Detect geometry columns of views missing in geometry_columns
+    pub detect_views: Option<bool>,
     // GDAL
     pub path: Option<String>,
 }
 
+/// Handling of SRIDs missing in the datasource
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum SridCheck {
+    /// Log a warning
+    Warn,
+    /// Abort initialization
+    Error,
+}
+
+impl SridCheck {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SridCheck::Warn => "warn",
+            SridCheck::Error => "error",
+        }
+    }
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct GridCfg {
     pub predefined: Option<String>,
+    /// Default grid for a well-known EPSG code (3857, 4326, 2056, 25832, 3035)
+    pub epsg: Option<i32>,
     pub user: Option<UserGridCfg>,
+    /// Tile adressing scheme (Default: xyz for Web Mercator and grids with top left origin)
+    pub tile_scheme: Option<TileScheme>,
+}
+
+/// Row numbering of tile requests and cache paths
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum TileScheme {
+    /// Rows counted from the top
+    Xyz,
+    /// Rows counted from the bottom
+    Tms,
+}
+
+impl TileScheme {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TileScheme::Xyz => "xyz",
+            TileScheme::Tms => "tms",
+        }
+    }
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -98,17 +176,47 @@ pub struct UserGridCfg {
 #[derive(Deserialize, Clone, Debug)]
 pub struct TilesetCfg {
     pub name: String,
+    /// Vector tiles or raster tiles proxied from `url`
+    #[serde(rename = "type", default)]
+    pub tileset_type: TilesetType,
+    /// URL template of raster tiles, e.g. "https://tile.example.com/{z}/{x}/{y}.png"
+    pub url: Option<String>,
+    /// Name of grid in `[grids]` (Default: `[grid]`)
+    pub grid: Option<String>,
     pub extent: Option<ExtentCfg>,
+    /// Detect extent from layer data at startup, if no extent is configured
+    #[serde(default)]
+    pub detect_extent: bool,
     pub minzoom: Option<u8>,
     pub maxzoom: Option<u8>,
     pub center: Option<(f64, f64)>,
     pub start_zoom: Option<u8>,
     pub attribution: Option<String>,
-    #[serde(rename = "layer")]
+    #[serde(rename = "layer", default)]
     pub layers: Vec<LayerCfg>,
     // Inline style
     pub style: Option<Value>,
     pub cache_limits: Option<TilesetCacheCfg>,
+    /// Prebuilt tile served instead of generated tiles within a zoom band
+    pub placeholder_tile: Option<PlaceholderTileCfg>,
+    /// Cache tiles of this tileset (Default: true)
+    #[serde(default = "default_cache")]
+    pub cache: bool,
+    /// Write tile presence bitmap when generating and use it for serving
+    #[serde(default)]
+    pub tile_bitmap: bool,
+    /// Data version for cache busting
+    pub version: Option<String>,
+    /// Query returning the data version, e.g. `SELECT max(updated_at) FROM osm_roads`
+    pub version_query: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TilesetType {
+    #[default]
+    Vector,
+    Raster,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -123,13 +231,27 @@ pub struct LayerQueryCfg {
     pub sql: Option<String>,
 }
 
+/// Attribute computed with an SQL expression
+#[derive(Deserialize, Clone, Debug)]
+pub struct ComputedAttrCfg {
+    pub name: String,
+    pub expr: String,
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct LayerCfg {
     pub name: String,
+    /// Include layer in tiles and metadata (Default: true)
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
     pub datasource: Option<String>,
     pub geometry_field: Option<String>,
     pub geometry_type: Option<String>,
-    /// Spatial reference system (PostGIS SRID)
+    /// Serve only features of this geometry type (POINT, LINESTRING or POLYGON
+    /// and their multi-types) from a GEOMETRY column with mixed types
+    pub geometry_type_filter: Option<String>,
+    /// Spatial reference system (PostGIS SRID). Not read from geometry_columns,
+    /// e.g. for views reporting SRID 0.
     pub srid: Option<i32>,
     /// Handle geometry like one in grid SRS
     #[serde(default)]
@@ -138,11 +260,22 @@ pub struct LayerCfg {
     // Input for derived queries
     pub table_name: Option<String>,
     pub query_limit: Option<u32>,
+    /// Column for ordering features before applying `query_limit`
+    pub order_by: Option<String>,
+    /// Order features descending
+    #[serde(default)]
+    pub order_desc: bool,
     // Explicit queries
     #[serde(default)]
     pub query: Vec<LayerQueryCfg>,
+    /// Additional attributes computed in the feature query (PostGIS only)
+    #[serde(default)]
+    pub computed: Vec<ComputedAttrCfg>,
     pub minzoom: Option<u8>,
     pub maxzoom: Option<u8>,
+    /// Serve data of maxzoom level clipped to tiles of higher zoom levels
+    #[serde(default)]
+    pub overzoom: bool,
     /// Width and height of the tile (Default: 4096. Grid default size is 256)
     #[serde(default = "default_tile_size")]
     pub tile_size: u32,
@@ -150,18 +283,169 @@ pub struct LayerCfg {
     #[serde(default)]
     pub simplify: bool,
     /// Simplification tolerance (default to !pixel_width!/2)
-    #[serde(default = "default_tolerance")]
-    pub tolerance: String,
+    #[serde(default = "default_tolerance_cfg")]
+    pub tolerance: ToleranceCfg,
+    /// Tolerance for linearizing CURVEPOLYGON and COMPOUNDCURVE geometries
+    /// (true: simplification tolerance of zoom level)
+    pub curve_tolerance: Option<CurveToleranceCfg>,
     /// Tile buffer size in pixels (None: no clipping)
     pub buffer_size: Option<u32>,
+    /// Tile buffer size in MVT extent units (alternative to `buffer_size`)
+    pub buffer_extent: Option<u32>,
     /// Fix invalid geometries before clipping (lines and polygons)
     #[serde(default)]
     pub make_valid: bool,
-    /// Apply ST_Shift_Longitude to (transformed) bbox
+    /// Apply ST_Shift_Longitude to (transformed) bbox and split lines crossing the antimeridian
     #[serde(default)]
     pub shift_longitude: bool,
+    /// Clip geometries to buffered tile extent when encoding
+    pub clip_on_encode: Option<bool>,
+    /// Clip and transform geometries to tile coordinates with ST_AsMVTGeom (PostGIS 2.4+)
+    #[serde(default)]
+    pub use_st_asmvtgeom: bool,
+    /// Encode layer with ST_AsMVT in PostGIS (PostGIS 2.4+, with fid_field 3.0+).
+    /// Attribute options like `attr_case` or `constant_attrs` are not applied.
+    #[serde(default)]
+    pub server_side_mvt: bool,
+    /// Add a point layer `{name}_label` with a point on the surface of each polygon
+    #[serde(default)]
+    pub label_point: bool,
+    /// Handling of attributes with reserved names like `id` (keep, prefix or drop)
+    #[serde(default)]
+    pub reserved_attr_handling: ReservedAttrHandling,
+    /// Case normalization of attribute names (lower, upper or preserve)
+    #[serde(default)]
+    pub attr_case: AttrCase,
+    /// Attributes with constant values added to every feature
+    #[serde(default)]
+    pub constant_attrs: BTreeMap<String, Value>,
+    /// Number of decimals of float attributes
+    pub attribute_precision: Option<u8>,
+    /// Feature IDs of features without fid_field value (field, auto or hash)
+    #[serde(default)]
+    pub feature_id: FeatureIdStrategy,
     // Inline style
     pub style: Option<Value>,
+    /// JSON file with layer style, used if no inline style is given
+    pub style_file: Option<String>,
+    /// Acknowledgment of data source, added to tileset attribution
+    pub attribution: Option<String>,
+}
+
+/// Simplification tolerance for all zoom levels or per zoom level range
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum ToleranceCfg {
+    Value(String),
+    Ranges(Vec<ToleranceRangeCfg>),
+}
+
+/// Curve linearization tolerance enabled with `true` or given as SQL expression
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum CurveToleranceCfg {
+    Enabled(bool),
+    Value(String),
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ToleranceRangeCfg {
+    #[serde(default)]
+    pub minzoom: u8,
+    pub maxzoom: Option<u8>,
+    pub value: String,
+}
+
+/// Handling of feature attributes named `id` or `geometry`
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ReservedAttrHandling {
+    /// Add attribute unchanged
+    #[default]
+    Keep,
+    /// Add attribute with `_` prefix
+    Prefix,
+    /// Skip attribute
+    Drop,
+}
+
+impl ReservedAttrHandling {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReservedAttrHandling::Keep => "keep",
+            ReservedAttrHandling::Prefix => "prefix",
+            ReservedAttrHandling::Drop => "drop",
+        }
+    }
+}
+
+/// Case normalization of feature attribute names
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AttrCase {
+    /// Keep attribute names as returned by the datasource
+    #[default]
+    Preserve,
+    Lower,
+    Upper,
+}
+
+impl AttrCase {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AttrCase::Preserve => "preserve",
+            AttrCase::Lower => "lower",
+            AttrCase::Upper => "upper",
+        }
+    }
+    /// Attribute name with normalized case
+    pub fn apply(&self, name: &str) -> String {
+        match self {
+            AttrCase::Preserve => name.to_string(),
+            AttrCase::Lower => name.to_lowercase(),
+            AttrCase::Upper => name.to_uppercase(),
+        }
+    }
+}
+
+/// Assignment of feature IDs
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FeatureIdStrategy {
+    /// ID from `fid_field` only
+    #[default]
+    Field,
+    /// Sequential ID within layer of tile
+    Auto,
+    /// Hash of encoded geometry and attributes
+    Hash,
+}
+
+impl FeatureIdStrategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FeatureIdStrategy::Field => "field",
+            FeatureIdStrategy::Auto => "auto",
+            FeatureIdStrategy::Hash => "hash",
+        }
+    }
+}
+
+pub const DEFAULT_COMPRESSION_LEVEL: u32 = 6;
+
+pub fn default_cache() -> bool {
+    true
+}
+
+pub fn default_enabled() -> bool {
+    true
+}
+
+pub fn default_compressed_formats() -> Vec<String> {
+    ["png", "jpg", "jpeg", "webp"]
+        .iter()
+        .map(|format| format.to_string())
+        .collect()
 }
 
 pub fn default_tile_size() -> u32 {
@@ -174,6 +458,10 @@ pub fn default_tolerance() -> String {
     DEFAULT_TOLERANCE.to_string()
 }
 
+pub fn default_tolerance_cfg() -> ToleranceCfg {
+    ToleranceCfg::Value(default_tolerance())
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct TilesetCacheCfg {
     #[serde(default)]
@@ -183,16 +471,42 @@ pub struct TilesetCacheCfg {
     pub no_cache: bool,
 }
 
+#[derive(Deserialize, Clone, Debug)]
+pub struct PlaceholderTileCfg {
+    /// Path of vector tile file (.pbf, optionally gzip compressed)
+    pub path: String,
+    #[serde(default)]
+    pub minzoom: u8,
+    pub maxzoom: u8,
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct CacheCfg {
     pub file: Option<CacheFileCfg>,
     pub s3: Option<S3CacheFileCfg>,
+    pub gcs: Option<GcsCacheCfg>,
+    pub azure: Option<AzureCacheCfg>,
+    /// In-memory LRU cache in front of file or S3 cache
+    pub memory: Option<CacheMemoryCfg>,
+    /// Gzip compression level of cached tiles (0: uncompressed, default: 6)
+    pub compression_level: Option<u32>,
 }
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct CacheFileCfg {
     pub base: String,
     pub baseurl: Option<String>,
+    /// Number of tiles buffered in memory before writing them in a batch
+    pub flush_interval: Option<usize>,
+    /// Spread tile files over subdirectories with a hash prefix (not supported with `baseurl`)
+    #[serde(default)]
+    pub shard: bool,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct CacheMemoryCfg {
+    /// Maximal total size of cached tiles in bytes
+    pub max_bytes: usize,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -205,6 +519,39 @@ pub struct S3CacheFileCfg {
     pub baseurl: Option<String>,
     pub key_prefix: Option<String>,
     pub gzip_header_enabled: Option<bool>,
+    /// Content-Type of tile objects (Default: application/vnd.mapbox-vector-tile)
+    pub content_type: Option<String>,
+    /// Cache-Control header of uploaded objects (e.g. "max-age=86400")
+    pub cache_control: Option<String>,
+}
+
+/// Google Cloud Storage cache with credentials from `GOOGLE_APPLICATION_CREDENTIALS`
+#[derive(Deserialize, Clone, Debug)]
+pub struct GcsCacheCfg {
+    pub bucket: String,
+    pub baseurl: Option<String>,
+    pub key_prefix: Option<String>,
+    pub gzip_header_enabled: Option<bool>,
+    /// Content-Type of tile objects (Default: application/vnd.mapbox-vector-tile)
+    pub content_type: Option<String>,
+    /// Cache-Control header of uploaded objects (e.g. "max-age=86400")
+    pub cache_control: Option<String>,
+}
+
+/// Azure Blob Storage cache
+#[derive(Deserialize, Clone, Debug)]
+pub struct AzureCacheCfg {
+    pub account: String,
+    pub container: String,
+    /// Storage connection string with account key. Managed identity is used if not set.
+    pub connection_string: Option<String>,
+    pub baseurl: Option<String>,
+    pub key_prefix: Option<String>,
+    pub gzip_header_enabled: Option<bool>,
+    /// Content-Type of tile objects (Default: application/vnd.mapbox-vector-tile)
+    pub content_type: Option<String>,
+    /// Cache-Control header of uploaded objects (e.g. "max-age=86400")
+    pub cache_control: Option<String>,
 }
 
 #[derive(Deserialize, Clone, Debug)]
@@ -215,10 +562,62 @@ pub struct WebserverCfg {
     // Cache-Control headers set by web server
     // https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Cache-Control#Expiration
     pub cache_control_max_age: Option<u32>,
+    /// Add `X-Tile-Generation-Ms` and `X-Tile-Cache` headers to tile responses
+    #[serde(default)]
+    pub timing_headers: bool,
+    /// Formats of already compressed content served without HTTP compression
+    #[serde(default = "default_compressed_formats")]
+    pub compressed_formats: Vec<String>,
+    /// Directory with SDF glyphs as `{fontstack}/{range}.pbf`, replacing the embedded fonts
+    pub glyphs_dir: Option<String>,
+    /// Directory with `sprite.json`, `sprite.png` and `@2x` variants
+    pub sprite_dir: Option<String>,
+    /// Structured access log format (json or clf)
+    pub access_log: Option<AccessLogFormat>,
+    /// Protection of admin endpoints
+    pub auth: Option<WebserverAuthCfg>,
+    /// Sustained requests per second per client IP
+    pub rate_limit_rps: Option<f64>,
+    /// Requests per client IP allowed in a burst (default: `rate_limit_rps`)
+    pub burst: Option<u32>,
+    /// Addresses of reverse proxies whose `Forwarded` or `X-Forwarded-For` headers
+    /// are used to identify the client IP
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
     #[serde(rename = "static", default)]
     pub static_: Vec<WebserverStaticCfg>,
 }
 
+/// Format of access log lines
+#[derive(Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessLogFormat {
+    /// JSON lines with request, tile and response fields
+    Json,
+    /// Common Log Format
+    Clf,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct WebserverAuthCfg {
+    /// Bearer token
+    pub token: Option<String>,
+    /// Basic authentication credentials
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Protected path prefixes
+    #[serde(default = "default_auth_paths")]
+    pub paths: Vec<String>,
+}
+
+pub fn default_auth_paths() -> Vec<String> {
+    vec![
+        "/metrics".to_string(),
+        "/purge".to_string(),
+        "/config".to_string(),
+    ]
+}
+
 #[derive(Deserialize, Clone, Debug)]
 pub struct WebserverStaticCfg {
     pub path: String,
@@ -264,8 +663,36 @@ pub fn read_config<'a, T: Deserialize<'a>>(path: &str) -> Result<T, String> {
     parse_config(config_toml, path)
 }
 
+/// Mask passwords of connection strings in configuration
+pub fn redact_passwords(config_toml: &str) -> String {
+    let url_password = Regex::new(r#"(://[^:/@\s"]*:)[^@\s"]*@"#).unwrap();
+    // Unquoted, single quoted (libpq) or double quoted (TOML) values
+    let kv_password =
+        Regex::new(r#"(password\s*=\s*)('(?:[^'\\]|\\.)*'|"(?:[^"\\]|\\.)*"|[^\s"']+)"#).unwrap();
+    let config_toml = url_password.replace_all(config_toml, "${1}***@");
+    kv_password
+        .replace_all(&config_toml, |caps: &Captures| {
+            let quote = match caps[2].chars().next() {
+                Some(c @ '\'') | Some(c @ '"') => c.to_string(),
+                _ => "".to_string(),
+            };
+            format!("{}{}***{}", &caps[1], quote, quote)
+        })
+        .to_string()
+}
+
 /// Parse the configuration into an config struct.
 pub fn parse_config<'a, T: Deserialize<'a>>(config_toml: String, path: &str) -> Result<T, String> {
+    let mut config = parse_toml(config_toml, path)?;
+    resolve_style_files(&mut config, path);
+    merge_includes(&mut config, path)?;
+    config
+        .try_into::<T>()
+        .map_err(|err| format!("{} - {}", path, err))
+}
+
+/// Render configuration template and parse TOML
+fn parse_toml(config_toml: String, path: &str) -> Result<Value, String> {
     // Check for old ${var} expressions
     let re = Regex::new(r"\$\{([[:alnum:]]+)\}").unwrap();
     if re.is_match(&config_toml) {
@@ -289,6 +716,85 @@ pub fn parse_config<'a, T: Deserialize<'a>>(config_toml: String, path: &str) ->
         .map_err(|e| format!("Template error: {}", e.source().unwrap()))?;
 
     toml.parse::<Value>()
-        .and_then(|cfg| cfg.try_into::<T>())
         .map_err(|err| format!("{} - {}", path, err))
 }
+
+/// Make relative `style_file` paths of layers relative to the directory of the configuration file
+fn resolve_style_files(config: &mut Value, path: &str) {
+    let basedir = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+    let tilesets = match config.get_mut("tileset") {
+        Some(Value::Array(tilesets)) => tilesets,
+        _ => return,
+    };
+    for tileset in tilesets {
+        let layers = match tileset.get_mut("layer") {
+            Some(Value::Array(layers)) => layers,
+            _ => continue,
+        };
+        for layer in layers {
+            if let Some(Value::String(style_file)) = layer.get_mut("style_file") {
+                *style_file = basedir.join(&style_file).to_string_lossy().to_string();
+            }
+        }
+    }
+}
+
+/// Add tilesets and datasources of files matching the `include` patterns.
+/// Patterns are relative to the directory of the main configuration file.
+fn merge_includes(config: &mut Value, path: &str) -> Result<(), String> {
+    let includes = match config.get("include") {
+        Some(Value::Array(includes)) => includes.clone(),
+        Some(_) => {
+            return Err(format!(
+                "{} - include: array of file patterns expected",
+                path
+            ))
+        }
+        None => return Ok(()),
+    };
+    let basedir = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+    for include in includes {
+        let pattern = include
+            .as_str()
+            .ok_or(format!("{} - include: file pattern expected", path))?;
+        let pattern = basedir.join(pattern).to_string_lossy().to_string();
+        let files = glob::glob(&pattern)
+            .map_err(|e| format!("{} - include '{}': {}", path, pattern, e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("{} - include '{}': {}", path, pattern, e))?;
+        if files.is_empty() {
+            warn!("{} - include '{}' matches no files", path, pattern);
+        }
+        for file in files {
+            let file_path = file.to_string_lossy().to_string();
+            let included_toml = fs::read_to_string(&file)
+                .map_err(|e| format!("Error while reading config {}: [{}]", file_path, e))?;
+            let mut included = parse_toml(included_toml, &file_path)?;
+            resolve_style_files(&mut included, &file_path);
+            for key in &["tileset", "datasource"] {
+                let entries = match included.get(key) {
+                    Some(Value::Array(entries)) => entries,
+                    Some(_) => return Err(format!("{} - {}: array expected", file_path, key)),
+                    None => continue,
+                };
+                let table = config.as_table_mut().unwrap();
+                match table
+                    .entry(key.to_string())
+                    .or_insert_with(|| Value::Array(Vec::new()))
+                {
+                    Value::Array(list) => list.extend(entries.iter().cloned()),
+                    _ => return Err(format!("{} - {}: array expected", path, key)),
+                }
+            }
+        }
+    }
+    if let Some(Value::Array(tilesets)) = config.get("tileset") {
+        let mut names = HashSet::new();
+        for name in tilesets.iter().filter_map(|ts| ts.get("name")?.as_str()) {
+            if !names.insert(name) {
+                return Err(format!("{} - duplicate tileset '{}'", path, name));
+            }
+        }
+    }
+    Ok(())
+}
@@ -3,7 +3,10 @@
 // Licensed under the MIT License. See LICENSE file in the project root for full license information.
 //
 
-use crate::core::geom::GeometryType;
+use crate::core::geom::{point_on_surface, GeometryType, Point};
+use crate::core::layer::Layer;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
 
 /// Supported feature attribute value types
 #[derive(Clone, PartialEq, Debug)]
@@ -18,10 +21,77 @@ pub enum FeatureAttrValType {
     VarcharArray(Vec<String>),
 }
 
+/// Layers with a logged warning about a non-integer `fid_field`
+static NON_INTEGER_FID_LAYERS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Feature id from `fid_field` value (`None`: NULL value).
+/// Only integer values are used as feature id.
+pub fn fid_from_value(layer: &Layer, value: Option<FeatureAttrValType>) -> Option<u64> {
+    match value {
+        Some(FeatureAttrValType::Int(fid)) => Some(fid as u64),
+        Some(_) => {
+            warn_non_integer_fid(layer);
+            None
+        }
+        None => None,
+    }
+}
+
+/// Log a warning about a non-integer `fid_field` once per layer.
+/// Returns true, if the warning was logged.
+pub fn warn_non_integer_fid(layer: &Layer) -> bool {
+    let mut layers = NON_INTEGER_FID_LAYERS.lock().unwrap();
+    if layers.insert(layer.name.clone()) {
+        warn!(
+            "Layer '{}': fid_field '{}' is not an integer field - feature ids are omitted",
+            layer.name,
+            layer.fid_field.as_ref().map(|s| s.as_str()).unwrap_or("")
+        );
+        true
+    } else {
+        false
+    }
+}
+
 pub trait Feature {
     fn fid(&self) -> Option<u64>;
     fn attributes(&self) -> Vec<FeatureAttr>; //TODO: return tuples
     fn geometry(&self) -> Result<GeometryType, String>;
+    /// Point on the surface of a polygon feature
+    fn label_point(&self) -> Option<Point> {
+        point_on_surface(&self.geometry().ok()?)
+    }
+}
+
+/// Label point of a polygon feature with the attributes of the polygon
+pub struct LabelPointFeature {
+    fid: Option<u64>,
+    attributes: Vec<FeatureAttr>,
+    point: Point,
+}
+
+impl LabelPointFeature {
+    /// None for non-polygon features
+    pub fn from_feature(feature: &dyn Feature) -> Option<LabelPointFeature> {
+        let point = feature.label_point()?;
+        Some(LabelPointFeature {
+            fid: feature.fid(),
+            attributes: feature.attributes(),
+            point,
+        })
+    }
+}
+
+impl Feature for LabelPointFeature {
+    fn fid(&self) -> Option<u64> {
+        self.fid
+    }
+    fn attributes(&self) -> Vec<FeatureAttr> {
+        self.attributes.clone()
+    }
+    fn geometry(&self) -> Result<GeometryType, String> {
+        Ok(GeometryType::Point(self.point))
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -0,0 +1,37 @@
+//
+// Copyright (c) Pirmin Kalberer. All rights reserved.
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+//
+
+use crate::core::feature::{fid_from_value, warn_non_integer_fid, FeatureAttrValType};
+use crate::core::layer::Layer;
+
+#[test]
+fn test_integer_fid() {
+    let mut layer = Layer::new("integer_fid");
+    layer.fid_field = Some("id".to_string());
+    assert_eq!(
+        fid_from_value(&layer, Some(FeatureAttrValType::Int(42))),
+        Some(42)
+    );
+    // NULL value
+    assert_eq!(fid_from_value(&layer, None), None);
+    // No warning logged yet
+    assert!(warn_non_integer_fid(&layer));
+}
+
+#[test]
+fn test_string_fid() {
+    let mut layer = Layer::new("string_fid");
+    layer.fid_field = Some("code".to_string());
+    assert_eq!(
+        fid_from_value(&layer, Some(FeatureAttrValType::String("42".to_string()))),
+        None
+    );
+    // Warning is logged only once per layer
+    assert!(!warn_non_integer_fid(&layer));
+    assert_eq!(
+        fid_from_value(&layer, Some(FeatureAttrValType::Double(42.0))),
+        None
+    );
+}
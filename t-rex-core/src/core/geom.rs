@@ -39,4 +39,249 @@ impl GeometryType {
             _ => false,
         }
     }
+    /// Geometry type name like PostGIS `GeometryType`
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            GeometryType::Point(_) => "POINT",
+            GeometryType::LineString(_) => "LINESTRING",
+            GeometryType::Polygon(_) => "POLYGON",
+            GeometryType::MultiPoint(_) => "MULTIPOINT",
+            GeometryType::MultiLineString(_) => "MULTILINESTRING",
+            GeometryType::MultiPolygon(_) => "MULTIPOLYGON",
+            GeometryType::GeometryCollection(_) => "GEOMETRYCOLLECTION",
+            GeometryType::Geometry(g) => GeometryType::from_geometry(g).type_name(),
+        }
+    }
+    /// Well-known text representation
+    pub fn to_wkt(&self) -> String {
+        match self {
+            &GeometryType::Point(ref g) => format!("POINT({})", wkt_point(g)),
+            &GeometryType::LineString(ref g) => wkt_tagged("LINESTRING", wkt_points(&g.points)),
+            &GeometryType::Polygon(ref g) => wkt_tagged("POLYGON", wkt_rings(&g.rings)),
+            &GeometryType::MultiPoint(ref g) => wkt_tagged("MULTIPOINT", wkt_points(&g.points)),
+            &GeometryType::MultiLineString(ref g) => {
+                wkt_tagged("MULTILINESTRING", wkt_rings(&g.lines))
+            }
+            &GeometryType::MultiPolygon(ref g) => wkt_tagged(
+                "MULTIPOLYGON",
+                g.polygons
+                    .iter()
+                    .map(|p| format!("({})", wkt_rings(&p.rings)))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            &GeometryType::GeometryCollection(ref g) => wkt_tagged(
+                "GEOMETRYCOLLECTION",
+                g.geometries
+                    .iter()
+                    .map(|g| GeometryType::from_geometry(g).to_wkt())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            ),
+            &GeometryType::Geometry(ref g) => GeometryType::from_geometry(g).to_wkt(),
+        }
+    }
+    /// GeoJSON geometry object
+    pub fn to_geojson(&self) -> serde_json::Value {
+        match self {
+            &GeometryType::Point(ref g) => json!({"type": "Point", "coordinates": [g.x, g.y]}),
+            &GeometryType::LineString(ref g) => {
+                json!({"type": "LineString", "coordinates": json_points(&g.points)})
+            }
+            &GeometryType::Polygon(ref g) => {
+                json!({"type": "Polygon", "coordinates": json_rings(&g.rings)})
+            }
+            &GeometryType::MultiPoint(ref g) => {
+                json!({"type": "MultiPoint", "coordinates": json_points(&g.points)})
+            }
+            &GeometryType::MultiLineString(ref g) => {
+                json!({"type": "MultiLineString", "coordinates": json_rings(&g.lines)})
+            }
+            &GeometryType::MultiPolygon(ref g) => json!({
+                "type": "MultiPolygon",
+                "coordinates": g.polygons.iter().map(|p| json_rings(&p.rings)).collect::<Vec<_>>()
+            }),
+            &GeometryType::GeometryCollection(ref g) => json!({
+                "type": "GeometryCollection",
+                "geometries": g.geometries
+                    .iter()
+                    .map(|g| GeometryType::from_geometry(g).to_geojson())
+                    .collect::<Vec<_>>()
+            }),
+            &GeometryType::Geometry(ref g) => GeometryType::from_geometry(g).to_geojson(),
+        }
+    }
+    /// GeoJSON geometry object of WGS84 coordinates with lines crossing the
+    /// antimeridian split at ±180 (RFC 7946, 3.1.9). Polygons are not split.
+    pub fn to_geojson_antimeridian_split(&self) -> serde_json::Value {
+        match self {
+            &GeometryType::LineString(ref g) => {
+                let lines = split_antimeridian(&g.points);
+                if lines.len() > 1 {
+                    json!({"type": "MultiLineString", "coordinates": lines})
+                } else {
+                    self.to_geojson()
+                }
+            }
+            &GeometryType::MultiLineString(ref g) => {
+                let lines = g
+                    .lines
+                    .iter()
+                    .flat_map(|l| split_antimeridian(&l.points))
+                    .collect::<Vec<_>>();
+                json!({"type": "MultiLineString", "coordinates": lines})
+            }
+            &GeometryType::GeometryCollection(ref g) => json!({
+                "type": "GeometryCollection",
+                "geometries": g.geometries
+                    .iter()
+                    .map(|g| GeometryType::from_geometry(g).to_geojson_antimeridian_split())
+                    .collect::<Vec<_>>()
+            }),
+            &GeometryType::Geometry(ref g) => {
+                GeometryType::from_geometry(g).to_geojson_antimeridian_split()
+            }
+            _ => self.to_geojson(),
+        }
+    }
+    fn from_geometry(geom: &Geometry) -> GeometryType {
+        match geom.clone() {
+            ewkb::GeometryT::Point(g) => GeometryType::Point(g),
+            ewkb::GeometryT::LineString(g) => GeometryType::LineString(g),
+            ewkb::GeometryT::Polygon(g) => GeometryType::Polygon(g),
+            ewkb::GeometryT::MultiPoint(g) => GeometryType::MultiPoint(g),
+            ewkb::GeometryT::MultiLineString(g) => GeometryType::MultiLineString(g),
+            ewkb::GeometryT::MultiPolygon(g) => GeometryType::MultiPolygon(g),
+            ewkb::GeometryT::GeometryCollection(g) => GeometryType::GeometryCollection(g),
+        }
+    }
+}
+
+/// Point in the interior of a (multi)polygon, comparable to `ST_PointOnSurface`.
+/// The point is the midpoint of the widest section of a horizontal line
+/// through the middle of the polygon, avoiding vertices.
+pub fn point_on_surface(geom: &GeometryType) -> Option<Point> {
+    match geom {
+        GeometryType::Polygon(g) => polygon_interior_section(g).map(|(pt, _)| pt),
+        GeometryType::MultiPolygon(g) => g
+            .polygons
+            .iter()
+            .filter_map(polygon_interior_section)
+            .fold(
+                None,
+                |widest: Option<(Point, f64)>, (pt, width)| match widest {
+                    Some((_, w)) if w >= width => widest,
+                    _ => Some((pt, width)),
+                },
+            )
+            .map(|(pt, _)| pt),
+        GeometryType::Geometry(g) => point_on_surface(&GeometryType::from_geometry(g)),
+        _ => None,
+    }
+}
+
+/// Midpoint and width of the widest interior section of a scan line
+fn polygon_interior_section(polygon: &Polygon) -> Option<(Point, f64)> {
+    let shell = &polygon.rings.first()?.points;
+    let miny = shell.iter().map(|p| p.y).fold(f64::INFINITY, f64::min);
+    let maxy = shell.iter().map(|p| p.y).fold(f64::NEG_INFINITY, f64::max);
+    let centre_y = (miny + maxy) / 2.0;
+    // Scan line between the nearest vertices below and above the centre
+    let (mut lo_y, mut hi_y) = (miny, maxy);
+    for p in polygon.rings.iter().flat_map(|r| r.points.iter()) {
+        if p.y <= centre_y && p.y > lo_y {
+            lo_y = p.y;
+        } else if p.y > centre_y && p.y < hi_y {
+            hi_y = p.y;
+        }
+    }
+    let scan_y = (lo_y + hi_y) / 2.0;
+    let mut xs: Vec<f64> = polygon
+        .rings
+        .iter()
+        .flat_map(|r| r.points.windows(2))
+        .filter(|seg| (seg[0].y <= scan_y) != (seg[1].y <= scan_y))
+        .map(|seg| seg[0].x + (scan_y - seg[0].y) * (seg[1].x - seg[0].x) / (seg[1].y - seg[0].y))
+        .collect();
+    xs.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    xs.chunks_exact(2)
+        .map(|section| (section[0], section[1] - section[0]))
+        .fold(
+            None,
+            |widest: Option<(f64, f64)>, (x, width)| match widest {
+                Some((_, w)) if w >= width => widest,
+                _ => Some((x, width)),
+            },
+        )
+        .filter(|&(_, width)| width > 0.0)
+        .map(|(x, width)| {
+            let pt = Point {
+                x: x + width / 2.0,
+                y: scan_y,
+                srid: polygon.srid,
+            };
+            (pt, width)
+        })
+}
+
+fn wkt_tagged(tag: &str, content: String) -> String {
+    if content.is_empty() {
+        format!("{} EMPTY", tag)
+    } else {
+        format!("{}({})", tag, content)
+    }
+}
+
+fn wkt_point(point: &Point) -> String {
+    format!("{} {}", point.x, point.y)
+}
+
+fn wkt_points(points: &[Point]) -> String {
+    points.iter().map(wkt_point).collect::<Vec<_>>().join(",")
+}
+
+fn wkt_rings(rings: &[LineString]) -> String {
+    rings
+        .iter()
+        .map(|r| format!("({})", wkt_points(&r.points)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn json_points(points: &[Point]) -> Vec<[f64; 2]> {
+    points.iter().map(|p| [p.x, p.y]).collect()
+}
+
+fn json_rings(rings: &[LineString]) -> Vec<Vec<[f64; 2]>> {
+    rings.iter().map(|r| json_points(&r.points)).collect()
+}
+
+/// Split line into parts where a segment crosses the antimeridian.
+/// Segments with a longitude difference of more than 180° are treated as crossing.
+fn split_antimeridian(points: &[Point]) -> Vec<Vec<[f64; 2]>> {
+    let mut lines = Vec::new();
+    let mut line: Vec<[f64; 2]> = Vec::new();
+    for (i, p) in points.iter().enumerate() {
+        if i > 0 {
+            let prev = &points[i - 1];
+            if (p.x - prev.x).abs() > 180.0 {
+                // Crossing longitude seen from the previous point
+                let (cross_x, shifted_x) = if prev.x > 0.0 {
+                    (180.0, p.x + 360.0)
+                } else {
+                    (-180.0, p.x - 360.0)
+                };
+                let t = (cross_x - prev.x) / (shifted_x - prev.x);
+                let y = prev.y + t * (p.y - prev.y);
+                line.push([cross_x, y]);
+                lines.push(line);
+                line = vec![[-cross_x, y]];
+            }
+        }
+        line.push([p.x, p.y]);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
 }
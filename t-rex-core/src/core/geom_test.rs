@@ -24,3 +24,159 @@ fn test_geom_creation() {
     };
     assert_eq!(p.x, 960000.0);
 }
+
+#[test]
+fn test_wkt_geojson() {
+    let point = GeometryType::new_point(960000.0, 6002729.5);
+    assert_eq!(point.to_wkt(), "POINT(960000 6002729.5)");
+    assert_eq!(
+        point.to_geojson().to_string(),
+        r#"{"coordinates":[960000.0,6002729.5],"type":"Point"}"#
+    );
+
+    let ring = |coords: &[(f64, f64)]| ewkb::LineString {
+        points: coords
+            .iter()
+            .map(|&(x, y)| Point::new(x, y, None))
+            .collect(),
+        srid: None,
+    };
+    let polygon = ewkb::Polygon {
+        rings: vec![
+            ring(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 0.0)]),
+            ring(&[(1.0, 1.0), (2.0, 1.0), (2.0, 2.0), (1.0, 1.0)]),
+        ],
+        srid: None,
+    };
+    let multipolygon = GeometryType::MultiPolygon(ewkb::MultiPolygon {
+        polygons: vec![polygon.clone()],
+        srid: None,
+    });
+    let polygon = GeometryType::Polygon(polygon);
+    assert_eq!(
+        polygon.to_wkt(),
+        "POLYGON((0 0,10 0,10 10,0 0),(1 1,2 1,2 2,1 1))"
+    );
+    assert_eq!(
+        multipolygon.to_wkt(),
+        "MULTIPOLYGON(((0 0,10 0,10 10,0 0),(1 1,2 1,2 2,1 1)))"
+    );
+    assert_eq!(
+        polygon.to_geojson().to_string(),
+        r#"{"coordinates":[[[0.0,0.0],[10.0,0.0],[10.0,10.0],[0.0,0.0]],[[1.0,1.0],[2.0,1.0],[2.0,2.0],[1.0,1.0]]],"type":"Polygon"}"#
+    );
+
+    let line = GeometryType::LineString(ring(&[(1.0, 2.0), (3.0, 4.0)]));
+    assert_eq!(line.to_wkt(), "LINESTRING(1 2,3 4)");
+    let empty = GeometryType::MultiPoint(ewkb::MultiPoint {
+        points: Vec::new(),
+        srid: None,
+    });
+    assert_eq!(empty.to_wkt(), "MULTIPOINT EMPTY");
+    assert_eq!(
+        empty.to_geojson().to_string(),
+        r#"{"coordinates":[],"type":"MultiPoint"}"#
+    );
+}
+
+#[test]
+fn test_geojson_antimeridian_split() {
+    let line = |coords: &[(f64, f64)]| ewkb::LineString {
+        points: coords
+            .iter()
+            .map(|&(x, y)| Point::new(x, y, None))
+            .collect(),
+        srid: None,
+    };
+    // Eastwards across the antimeridian
+    let crossing = GeometryType::LineString(line(&[(170.0, 0.0), (-170.0, 10.0), (-160.0, 10.0)]));
+    assert_eq!(
+        crossing.to_geojson_antimeridian_split().to_string(),
+        r#"{"coordinates":[[[170.0,0.0],[180.0,5.0]],[[-180.0,5.0],[-170.0,10.0],[-160.0,10.0]]],"type":"MultiLineString"}"#
+    );
+    // Westwards
+    let crossing = GeometryType::LineString(line(&[(-175.0, 0.0), (175.0, 10.0)]));
+    assert_eq!(
+        crossing.to_geojson_antimeridian_split().to_string(),
+        r#"{"coordinates":[[[-175.0,0.0],[-180.0,5.0]],[[180.0,5.0],[175.0,10.0]]],"type":"MultiLineString"}"#
+    );
+    // Unchanged without crossing
+    let line = GeometryType::LineString(line(&[(170.0, 0.0), (-10.0, 10.0)]));
+    assert_eq!(line.to_geojson_antimeridian_split(), line.to_geojson());
+}
+
+#[test]
+fn test_point_on_surface() {
+    use crate::core::geom::point_on_surface;
+
+    let ring = |coords: &[(f64, f64)]| ewkb::LineString {
+        points: coords
+            .iter()
+            .map(|&(x, y)| Point::new(x, y, Some(3857)))
+            .collect(),
+        srid: Some(3857),
+    };
+    let polygon = |rings: Vec<ewkb::LineString>| ewkb::Polygon {
+        rings,
+        srid: Some(3857),
+    };
+
+    let square = polygon(vec![ring(&[
+        (0.0, 0.0),
+        (10.0, 0.0),
+        (10.0, 10.0),
+        (0.0, 10.0),
+        (0.0, 0.0),
+    ])]);
+    let pt = point_on_surface(&GeometryType::Polygon(square.clone())).unwrap();
+    assert_eq!((pt.x, pt.y, pt.srid), (5.0, 5.0, Some(3857)));
+
+    // U-shape with centroid outside of the polygon
+    let u_shape = polygon(vec![ring(&[
+        (0.0, 0.0),
+        (10.0, 0.0),
+        (10.0, 10.0),
+        (8.0, 10.0),
+        (8.0, 2.0),
+        (2.0, 2.0),
+        (2.0, 10.0),
+        (0.0, 10.0),
+        (0.0, 0.0),
+    ])]);
+    let pt = point_on_surface(&GeometryType::Polygon(u_shape)).unwrap();
+    assert_eq!((pt.x, pt.y), (1.0, 6.0));
+
+    // Hole in the middle
+    let with_hole = polygon(vec![
+        ring(&[
+            (0.0, 0.0),
+            (10.0, 0.0),
+            (10.0, 10.0),
+            (0.0, 10.0),
+            (0.0, 0.0),
+        ]),
+        ring(&[(2.0, 4.0), (7.0, 4.0), (7.0, 6.0), (2.0, 6.0), (2.0, 4.0)]),
+    ]);
+    let pt = point_on_surface(&GeometryType::Polygon(with_hole.clone())).unwrap();
+    assert_eq!((pt.x, pt.y), (8.5, 5.0));
+
+    // Widest member of multipolygon
+    let small = polygon(vec![ring(&[
+        (20.0, 0.0),
+        (21.0, 0.0),
+        (21.0, 1.0),
+        (20.0, 1.0),
+        (20.0, 0.0),
+    ])]);
+    let multipolygon = GeometryType::MultiPolygon(ewkb::MultiPolygon {
+        polygons: vec![small, square],
+        srid: Some(3857),
+    });
+    let pt = point_on_surface(&multipolygon).unwrap();
+    assert_eq!((pt.x, pt.y), (5.0, 5.0));
+
+    assert!(point_on_surface(&GeometryType::new_point(1.0, 1.0)).is_none());
+    assert!(point_on_surface(&GeometryType::LineString(ring(&[(0.0, 0.0), (1.0, 1.0)]))).is_none());
+    let empty = polygon(Vec::new());
+    assert!(point_on_surface(&GeometryType::Polygon(empty)).is_none());
+}
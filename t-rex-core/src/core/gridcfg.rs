@@ -5,6 +5,8 @@
 
 use crate::core::config::GridCfg;
 use crate::core::Config;
+use serde_json::Value;
+use std::f64::consts;
 use tile_grid::{Extent, Grid, Origin, Unit};
 
 #[derive(Deserialize, Clone, Debug)]
@@ -34,6 +36,8 @@ impl<'a> Config<'a, GridCfg> for Grid {
                 "web_mercator" => Ok(Grid::web_mercator()),
                 _ => Err(format!("Unkown grid '{}'", gridname)),
             }
+        } else if let Some(epsg) = grid_cfg.epsg {
+            Grid::from_epsg(epsg)
         } else if let Some(ref usergrid) = grid_cfg.user {
             let units = match &usergrid.units.to_lowercase() as &str {
                 "m" => Ok(Unit::Meters),
@@ -46,6 +50,7 @@ impl<'a> Config<'a, GridCfg> for Grid {
                 "BottomLeft" => Ok(Origin::BottomLeft),
                 _ => Err(format!("Unexpected enum value '{}'", usergrid.origin)),
             };
+            check_resolutions(&usergrid.resolutions)?;
             let grid = Grid::new(
                 usergrid.width,
                 usergrid.height,
@@ -55,6 +60,13 @@ impl<'a> Config<'a, GridCfg> for Grid {
                 usergrid.resolutions.clone(),
                 origin?,
             );
+            if grid.maxzoom() as usize != usergrid.resolutions.len() - 1 {
+                return Err(format!(
+                    "Grid resolutions: {} zoom levels exceed the maximal number of {}",
+                    usergrid.resolutions.len(),
+                    u8::MAX as usize + 1
+                ));
+            }
             Ok(grid)
         } else {
             Err("Invalid grid definition".to_string())
@@ -68,3 +80,179 @@ predefined = "web_mercator"
         toml.to_string()
     }
 }
+
+/// Resolutions must be positive and ordered from largest to smallest
+fn check_resolutions(resolutions: &[f64]) -> Result<(), String> {
+    if resolutions.is_empty() {
+        return Err("Grid resolutions: list is empty".to_string());
+    }
+    if let Some(res) = resolutions.iter().find(|res| res.is_nan() || **res <= 0.0) {
+        return Err(format!("Grid resolutions: invalid resolution {}", res));
+    }
+    if let Some(zoom) = (1..resolutions.len()).find(|&z| resolutions[z] >= resolutions[z - 1]) {
+        return Err(format!(
+            "Grid resolutions: not strictly decreasing at zoom level {} ({} >= {})",
+            zoom,
+            resolutions[zoom],
+            resolutions[zoom - 1]
+        ));
+    }
+    Ok(())
+}
+
+/// Default grids for common coordinate reference systems
+pub trait EpsgGrid: Sized {
+    fn from_epsg(code: i32) -> Result<Self, String>;
+}
+
+/// Resolutions of `levels` zoom levels, halved at each level
+fn halving_resolutions(resolution: f64, levels: i32) -> Vec<f64> {
+    (0..levels).map(|z| resolution / 2f64.powi(z)).collect()
+}
+
+impl EpsgGrid for Grid {
+    fn from_epsg(code: i32) -> Result<Self, String> {
+        match code {
+            3857 => Ok(Grid::web_mercator()),
+            4326 => Ok(Grid::wgs84()),
+            // Swiss LV95 (swisstopo WMTS)
+            2056 => Ok(Grid::new(
+                256,
+                256,
+                Extent {
+                    minx: 2420000.0,
+                    miny: 1030000.0,
+                    maxx: 2900000.0,
+                    maxy: 1350000.0,
+                },
+                2056,
+                Unit::Meters,
+                vec![
+                    4000.0, 3750.0, 3500.0, 3250.0, 3000.0, 2750.0, 2500.0, 2250.0, 2000.0, 1750.0,
+                    1500.0, 1250.0, 1000.0, 750.0, 650.0, 500.0, 250.0, 100.0, 50.0, 20.0, 10.0,
+                    5.0, 2.5, 2.0, 1.5, 1.0, 0.5, 0.25, 0.1,
+                ],
+                Origin::TopLeft,
+            )),
+            // ETRS89 / UTM zone 32N (AdV WMTS)
+            25832 => Ok(Grid::new(
+                256,
+                256,
+                Extent {
+                    minx: -46133.17,
+                    miny: 5048875.26857567,
+                    maxx: 1206211.10142433,
+                    maxy: 6301219.54,
+                },
+                25832,
+                Unit::Meters,
+                halving_resolutions(4891.96981025128, 18),
+                Origin::TopLeft,
+            )),
+            // ETRS89-LAEA Europe
+            3035 => Ok(Grid::new(
+                256,
+                256,
+                Extent {
+                    minx: 2000000.0,
+                    miny: 1000000.0,
+                    maxx: 6500000.0,
+                    maxy: 5500000.0,
+                },
+                3035,
+                Unit::Meters,
+                halving_resolutions(4500000.0 / 256.0, 20),
+                Origin::TopLeft,
+            )),
+            _ => Err(format!("No default grid for EPSG:{}", code)),
+        }
+    }
+}
+
+/// Returns (lon, lat) of Spherical Mercator (x, y) in meters
+pub fn merc_to_lonlat(x: f64, y: f64) -> (f64, f64) {
+    let lon = (x / 6378137.0).to_degrees();
+    let lat = (2.0 * (y / 6378137.0).exp().atan() - consts::PI * 0.5).to_degrees();
+    (lon, lat)
+}
+
+/// Geographic extent of Spherical Mercator extent (inverse of `extent_wgs84_to_merc`)
+pub fn extent_merc_to_wgs84(extent: &Extent) -> Extent {
+    let (minx, miny) = merc_to_lonlat(extent.minx, extent.miny);
+    let (maxx, maxy) = merc_to_lonlat(extent.maxx, extent.maxy);
+    Extent {
+        minx,
+        miny,
+        maxx,
+        maxy,
+    }
+}
+
+pub trait Wgs84Bounds {
+    /// WGS84 bounds of tile in TMS adressing scheme
+    fn tile_bbox_wgs84(&self, xtile: u32, ytile: u32, zoom: u8) -> Extent;
+}
+
+impl Wgs84Bounds for Grid {
+    fn tile_bbox_wgs84(&self, xtile: u32, ytile: u32, zoom: u8) -> Extent {
+        let extent = self.tile_extent(xtile, ytile, zoom);
+        match self.srid {
+            3857 => extent_merc_to_wgs84(&extent),
+            4326 => extent,
+            _ => {
+                warn!(
+                    "Tile bounds of grid with SRID {} not reprojected to WGS84",
+                    self.srid
+                );
+                extent
+            }
+        }
+    }
+}
+
+/// OGC Two Dimensional Tile Matrix Set (https://docs.ogc.org/is/17-083r4/17-083r4.html)
+pub trait TileMatrixSet {
+    fn as_tilematrixset_json(&self) -> Value;
+}
+
+impl TileMatrixSet for Grid {
+    fn as_tilematrixset_json(&self) -> Value {
+        const METERS_PER_DEGREE: f64 = 6378137.0 * 2.0 * consts::PI / 360.0;
+        let (corner, origin) = match self.origin {
+            Origin::TopLeft => ("topLeft", [self.extent.minx, self.extent.maxy]),
+            Origin::BottomLeft => ("bottomLeft", [self.extent.minx, self.extent.miny]),
+        };
+        let limits = self.tile_limits(self.extent.clone(), 0);
+        let matrices: Vec<Value> = (0..self.nlevels())
+            .map(|zoom| {
+                // Resolution in grid units, derived from pixel width in meters
+                let cell_size = match self.units {
+                    Unit::Meters => self.pixel_width(zoom),
+                    Unit::Degrees => self.pixel_width(zoom) / METERS_PER_DEGREE,
+                    Unit::Feet => self.pixel_width(zoom) / 0.3048,
+                };
+                let tile = self.tile_extent(0, 0, zoom);
+                let level = &limits[zoom as usize];
+                json!({
+                    "id": zoom.to_string(),
+                    "scaleDenominator": self.scale_denominator(zoom),
+                    "cellSize": cell_size,
+                    "cornerOfOrigin": corner,
+                    "pointOfOrigin": origin,
+                    "tileWidth": ((tile.maxx - tile.minx) / cell_size).round() as u32,
+                    "tileHeight": ((tile.maxy - tile.miny) / cell_size).round() as u32,
+                    "matrixWidth": level.maxx - level.minx,
+                    "matrixHeight": level.maxy - level.miny,
+                })
+            })
+            .collect();
+        json!({
+            "crs": format!("http://www.opengis.net/def/crs/EPSG/0/{}", self.srid),
+            "boundingBox": {
+                "lowerLeft": [self.extent.minx, self.extent.miny],
+                "upperRight": [self.extent.maxx, self.extent.maxy],
+            },
+            "tileMatrices": matrices,
+        })
+    }
+}
@@ -74,3 +74,267 @@ fn test_grid_from_config() {
         }
     );
 }
+
+#[test]
+fn test_tilematrixset_json() {
+    use crate::core::parse_config;
+    use crate::core::TileMatrixSet;
+
+    let toml = r#"
+        [user]
+        width = 256
+        height = 256
+        extent = { minx = 2420000.0, miny = 1030000.0, maxx = 2900000.0, maxy = 1350000.0 }
+        srid = 2056
+        units = "m"
+        resolutions = [4000.0,3750.0,3500.0,3250.0,3000.0,2750.0,2500.0,2250.0,2000.0,1750.0,1500.0,1250.0,1000.0,750.0,650.0,500.0,250.0,100.0,50.0,20.0,10.0,5.0,2.5,2.0,1.5,1.0,0.5,0.25,0.1]
+        origin = "TopLeft"
+        "#;
+    let config: GridCfg = parse_config(toml.to_string(), "").unwrap();
+    let grid = Grid::from_config(&config).unwrap();
+    let tms = grid.as_tilematrixset_json();
+    assert_eq!(tms["crs"], "http://www.opengis.net/def/crs/EPSG/0/2056");
+    assert_eq!(
+        tms["boundingBox"]["upperRight"],
+        json!([2900000.0, 1350000.0])
+    );
+    let matrices = tms["tileMatrices"].as_array().unwrap();
+    assert_eq!(matrices.len(), 29);
+    assert_eq!(
+        matrices[0],
+        json!({
+            "id": "0",
+            "scaleDenominator": 4000.0 / 0.00028,
+            "cellSize": 4000.0,
+            "cornerOfOrigin": "topLeft",
+            "pointOfOrigin": [2420000.0, 1350000.0],
+            "tileWidth": 256,
+            "tileHeight": 256,
+            "matrixWidth": 1,
+            "matrixHeight": 1,
+        })
+    );
+    assert_eq!(matrices[16]["id"], "16");
+    assert_eq!(matrices[16]["cellSize"], 250.0);
+    assert_eq!(matrices[16]["matrixWidth"], 8);
+    assert_eq!(matrices[16]["matrixHeight"], 5);
+
+    let tms = Grid::wgs84().as_tilematrixset_json();
+    let matrices = tms["tileMatrices"].as_array().unwrap();
+    assert_eq!(matrices[0]["cornerOfOrigin"], "bottomLeft");
+    assert_eq!(matrices[0]["tileWidth"], 256);
+    assert_eq!(matrices[1]["matrixWidth"], 4);
+    assert_eq!(matrices[1]["matrixHeight"], 2);
+}
+
+#[test]
+fn test_grid_origin() {
+    use crate::core::parse_config;
+
+    let grid = |origin: &str| {
+        let toml = format!(
+            r#"
+            [user]
+            width = 256
+            height = 256
+            extent = {{ minx = 0.0, miny = 0.0, maxx = 1024.0, maxy = 512.0 }}
+            srid = 2056
+            units = "m"
+            resolutions = [2.0, 1.0]
+            origin = "{}"
+            "#,
+            origin
+        );
+        let config: GridCfg = parse_config(toml, "").unwrap();
+        Grid::from_config(&config).unwrap()
+    };
+    let top_left = grid("TopLeft");
+    let bottom_left = grid("BottomLeft");
+    assert_eq!(top_left.origin, Origin::TopLeft);
+    assert_eq!(bottom_left.origin, Origin::BottomLeft);
+
+    // Top row
+    let extent = Extent {
+        minx: 256.0,
+        miny: 256.0,
+        maxx: 512.0,
+        maxy: 512.0,
+    };
+    assert_eq!(top_left.tile_extent(1, 0, 1), extent);
+    assert_eq!(bottom_left.tile_extent(1, 1, 1), extent);
+    // Same tile with row counted from the other side
+    for (x, y) in vec![(0, 0), (3, 0), (2, 1)] {
+        assert_eq!(
+            top_left.tile_extent(x, y, 1),
+            bottom_left.tile_extent(x, bottom_left.ytile_from_xyz(y, 1), 1)
+        );
+        assert_eq!(
+            top_left.tile_extent(x, y, 1),
+            bottom_left.tile_extent_xyz(x, y, 1)
+        );
+    }
+    // Both origins cover the same tiles
+    assert_eq!(
+        top_left.tile_limits(top_left.extent.clone(), 0),
+        bottom_left.tile_limits(bottom_left.extent.clone(), 0)
+    );
+}
+
+#[test]
+fn test_grid_from_epsg() {
+    use crate::core::parse_config;
+    use crate::core::EpsgGrid;
+
+    // Swiss grid of the tile-grid crate documentation
+    let grid = Grid::from_epsg(2056).unwrap();
+    assert_eq!(grid.srid, 2056);
+    assert_eq!(grid.origin, Origin::TopLeft);
+    assert_eq!(
+        grid.tile_extent(0, 0, 15),
+        Extent {
+            minx: 2420000.0,
+            miny: 1222000.0,
+            maxx: 2548000.0,
+            maxy: 1350000.0,
+        }
+    );
+
+    let grid = Grid::from_epsg(25832).unwrap();
+    let extent = grid.tile_extent(0, 0, 0);
+    assert!((extent.maxx - grid.extent.maxx).abs() < 0.01);
+    assert!((extent.miny - grid.extent.miny).abs() < 0.01);
+    assert_eq!(grid.nlevels(), 18);
+
+    let grid = Grid::from_epsg(3035).unwrap();
+    assert_eq!(grid.tile_extent(0, 0, 0), grid.extent);
+    assert_eq!(grid.pixel_width(1), 4500000.0 / 512.0);
+
+    assert_eq!(
+        Grid::from_epsg(3857).unwrap().extent,
+        Grid::web_mercator().extent
+    );
+    assert_eq!(
+        Grid::from_epsg(21781).err(),
+        Some("No default grid for EPSG:21781".to_string())
+    );
+
+    let toml = r#"
+        #[grid]
+        epsg = 2056
+        "#;
+    let config: GridCfg = parse_config(toml.to_string(), "").unwrap();
+    let grid = Grid::from_config(&config).unwrap();
+    assert_eq!(grid.srid, 2056);
+    assert_eq!(grid.nlevels(), 29);
+}
+
+#[test]
+fn test_invalid_resolutions() {
+    use crate::core::parse_config;
+
+    let grid = |resolutions: &str| {
+        let toml = format!(
+            r#"
+            [user]
+            width = 256
+            height = 256
+            extent = {{ minx = 0.0, miny = 0.0, maxx = 1024.0, maxy = 512.0 }}
+            srid = 2056
+            units = "m"
+            resolutions = {}
+            origin = "TopLeft"
+            "#,
+            resolutions
+        );
+        let config: GridCfg = parse_config(toml, "").unwrap();
+        Grid::from_config(&config)
+    };
+    assert!(grid("[4.0, 2.0, 1.0]").is_ok());
+    assert_eq!(
+        grid("[]").err(),
+        Some("Grid resolutions: list is empty".to_string())
+    );
+    assert_eq!(
+        grid("[4.0, 2.0, 2.0, 1.0]").err(),
+        Some("Grid resolutions: not strictly decreasing at zoom level 2 (2 >= 2)".to_string())
+    );
+    assert_eq!(
+        grid("[1.0, 2.0, 4.0]").err(),
+        Some("Grid resolutions: not strictly decreasing at zoom level 1 (2 >= 1)".to_string())
+    );
+    assert_eq!(
+        grid("[2.0, 0.0]").err(),
+        Some("Grid resolutions: invalid resolution 0".to_string())
+    );
+    let too_many: Vec<String> = (0..300).map(|z| format!("{}.0", 1000 - z)).collect();
+    assert_eq!(
+        grid(&format!("[{}]", too_many.join(","))).err(),
+        Some("Grid resolutions: 300 zoom levels exceed the maximal number of 256".to_string())
+    );
+}
+
+#[test]
+fn test_tile_bbox_wgs84() {
+    use crate::core::Wgs84Bounds;
+
+    let grid = Grid::web_mercator();
+    let bbox = grid.tile_bbox_wgs84(2, 2, 2);
+    assert_eq!(bbox.minx, 0.0);
+    assert_eq!(bbox.miny, 0.0);
+    assert!((bbox.maxx - 90.0).abs() < 1e-9);
+    assert!((bbox.maxy - 66.51326044311186).abs() < 1e-9);
+    let bbox = grid.tile_bbox_wgs84(0, 0, 0);
+    assert!((bbox.minx + 180.0).abs() < 1e-9);
+    assert!((bbox.maxy - 85.0511287798066).abs() < 1e-9);
+
+    let grid = Grid::wgs84();
+    assert_eq!(grid.tile_bbox_wgs84(0, 0, 0), grid.tile_extent(0, 0, 0));
+}
+
+#[test]
+fn test_extent_merc_to_wgs84() {
+    use crate::core::extent_merc_to_wgs84;
+    use tile_grid::extent_wgs84_to_merc;
+
+    let extents = [
+        Extent {
+            minx: 5.96526,
+            miny: 45.82056,
+            maxx: 10.5603,
+            maxy: 47.77352,
+        },
+        Extent {
+            minx: -179.9,
+            miny: -85.0,
+            maxx: 179.9,
+            maxy: 85.0,
+        },
+        Extent {
+            minx: -74.25909,
+            miny: -33.86785,
+            maxx: 151.20929,
+            maxy: 40.91553,
+        },
+    ];
+    for extent in extents.iter() {
+        let roundtrip = extent_merc_to_wgs84(&extent_wgs84_to_merc(extent));
+        assert!((roundtrip.minx - extent.minx).abs() < 1e-9);
+        assert!((roundtrip.miny - extent.miny).abs() < 1e-9);
+        assert!((roundtrip.maxx - extent.maxx).abs() < 1e-9);
+        assert!((roundtrip.maxy - extent.maxy).abs() < 1e-9);
+    }
+    assert_eq!(
+        extent_merc_to_wgs84(&Extent {
+            minx: 0.0,
+            miny: 0.0,
+            maxx: 0.0,
+            maxy: 0.0
+        }),
+        Extent {
+            minx: 0.0,
+            miny: 0.0,
+            maxx: 0.0,
+            maxy: 0.0
+        }
+    );
+}
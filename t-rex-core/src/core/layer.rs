@@ -3,10 +3,16 @@
 // Licensed under the MIT License. See LICENSE file in the project root for full license information.
 //
 
-use crate::core::config::{self, LayerCfg};
+use crate::core::config::{
+    self, AttrCase, CurveToleranceCfg, FeatureIdStrategy, LayerCfg, ReservedAttrHandling,
+    ToleranceCfg,
+};
+use crate::core::feature::{FeatureAttr, FeatureAttrValType};
 use crate::core::Config;
 use crate::service::glstyle_converter::toml_style_to_gljson;
 use std::collections::HashMap;
+use std::fs;
+use toml::Value;
 
 #[derive(Clone, Debug)]
 pub struct LayerQuery {
@@ -17,12 +23,40 @@ pub struct LayerQuery {
     pub sql: Option<String>,
 }
 
+/// Attribute computed with an SQL expression
+#[derive(Clone, Debug)]
+pub struct ComputedAttr {
+    pub name: String,
+    pub expr: String,
+}
+
+/// Simplification tolerance for a zoom level range
+#[derive(Clone, Debug)]
+pub struct ToleranceRange {
+    pub minzoom: u8,
+    pub maxzoom: u8,
+    pub value: String,
+}
+
+/// Tolerance for linearizing curves
+#[derive(Clone, PartialEq, Debug)]
+pub enum CurveTolerance {
+    /// Simplification tolerance of zoom level
+    Tolerance,
+    /// SQL expression
+    Value(String),
+}
+
 #[derive(Default, Clone, Debug)]
 pub struct Layer {
     pub name: String,
+    /// Disabled layers are skipped in tiles and metadata
+    pub enabled: bool,
     pub datasource: Option<String>,
     pub geometry_field: Option<String>,
     pub geometry_type: Option<String>,
+    /// Geometry type of served features (POINT, LINESTRING or POLYGON) including multi-types
+    pub geometry_type_filter: Option<String>,
     /// Spatial reference system (PostGIS SRID)
     pub srid: Option<i32>,
     /// Handle geometry like one in grid SRS
@@ -31,30 +65,66 @@ pub struct Layer {
     // Input for derived queries
     pub table_name: Option<String>,
     pub query_limit: Option<u32>,
+    /// Column for ordering features before applying `query_limit`
+    pub order_by: Option<String>,
+    /// Order features descending
+    pub order_desc: bool,
     // Explicit queries
     pub query: Vec<LayerQuery>,
+    /// Attributes computed in the feature query
+    pub computed: Vec<ComputedAttr>,
     pub minzoom: Option<u8>,
     pub maxzoom: Option<u8>,
+    /// Serve data of maxzoom level clipped to tiles of higher zoom levels
+    pub overzoom: bool,
     /// Width and height of the tiles
     pub tile_size: u32,
     /// Simplify geometry (lines and polygons)
     pub simplify: bool,
     /// Simplification tolerance (default to !pixel_width!/2)
     pub tolerance: String,
+    /// Zoom level dependent simplification tolerance
+    pub tolerance_ranges: Vec<ToleranceRange>,
+    /// Tolerance for linearizing curves (None: PostGIS default)
+    pub curve_tolerance: Option<CurveTolerance>,
     /// Tile buffer size in pixels (None: no clipping)
     pub buffer_size: Option<u32>,
     /// Fix invalid geometries before clipping (lines and polygons)
     pub make_valid: bool,
-    /// Apply ST_Shift_Longitude to (transformed) bbox
+    /// Apply ST_Shift_Longitude to (transformed) bbox and split lines crossing the antimeridian
     pub shift_longitude: bool,
+    /// Clip geometries to buffered tile extent when encoding
+    /// (Default: true for datasources without server-side clipping)
+    pub clip_on_encode: Option<bool>,
+    /// Clip and transform geometries to tile coordinates with ST_AsMVTGeom
+    pub use_st_asmvtgeom: bool,
+    /// Encode layer with ST_AsMVT on the server
+    pub server_side_mvt: bool,
+    /// Add point layer with a label point of each polygon
+    pub label_point: bool,
+    /// Handling of attributes with reserved names like `id`
+    pub reserved_attr_handling: ReservedAttrHandling,
+    /// Case normalization of attribute names
+    pub attr_case: AttrCase,
+    /// Attributes with constant values added to every feature
+    pub constant_attrs: Vec<FeatureAttr>,
+    /// Number of decimals of float attributes (Default: full precision)
+    pub attribute_precision: Option<u8>,
+    /// Feature IDs of features without fid_field value
+    pub feature_id: FeatureIdStrategy,
     // Inline style
     pub style: Option<String>,
+    /// JSON file of style, unless overridden by an inline style
+    pub style_file: Option<String>,
+    /// Acknowledgment of data source
+    pub attribution: Option<String>,
 }
 
 impl Layer {
     pub fn new(name: &str) -> Layer {
         Layer {
             name: String::from(name),
+            enabled: true,
             tile_size: 4096,
             ..Default::default()
         }
@@ -72,6 +142,59 @@ impl Layer {
                 .unwrap_or(default),
         )
     }
+    /// Zoom level of data served in tiles of `zoom`. None if the layer is not visible.
+    /// Overzoomed layers use the data of their maxzoom level.
+    pub fn data_zoom(&self, zoom: u8, default_maxzoom: u8) -> Option<u8> {
+        let maxzoom = self.maxzoom(default_maxzoom);
+        if zoom < self.minzoom() {
+            None
+        } else if zoom <= maxzoom {
+            Some(zoom)
+        } else if self.overzoom {
+            Some(maxzoom)
+        } else {
+            None
+        }
+    }
+    /// Clip geometries when encoding. `server_side_clipping` is true for datasources
+    /// clipping geometries to the buffered tile extent.
+    /// Layers without buffer are clipped to the tile extent, unless clipping is done server-side.
+    pub fn clip_on_encode(&self, server_side_clipping: bool) -> bool {
+        if server_side_clipping && self.buffer_size.is_none() {
+            // Server-side queries of layers without buffer are not clipped either
+            return false;
+        }
+        self.clip_on_encode.unwrap_or(!server_side_clipping)
+    }
+    /// Geometry type of features, narrowed by `geometry_type_filter` for GEOMETRY layers
+    pub fn feature_geometry_type(&self) -> Option<&str> {
+        match self.geometry_type.as_deref() {
+            None | Some("GEOMETRY") => self
+                .geometry_type_filter
+                .as_deref()
+                .or(self.geometry_type.as_deref()),
+            geometry_type => geometry_type,
+        }
+    }
+    /// Features of `geometry_type` (e.g. "MULTIPOLYGON") pass `geometry_type_filter`
+    pub fn accepts_geometry_type(&self, geometry_type: &str) -> bool {
+        match self.geometry_type_filter {
+            Some(ref filter) => geometry_type.trim_start_matches("MULTI") == filter,
+            None => true,
+        }
+    }
+    /// Name of the label point layer
+    pub fn label_layer_name(&self) -> String {
+        format!("{}_label", self.name)
+    }
+    /// Tiles contain a label point layer (not supported with `server_side_mvt`)
+    pub fn has_label_layer(&self) -> bool {
+        self.label_point && !self.server_side_mvt
+    }
+    /// Transform geometries to tile coordinates with ST_AsMVTGeom
+    pub fn st_asmvtgeom(&self) -> bool {
+        self.use_st_asmvtgeom || self.server_side_mvt
+    }
     /// Query config for zoom level
     fn query_cfg<F>(&self, level: u8, check: F) -> Option<&LayerQuery>
     where
@@ -105,7 +228,30 @@ impl Layer {
         let query_cfg = self.query_cfg(level, |q| q.tolerance.is_some());
         query_cfg
             .and_then(|q| q.tolerance.as_ref())
-            .unwrap_or(&self.tolerance)
+            .unwrap_or_else(|| {
+                self.tolerance_ranges
+                    .iter()
+                    .find(|r| level >= r.minzoom && level <= r.maxzoom)
+                    .map(|r| &r.value)
+                    .unwrap_or(&self.tolerance)
+            })
+    }
+    /// Curve linearization tolerance for zoom level
+    pub fn curve_tolerance(&self, level: u8) -> Option<&String> {
+        match self.curve_tolerance {
+            Some(CurveTolerance::Tolerance) => Some(self.tolerance(level)),
+            Some(CurveTolerance::Value(ref value)) => Some(value),
+            None => None,
+        }
+    }
+    /// SRID of layer coordinates for extent calculation.
+    /// With `no_transform`, coordinates are handled like ones in grid SRS.
+    pub fn effective_extent_srid(&self, grid_srid: i32) -> Option<i32> {
+        if self.no_transform {
+            Some(grid_srid)
+        } else {
+            self.srid
+        }
     }
     /// Layer properties needed e.g. for metadata.json
     pub fn metadata(&self) -> HashMap<&str, String> {
@@ -122,6 +268,30 @@ impl Layer {
     }
 }
 
+/// JSON style object read from file
+fn read_style_file(path: &str) -> Result<String, String> {
+    let json = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    match serde_json::from_str(&json) {
+        Ok(serde_json::Value::Object(_)) => Ok(json),
+        Ok(_) => Err("JSON object expected".to_string()),
+        Err(e) => Err(format!("invalid JSON: {}", e)),
+    }
+}
+
+/// Single-part geometry type of a `geometry_type_filter` value
+fn normalize_geometry_type_filter(filter: &str) -> Option<String> {
+    let filter = filter.to_uppercase();
+    let geometry_type = match filter.trim_start_matches("MULTI") {
+        "LINE" => "LINESTRING",
+        geometry_type => geometry_type,
+    };
+    if ["POINT", "LINESTRING", "POLYGON"].contains(&geometry_type) {
+        Some(geometry_type.to_string())
+    } else {
+        None
+    }
+}
+
 impl<'a> Config<'a, LayerCfg> for Layer {
     fn from_config(layer_cfg: &LayerCfg) -> Result<Self, String> {
         let queries = layer_cfg
@@ -135,33 +305,168 @@ impl<'a> Config<'a, LayerCfg> for Layer {
                 sql: lq.sql.clone(),
             })
             .collect();
-        let style = match layer_cfg.style {
-            Some(ref style) => {
-                let gljson = toml_style_to_gljson(&style);
-                Some(gljson)
+        let (style, style_file) = match (&layer_cfg.style, &layer_cfg.style_file) {
+            (Some(style), _) => {
+                let gljson = toml_style_to_gljson(style);
+                (Some(gljson), None)
+            }
+            (None, Some(path)) => {
+                let json = read_style_file(path).map_err(|e| {
+                    format!("Layer '{}': style_file '{}': {}", layer_cfg.name, path, e)
+                })?;
+                (Some(json), Some(path.clone()))
+            }
+            (None, None) => (None, None),
+        };
+        // Buffer in MVT extent units is converted to pixels of a 256 pixel tile
+        let buffer_size = match (layer_cfg.buffer_size, layer_cfg.buffer_extent) {
+            (Some(_), Some(_)) => {
+                return Err(format!(
+                    "Layer '{}': buffer_size and buffer_extent are mutually exclusive",
+                    layer_cfg.name
+                ))
+            }
+            (None, Some(units)) => {
+                Some((units as f64 * 256.0 / layer_cfg.tile_size as f64).ceil() as u32)
             }
+            (buffer_size, None) => buffer_size,
+        };
+        let (tolerance, tolerance_ranges) = match layer_cfg.tolerance {
+            ToleranceCfg::Value(ref value) => (value.clone(), Vec::new()),
+            ToleranceCfg::Ranges(ref ranges) => {
+                let mut tolerance_ranges = ranges
+                    .iter()
+                    .map(|r| ToleranceRange {
+                        minzoom: r.minzoom,
+                        maxzoom: r.maxzoom.unwrap_or(22),
+                        value: r.value.clone(),
+                    })
+                    .collect::<Vec<_>>();
+                tolerance_ranges.sort_by_key(|r| r.minzoom);
+                if let Some(r) = tolerance_ranges.iter().find(|r| r.minzoom > r.maxzoom) {
+                    return Err(format!(
+                        "Layer '{}': invalid tolerance zoom range {}-{}",
+                        layer_cfg.name, r.minzoom, r.maxzoom
+                    ));
+                }
+                for pair in tolerance_ranges.windows(2) {
+                    if pair[1].minzoom <= pair[0].maxzoom {
+                        return Err(format!(
+                            "Layer '{}': overlapping tolerance zoom ranges {}-{} and {}-{}",
+                            layer_cfg.name,
+                            pair[0].minzoom,
+                            pair[0].maxzoom,
+                            pair[1].minzoom,
+                            pair[1].maxzoom
+                        ));
+                    }
+                }
+                (config::default_tolerance(), tolerance_ranges)
+            }
+        };
+        let curve_tolerance = match layer_cfg.curve_tolerance {
+            Some(CurveToleranceCfg::Enabled(true)) => Some(CurveTolerance::Tolerance),
+            Some(CurveToleranceCfg::Value(ref value)) => Some(CurveTolerance::Value(value.clone())),
+            Some(CurveToleranceCfg::Enabled(false)) | None => None,
+        };
+        let mut constant_attrs = Vec::with_capacity(layer_cfg.constant_attrs.len());
+        for (key, value) in &layer_cfg.constant_attrs {
+            let value = match value {
+                Value::String(v) => FeatureAttrValType::String(v.clone()),
+                Value::Integer(v) => FeatureAttrValType::Int(*v),
+                Value::Float(v) => FeatureAttrValType::Double(*v),
+                Value::Boolean(v) => FeatureAttrValType::Bool(*v),
+                _ => {
+                    return Err(format!(
+                        "Layer '{}': unsupported value type of constant attribute '{}'",
+                        layer_cfg.name, key
+                    ))
+                }
+            };
+            constant_attrs.push(FeatureAttr {
+                key: key.clone(),
+                value,
+            });
+        }
+        let geometry_type_filter = match layer_cfg.geometry_type_filter {
+            Some(ref filter) => Some(normalize_geometry_type_filter(filter).ok_or(format!(
+                "Layer '{}': invalid geometry_type_filter '{}' - expected POINT, LINESTRING or POLYGON",
+                layer_cfg.name, filter
+            ))?),
             None => None,
         };
+        let computed = layer_cfg
+            .computed
+            .iter()
+            .map(|c| ComputedAttr {
+                name: c.name.clone(),
+                expr: c.expr.clone(),
+            })
+            .collect();
+        if layer_cfg.server_side_mvt {
+            let mut ignored = Vec::new();
+            if layer_cfg.feature_id != FeatureIdStrategy::Field {
+                ignored.push("feature_id");
+            }
+            if layer_cfg.attr_case != AttrCase::Preserve {
+                ignored.push("attr_case");
+            }
+            if layer_cfg.attribute_precision.is_some() {
+                ignored.push("attribute_precision");
+            }
+            if layer_cfg.label_point {
+                ignored.push("label_point");
+            }
+            if !layer_cfg.constant_attrs.is_empty() {
+                ignored.push("constant_attrs");
+            }
+            if !ignored.is_empty() {
+                warn!(
+                    "Layer '{}': {} ignored with server_side_mvt",
+                    layer_cfg.name,
+                    ignored.join(", ")
+                );
+            }
+        }
         Ok(Layer {
             name: layer_cfg.name.clone(),
+            enabled: layer_cfg.enabled,
             datasource: layer_cfg.datasource.clone(), //TODO: inherit from parents if None?
             geometry_field: layer_cfg.geometry_field.clone(),
             geometry_type: layer_cfg.geometry_type.clone(),
+            geometry_type_filter,
             srid: layer_cfg.srid,
             no_transform: layer_cfg.no_transform,
             fid_field: layer_cfg.fid_field.clone(),
             table_name: layer_cfg.table_name.clone(),
             query_limit: layer_cfg.query_limit,
+            order_by: layer_cfg.order_by.clone(),
+            order_desc: layer_cfg.order_desc,
             query: queries,
+            computed,
             minzoom: layer_cfg.minzoom,
             maxzoom: layer_cfg.maxzoom,
+            overzoom: layer_cfg.overzoom,
             tile_size: layer_cfg.tile_size,
             simplify: layer_cfg.simplify,
-            tolerance: layer_cfg.tolerance.clone(),
-            buffer_size: layer_cfg.buffer_size,
+            tolerance,
+            tolerance_ranges,
+            curve_tolerance,
+            buffer_size,
             make_valid: layer_cfg.make_valid,
             shift_longitude: layer_cfg.shift_longitude,
+            clip_on_encode: layer_cfg.clip_on_encode,
+            use_st_asmvtgeom: layer_cfg.use_st_asmvtgeom,
+            server_side_mvt: layer_cfg.server_side_mvt,
+            label_point: layer_cfg.label_point,
+            reserved_attr_handling: layer_cfg.reserved_attr_handling,
+            attr_case: layer_cfg.attr_case,
+            constant_attrs,
+            attribute_precision: layer_cfg.attribute_precision,
+            feature_id: layer_cfg.feature_id,
             style: style,
+            style_file,
+            attribution: layer_cfg.attribution.clone(),
         })
     }
 
@@ -194,6 +499,9 @@ geometry_type = "POINT"
     fn gen_runtime_config(&self) -> String {
         let mut lines = vec!["[[tileset.layer]]".to_string()];
         lines.push(format!(r#"name = "{}""#, self.name));
+        if !self.enabled {
+            lines.push("enabled = false".to_string());
+        }
         if let Some(ref ds) = self.datasource {
             lines.push(format!("datasource = \"{}\"", ds));
         }
@@ -214,6 +522,9 @@ geometry_type = "POINT"
             Some(ref geometry_type) => lines.push(format!("geometry_type = \"{}\"", geometry_type)),
             _ => lines.push("#geometry_type = \"POINT\"".to_string()),
         }
+        if let Some(ref filter) = self.geometry_type_filter {
+            lines.push(format!("geometry_type_filter = \"{}\"", filter));
+        }
         match self.srid {
             Some(ref srid) => lines.push(format!("srid = {}", srid)),
             _ => lines.push("#srid = 3857".to_string()),
@@ -227,6 +538,9 @@ geometry_type = "POINT"
         if self.tile_size != 4096 {
             lines.push(format!(r#"tile_size = "{}""#, self.tile_size));
         }
+        if self.overzoom {
+            lines.push("overzoom = true".to_string());
+        }
         match self.buffer_size {
             Some(ref buffer_size) => lines.push(format!("buffer_size = {}", buffer_size)),
             _ => lines.push(format!("#buffer_size = 10")),
@@ -238,17 +552,97 @@ geometry_type = "POINT"
         if self.shift_longitude {
             lines.push(format!("shift_longitude = true"));
         }
+        if let Some(clip_on_encode) = self.clip_on_encode {
+            lines.push(format!("clip_on_encode = {}", clip_on_encode));
+        }
+        if self.use_st_asmvtgeom {
+            lines.push(format!("use_st_asmvtgeom = true"));
+        }
+        if self.server_side_mvt {
+            lines.push(format!("server_side_mvt = true"));
+        }
+        if self.label_point {
+            lines.push("label_point = true".to_string());
+        }
+        if self.reserved_attr_handling != ReservedAttrHandling::Keep {
+            lines.push(format!(
+                "reserved_attr_handling = \"{}\"",
+                self.reserved_attr_handling.as_str()
+            ));
+        }
+        if self.attr_case != AttrCase::Preserve {
+            lines.push(format!("attr_case = \"{}\"", self.attr_case.as_str()));
+        }
+        if !self.constant_attrs.is_empty() {
+            let attrs = self
+                .constant_attrs
+                .iter()
+                .map(|attr| {
+                    let value = match attr.value {
+                        FeatureAttrValType::String(ref v) => Value::String(v.clone()),
+                        FeatureAttrValType::Int(v) => Value::Integer(v),
+                        FeatureAttrValType::Double(v) => Value::Float(v),
+                        FeatureAttrValType::Bool(v) => Value::Boolean(v),
+                        _ => Value::String(String::new()),
+                    };
+                    format!("{} = {}", attr.key, value)
+                })
+                .collect::<Vec<_>>();
+            lines.push(format!("constant_attrs = {{ {} }}", attrs.join(", ")));
+        }
+        if let Some(precision) = self.attribute_precision {
+            lines.push(format!("attribute_precision = {}", precision));
+        }
+        if self.feature_id != FeatureIdStrategy::Field {
+            lines.push(format!("feature_id = \"{}\"", self.feature_id.as_str()));
+        }
         if self.geometry_type != Some("POINT".to_string()) {
             // simplify is ignored for points
             lines.push(format!("simplify = {}", self.simplify));
-            if self.simplify && self.tolerance != config::DEFAULT_TOLERANCE {
+            if self.simplify && !self.tolerance_ranges.is_empty() {
+                let ranges = self
+                    .tolerance_ranges
+                    .iter()
+                    .map(|r| {
+                        format!(
+                            "{{ minzoom = {}, maxzoom = {}, value = \"{}\" }}",
+                            r.minzoom, r.maxzoom, r.value
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                lines.push(format!("tolerance = [{}]", ranges.join(", ")));
+            } else if self.simplify && self.tolerance != config::DEFAULT_TOLERANCE {
                 lines.push(format!("tolerance = \"{}\"", self.tolerance));
             }
         }
+        match self.curve_tolerance {
+            Some(CurveTolerance::Tolerance) => lines.push("curve_tolerance = true".to_string()),
+            Some(CurveTolerance::Value(ref value)) => {
+                lines.push(format!("curve_tolerance = \"{}\"", value))
+            }
+            None => {}
+        }
         match self.query_limit {
             Some(ref query_limit) => lines.push(format!("query_limit = {}", query_limit)),
             _ => lines.push("#query_limit = 1000".to_string()),
         }
+        if let Some(ref order_by) = self.order_by {
+            lines.push(format!("order_by = \"{}\"", order_by));
+            if self.order_desc {
+                lines.push("order_desc = true".to_string());
+            }
+        }
+        if let Some(ref style_file) = self.style_file {
+            lines.push(format!(
+                "style_file = {}",
+                Value::String(style_file.clone())
+            ));
+        }
+        for computed in &self.computed {
+            lines.push("[[tileset.layer.computed]]".to_string());
+            lines.push(format!("name = {}", Value::String(computed.name.clone())));
+            lines.push(format!("expr = {}", Value::String(computed.expr.clone())));
+        }
         match self.query(0) {
             Some(ref query) => {
                 lines.push("[[tileset.layer.query]]".to_string());
@@ -92,6 +92,20 @@ fn test_layer_defaults() {
     assert_eq!(cfg.maxzoom(30), 30);
 }
 
+#[test]
+fn test_effective_extent_srid() {
+    let mut layer = Layer::new("points");
+    assert_eq!(layer.effective_extent_srid(3857), None);
+    layer.srid = Some(2056);
+    assert_eq!(layer.effective_extent_srid(3857), Some(2056));
+
+    // Geometries handled like ones in grid SRS
+    layer.no_transform = true;
+    assert_eq!(layer.effective_extent_srid(3857), Some(3857));
+    layer.srid = None;
+    assert_eq!(layer.effective_extent_srid(3857), Some(3857));
+}
+
 #[test]
 fn test_zoom_config() {
     // min/maxzoom in layer
@@ -258,6 +272,195 @@ fn test_simplify_config() {
     assert_eq!(cfg.tolerance(14), "!pixel_width!/5"); // should it be "!pixel_width!/6" ?
 }
 
+#[test]
+fn test_tolerance_ranges() {
+    let toml = r#"
+        #[[tileset.layer]]
+        name = "roads"
+        geometry_field = "wkb_geometry"
+        simplify = true
+        tolerance = [
+            { maxzoom = 5, value = "!pixel_width!" },
+            { minzoom = 6, maxzoom = 10, value = "!pixel_width!/4" },
+            { minzoom = 14, value = "0" },
+        ]
+        [[query]]
+        minzoom = 16
+        tolerance = "!pixel_width!/8"
+        sql = "SELECT name,wkb_geometry FROM roads_z16"
+        "#;
+    let cfg = layer_from_config(toml).unwrap();
+    assert_eq!(cfg.tolerance(0), "!pixel_width!");
+    assert_eq!(cfg.tolerance(5), "!pixel_width!");
+    assert_eq!(cfg.tolerance(6), "!pixel_width!/4");
+    assert_eq!(cfg.tolerance(10), "!pixel_width!/4");
+    // Gap between ranges falls back to default
+    assert_eq!(cfg.tolerance(12), "!pixel_width!/2"); // config::DEFAULT_TOLERANCE
+    assert_eq!(cfg.tolerance(14), "0");
+    assert_eq!(cfg.tolerance(15), "0");
+    // Query tolerance takes precedence
+    assert_eq!(cfg.tolerance(16), "!pixel_width!/8");
+
+    let toml = r#"
+        #[[tileset.layer]]
+        name = "roads"
+        tolerance = [
+            { minzoom = 10, value = "0" },
+            { maxzoom = 10, value = "!pixel_width!" },
+        ]
+        "#;
+    let cfg = layer_from_config(toml);
+    assert_eq!(
+        cfg.err(),
+        Some("Layer 'roads': overlapping tolerance zoom ranges 0-10 and 10-22".to_string())
+    );
+
+    let toml = r#"
+        #[[tileset.layer]]
+        name = "roads"
+        tolerance = [{ minzoom = 12, maxzoom = 8, value = "0" }]
+        "#;
+    let cfg = layer_from_config(toml);
+    assert_eq!(
+        cfg.err(),
+        Some("Layer 'roads': invalid tolerance zoom range 12-8".to_string())
+    );
+}
+
+#[test]
+fn test_curve_tolerance() {
+    let toml = r#"
+        #[[tileset.layer]]
+        name = "areas"
+        geometry_type = "CURVEPOLYGON"
+        tolerance = [{ maxzoom = 5, value = "!pixel_width!" }]
+        curve_tolerance = true
+        "#;
+    let cfg = layer_from_config(toml).unwrap();
+    assert_eq!(cfg.curve_tolerance(3), Some(&"!pixel_width!".to_string()));
+    assert_eq!(
+        cfg.curve_tolerance(12),
+        Some(&"!pixel_width!/2".to_string())
+    );
+
+    let toml = r#"
+        #[[tileset.layer]]
+        name = "areas"
+        curve_tolerance = "0.5"
+        "#;
+    let cfg = layer_from_config(toml).unwrap();
+    assert_eq!(cfg.curve_tolerance(3), Some(&"0.5".to_string()));
+
+    let toml = r#"
+        #[[tileset.layer]]
+        name = "areas"
+        curve_tolerance = false
+        "#;
+    let cfg = layer_from_config(toml).unwrap();
+    assert_eq!(cfg.curve_tolerance(3), None);
+}
+
+#[test]
+fn test_data_zoom() {
+    let mut layer = Layer::new("points");
+    layer.minzoom = Some(4);
+    layer.maxzoom = Some(12);
+    assert_eq!(layer.data_zoom(3, 22), None);
+    assert_eq!(layer.data_zoom(4, 22), Some(4));
+    assert_eq!(layer.data_zoom(12, 22), Some(12));
+    assert_eq!(layer.data_zoom(15, 22), None);
+
+    layer.overzoom = true;
+    assert_eq!(layer.data_zoom(3, 22), None);
+    assert_eq!(layer.data_zoom(12, 22), Some(12));
+    assert_eq!(layer.data_zoom(15, 22), Some(12));
+
+    layer.maxzoom = None;
+    assert_eq!(layer.data_zoom(15, 14), Some(14));
+}
+
+#[test]
+fn test_configured_srid() {
+    use crate::datasource::PostgisDatasource;
+
+    let pg = PostgisDatasource::new("postgresql://pi@localhost/osm2vectortiles", Some(1), None);
+    // View reporting SRID 0 in geometry_columns
+    let toml = r#"
+        name = "places"
+        table_name = "places_view"
+        geometry_field = "geom"
+        geometry_type = "POINT"
+        srid = 2056
+        "#;
+    let layer = layer_from_config(toml).unwrap();
+    assert_eq!(layer.srid, Some(2056));
+    assert_eq!(
+        pg.build_query(&layer, 3857, 10, None).unwrap().sql,
+        "SELECT ST_Transform(geom,3857) AS geom FROM places_view WHERE geom && ST_Transform(ST_Segmentize(ST_MakeEnvelope($1,$2,$3,$4,3857), ($3-$1)/512), 2056)"
+    );
+}
+
+#[test]
+fn test_buffer_extent() {
+    use crate::datasource::PostgisDatasource;
+
+    let pg = PostgisDatasource::new("postgresql://pi@localhost/osm2vectortiles", Some(1), None);
+    // 4 pixels of a 256 pixel tile correspond to 64 units of a 4096 MVT extent
+    let toml = r#"
+        name = "points"
+        table_name = "osm_place_point"
+        geometry_field = "geometry"
+        buffer_size = 4
+        "#;
+    let layer_pixels = layer_from_config(toml).unwrap();
+    let toml = r#"
+        name = "points"
+        table_name = "osm_place_point"
+        geometry_field = "geometry"
+        buffer_extent = 64
+        "#;
+    let layer_extent = layer_from_config(toml).unwrap();
+    assert_eq!(layer_extent.buffer_size, Some(4));
+    let sql = pg.build_query(&layer_extent, 3857, 10, None).unwrap().sql;
+    assert!(sql.contains("ST_MakeEnvelope($1-0.25*$5::FLOAT8,"), "{}", sql);
+    assert_eq!(
+        sql,
+        pg.build_query(&layer_pixels, 3857, 10, None).unwrap().sql
+    );
+
+    // Same physical buffer with 512 units MVT extent
+    let toml = r#"
+        name = "points"
+        table_name = "osm_place_point"
+        geometry_field = "geometry"
+        tile_size = 512
+        buffer_size = 4
+        "#;
+    let layer_pixels = layer_from_config(toml).unwrap();
+    let toml = r#"
+        name = "points"
+        table_name = "osm_place_point"
+        geometry_field = "geometry"
+        tile_size = 512
+        buffer_extent = 8
+        "#;
+    let layer_extent = layer_from_config(toml).unwrap();
+    assert_eq!(
+        pg.build_query(&layer_extent, 3857, 10, None).unwrap().sql,
+        pg.build_query(&layer_pixels, 3857, 10, None).unwrap().sql
+    );
+
+    let toml = r#"
+        name = "points"
+        buffer_size = 4
+        buffer_extent = 64
+        "#;
+    assert_eq!(
+        layer_from_config(toml).err(),
+        Some("Layer 'points': buffer_size and buffer_extent are mutually exclusive".to_string())
+    );
+}
+
 #[test]
 fn test_invalid_configs() {
     // Invalid config: missing required field
@@ -295,6 +498,65 @@ fn test_invalid_configs() {
     );
 }
 
+#[test]
+fn test_style_file() {
+    let dir = std::env::temp_dir().join("t_rex_test_style_file");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("invalid.json");
+    std::fs::write(&path, "{ \"type\": ").unwrap();
+    let path = path.to_str().unwrap().to_string();
+
+    let toml = format!(
+        r#"
+        #[[tileset.layer]]
+        name = "roads"
+        style_file = "{}"
+        "#,
+        path
+    );
+    let err = layer_from_config(&toml).err().unwrap();
+    assert!(err.starts_with(&format!(
+        "Layer 'roads': style_file '{}': invalid JSON",
+        path
+    )));
+
+    let toml = r#"
+        #[[tileset.layer]]
+        name = "roads"
+        style_file = "missing_style.json"
+        "#;
+    let err = layer_from_config(toml).err().unwrap();
+    assert!(err.starts_with("Layer 'roads': style_file 'missing_style.json': "));
+
+    // Inline style takes precedence
+    let toml = r#"
+        #[[tileset.layer]]
+        name = "roads"
+        style_file = "missing_style.json"
+        style = { type = "line" }
+        "#;
+    let layer = layer_from_config(toml).unwrap();
+    assert_eq!(layer.style_file, None);
+    assert!(layer.style.unwrap().contains(r#""type": "line""#));
+
+    let path = dir.join("style.json");
+    std::fs::write(&path, r#"{ "type": "line" }"#).unwrap();
+    let path = path.to_str().unwrap().to_string();
+    let toml = format!(
+        r#"
+        #[[tileset.layer]]
+        name = "roads"
+        style_file = "{}"
+        "#,
+        path
+    );
+    let layer = layer_from_config(&toml).unwrap();
+    assert_eq!(layer.style, Some(r#"{ "type": "line" }"#.to_string()));
+    assert!(layer
+        .gen_runtime_config()
+        .contains(&format!("style_file = {}", toml::Value::String(path))));
+}
+
 #[test]
 fn test_layers_from_config() {
     use crate::core::config::TilesetCfg;
@@ -343,3 +605,83 @@ fn test_layers_from_config() {
         Some(" - missing field `name`".to_string())
     );
 }
+
+#[test]
+fn test_geometry_type_filter() {
+    use crate::core::parse_config;
+
+    let toml = r#"
+        name = "roads"
+        geometry_field = "geom"
+        geometry_type = "GEOMETRY"
+        geometry_type_filter = "MultiLineString"
+        "#;
+    let layer = Layer::from_config(&parse_config(toml.to_string(), "").unwrap()).unwrap();
+    assert_eq!(layer.geometry_type_filter, Some("LINESTRING".to_string()));
+    assert_eq!(layer.feature_geometry_type(), Some("LINESTRING"));
+    assert!(layer.accepts_geometry_type("LINESTRING"));
+    assert!(layer.accepts_geometry_type("MULTILINESTRING"));
+    assert!(!layer.accepts_geometry_type("POLYGON"));
+    assert!(layer
+        .gen_runtime_config()
+        .contains(r#"geometry_type_filter = "LINESTRING""#));
+
+    let layer = Layer::from_config(
+        &parse_config(toml.replace("MultiLineString", "CIRCLE"), "").unwrap(),
+    );
+    assert_eq!(
+        layer.err(),
+        Some(
+            "Layer 'roads': invalid geometry_type_filter 'CIRCLE' - expected POINT, LINESTRING or POLYGON"
+                .to_string()
+        )
+    );
+
+    let mut layer = Layer::new("all");
+    assert!(layer.accepts_geometry_type("POINT"));
+    assert_eq!(layer.feature_geometry_type(), None);
+    layer.geometry_type = Some("POINT".to_string());
+    assert_eq!(layer.feature_geometry_type(), Some("POINT"));
+}
+
+#[test]
+fn test_computed_attributes() {
+    use crate::core::parse_config;
+
+    let toml = r#"
+        name = "buildings"
+        table_name = "osm_buildings"
+        geometry_field = "geometry"
+        [[computed]]
+        name = "area"
+        expr = "ST_Area(geometry)"
+        [[computed]]
+        name = "label"
+        expr = "name || ' (' || levels || ')'"
+        "#;
+    let layer = Layer::from_config(&parse_config(toml.to_string(), "").unwrap()).unwrap();
+    assert_eq!(layer.computed.len(), 2);
+    assert_eq!(layer.computed[0].name, "area");
+    assert_eq!(layer.computed[0].expr, "ST_Area(geometry)");
+    let runtime_config = layer.gen_runtime_config();
+    assert!(runtime_config.contains(
+        r#"[[tileset.layer.computed]]
+name = "area"
+expr = "ST_Area(geometry)"
+[[tileset.layer.computed]]
+name = "label"
+expr = "name || ' (' || levels || ')'""#
+    ));
+
+    // Names and expressions are escaped
+    let mut layer = layer;
+    layer.computed[1].name = r#"label "full""#.to_string();
+    layer.computed[1].expr = r#"replace(name, '\', '/') || "suffix""#.to_string();
+    let runtime_config: toml::Value = layer.gen_runtime_config().parse().unwrap();
+    let computed = &runtime_config["tileset"]["layer"][0]["computed"][1];
+    assert_eq!(computed["name"].as_str(), Some(r#"label "full""#));
+    assert_eq!(
+        computed["expr"].as_str(),
+        Some(r#"replace(name, '\', '/') || "suffix""#)
+    );
+}
@@ -13,10 +13,13 @@ pub mod screen;
 pub mod stats;
 
 pub use self::config::{parse_config, read_config, ApplicationCfg, Config};
+pub use self::gridcfg::{extent_merc_to_wgs84, EpsgGrid, TileMatrixSet, Wgs84Bounds};
 
 #[cfg(test)]
 mod config_test;
 #[cfg(test)]
+mod feature_test;
+#[cfg(test)]
 mod geom_test;
 #[cfg(test)]
 mod gridcfg_test;
@@ -29,6 +29,17 @@ pub struct LineString {
     pub points: Vec<Point>,
 }
 
+impl LineString {
+    /// Twice the signed area of a ring (shoelace formula)
+    pub fn ring_area(&self) -> i64 {
+        self.points
+            .iter()
+            .zip(self.points.iter().skip(1))
+            .map(|(p1, p2)| p1.x as i64 * p2.y as i64 - p2.x as i64 * p1.y as i64)
+            .sum()
+    }
+}
+
 #[derive(PartialEq, Debug)]
 pub struct MultiLineString {
     pub lines: Vec<LineString>,
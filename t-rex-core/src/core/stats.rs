@@ -6,7 +6,8 @@
 //! Statistics collector
 
 use serde_json;
-use stats::{MinMax, OnlineStats};
+use stats::{Commute, MinMax, OnlineStats};
+use std::cmp;
 use std::collections::BTreeMap;
 use std::fmt;
 
@@ -17,6 +18,8 @@ type MeasurementType = u64;
 struct StatCollector {
     online: OnlineStats,
     minmax: MinMax<MeasurementType>,
+    /// All measurements for percentile calculation
+    samples: Vec<MeasurementType>,
 }
 
 pub struct StatResults {
@@ -38,12 +41,33 @@ impl Statistics {
         self.0.entry(key.to_string()).or_insert(StatCollector {
             online: OnlineStats::new(),
             minmax: MinMax::new(),
+            samples: Vec::new(),
         })
     }
     pub fn add(&mut self, key: String, value: MeasurementType) {
         let collector = self.collector(key);
         collector.online.add(value);
         collector.minmax.add(value);
+        collector.samples.push(value);
+    }
+    /// Merge measurements collected independently, e.g. in parallel tasks
+    pub fn merge(&mut self, other: Statistics) {
+        for (key, other_collector) in other.0 {
+            let collector = self.collector(key);
+            collector.online.merge(other_collector.online);
+            collector.minmax.merge(other_collector.minmax);
+            collector.samples.extend(other_collector.samples);
+        }
+    }
+    /// Percentile `p` (0-100) of measurements using the nearest-rank method
+    pub fn percentile(&self, key: &str, p: f64) -> MeasurementType {
+        let mut samples = match self.0.get(key) {
+            Some(collector) if !collector.samples.is_empty() => collector.samples.clone(),
+            _ => return 0,
+        };
+        samples.sort_unstable();
+        let rank = (p.max(0.0).min(100.0) / 100.0 * samples.len() as f64).ceil() as usize;
+        samples[cmp::max(rank, 1) - 1]
     }
     /// Return the current results.
     pub fn results(&self, key: &str) -> StatResults {
@@ -69,10 +93,11 @@ impl Statistics {
     }
     pub fn as_csv(&self) -> String {
         let mut lines = Vec::new();
-        let mut header: Vec<String> = vec!["count", "min", "max", "mean", "stddev"]
-            .iter()
-            .map(|s| s.to_string())
-            .collect();
+        let mut header: Vec<String> =
+            vec!["count", "min", "max", "mean", "stddev", "p50", "p90", "p99"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect();
         let maxkeylen = self.0.keys().map(|k| k.split('.').count()).max().unwrap();
         header.extend((0..maxkeylen).map(|n| format!("key{}", n)));
         lines.push(header.join(","));
@@ -84,6 +109,9 @@ impl Statistics {
                 vals.max.to_string(),
                 vals.mean.to_string(),
                 vals.stddev.to_string(),
+                self.percentile(&key, 50.0).to_string(),
+                self.percentile(&key, 90.0).to_string(),
+                self.percentile(&key, 99.0).to_string(),
             ];
             cols.extend(key.split('.').map(|k| k.to_string()));
             lines.push(cols.join(","));
@@ -102,7 +130,10 @@ impl Statistics {
                 "min": vals.min,
                 "max": vals.max,
                 "mean": vals.mean,
-                "stddev": vals.stddev
+                "stddev": vals.stddev,
+                "p50": self.percentile(&key, 50.0),
+                "p90": self.percentile(&key, 90.0),
+                "p99": self.percentile(&key, 99.0)
                 });
                 for (n, k) in key.split('.').enumerate() {
                     rec.as_object_mut()
@@ -152,7 +183,7 @@ fn usage() {
     assert_eq!(stats.results("Layer.layer1").len, 3);
     assert_eq!(stats.results("Layer.layer1").min, 1);
     assert_eq!(stats.results("Layer.layer1").max, 3);
-    assert_eq!(&stats.as_csv(), "count,min,max,mean,stddev,key0,key1\n3,1,3,2,0.816496580927726,Layer,layer1\n1,2,2,2,0,Layer,layer2\n");
+    assert_eq!(&stats.as_csv(), "count,min,max,mean,stddev,p50,p90,p99,key0,key1\n3,1,3,2,0.816496580927726,2,3,3,Layer,layer1\n1,2,2,2,0,2,2,2,Layer,layer2\n");
     let jsonstats = format!("{:#}", stats.as_json().unwrap());
     let expected = r#"[
   {
@@ -163,6 +194,9 @@ fn usage() {
     "max": 3,
     "mean": 2.0,
     "min": 1,
+    "p50": 2,
+    "p90": 3,
+    "p99": 3,
     "stddev": 0.816496580927726
   },
   {
@@ -173,6 +207,9 @@ fn usage() {
     "max": 2,
     "mean": 2.0,
     "min": 2,
+    "p50": 2,
+    "p90": 2,
+    "p99": 2,
     "stddev": 0.0
   }
 ]"#;
@@ -181,3 +218,90 @@ fn usage() {
 
     assert_eq!(stats.results("Layer.layerx").mean, 0.0);
 }
+
+#[test]
+fn merge() {
+    let values = [(1, 3), (1, 7), (2, 4), (1, 2), (2, 9), (1, 5), (2, 1)];
+    let key = |layer| format!("Layer.layer{}", layer);
+    let mut serial = Statistics::new();
+    for &(layer, value) in &values {
+        serial.add(key(layer), value);
+    }
+    let mut merged = Statistics::new();
+    for chunk in values.chunks(3) {
+        let mut part = Statistics::new();
+        for &(layer, value) in chunk {
+            part.add(key(layer), value);
+        }
+        merged.merge(part);
+    }
+    for layer in 1..=2 {
+        let expected = serial.results(&key(layer));
+        let result = merged.results(&key(layer));
+        assert_eq!(result.len, expected.len);
+        assert_eq!(result.min, expected.min);
+        assert_eq!(result.max, expected.max);
+        assert!((result.mean - expected.mean).abs() < 1e-9);
+        assert!((result.stddev - expected.stddev).abs() < 1e-9);
+        assert_eq!(
+            merged.percentile(&key(layer), 50.0),
+            serial.percentile(&key(layer), 50.0)
+        );
+    }
+}
+
+#[test]
+fn percentiles() {
+    let mut stats = Statistics::new();
+    // Samples 1..=100 in shuffled order
+    for i in 0..100 {
+        stats.add("tile_ms".to_string(), (i * 37) % 100 + 1);
+    }
+    assert_eq!(stats.percentile("tile_ms", 50.0), 50);
+    assert_eq!(stats.percentile("tile_ms", 90.0), 90);
+    assert_eq!(stats.percentile("tile_ms", 99.0), 99);
+    assert_eq!(stats.percentile("tile_ms", 100.0), 100);
+    assert_eq!(stats.percentile("tile_ms", 0.0), 1);
+    assert_eq!(stats.percentile("unknown", 50.0), 0);
+
+    // Merge with a second set of samples 101..=200
+    let mut other = Statistics::new();
+    for i in 101..=200 {
+        other.add("tile_ms".to_string(), i);
+    }
+    stats.merge(other);
+    assert_eq!(stats.results("tile_ms").len, 200);
+    assert_eq!(stats.results("tile_ms").max, 200);
+    assert_eq!(stats.percentile("tile_ms", 50.0), 100);
+    assert_eq!(stats.percentile("tile_ms", 90.0), 180);
+    assert_eq!(stats.percentile("tile_ms", 99.0), 198);
+}
+
+#[test]
+fn drilldown_json() {
+    let mut stats = Statistics::new();
+    for (ms, count, bytes) in &[(12, 40, 2048), (30, 95, 5120)] {
+        stats.add("tile_ms.osm.points.10".to_string(), *ms);
+        stats.add("feature_count.osm.points.10".to_string(), *count);
+        stats.add("tile_bytes.osm.total.10".to_string(), *bytes);
+    }
+    let json = stats.as_json().unwrap();
+    let records = json.as_array().unwrap();
+    assert_eq!(records.len(), 3);
+    for rec in records {
+        for key in &["count", "min", "max", "p50", "p90", "p99"] {
+            assert!(rec[key].is_u64(), "{} in {}", key, rec);
+        }
+        for key in &["mean", "stddev"] {
+            assert!(rec[key].is_f64(), "{} in {}", key, rec);
+        }
+        assert!(rec["key"].is_string());
+        assert_eq!(rec["key1"], "osm");
+        assert_eq!(rec["key3"], "10");
+    }
+    let tile_ms = records.iter().find(|rec| rec["key0"] == "tile_ms").unwrap();
+    assert_eq!(tile_ms["key2"], "points");
+    assert_eq!(tile_ms["count"], 2);
+    assert_eq!(tile_ms["max"], 30);
+    assert_eq!(tile_ms["mean"], 21.0);
+}
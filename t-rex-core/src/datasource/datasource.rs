@@ -7,6 +7,7 @@ use crate::core::config::DatasourceCfg;
 use crate::core::feature::Feature;
 use crate::core::layer::Layer;
 use crate::core::Config;
+use std::time::SystemTime;
 use tile_grid::Extent;
 use tile_grid::Grid;
 
@@ -26,6 +27,7 @@ pub trait DatasourceType {
         src_srid: Option<i32>,
     ) -> Option<Extent>;
     /// Retrieve features of one layer. Return feature count.
+    /// Failed queries (e.g. canceled by a timeout) return an error instead of a partial count.
     fn retrieve_features<F>(
         &self,
         tileset: &str,
@@ -34,9 +36,41 @@ pub trait DatasourceType {
         zoom: u8,
         grid: &Grid,
         read: F,
-    ) -> u64
+    ) -> Result<u64, String>
     where
         F: FnMut(&dyn Feature);
+    /// Number of features of one layer `retrieve_features` would return
+    fn count_features(
+        &self,
+        tileset: &str,
+        layer: &Layer,
+        extent: &Extent,
+        zoom: u8,
+        grid: &Grid,
+    ) -> u64 {
+        self.retrieve_features(tileset, layer, extent, zoom, grid, |_| {})
+            .unwrap_or(0)
+    }
+    /// Layer encoded by the datasource as serialized vector tile.
+    /// None if server-side encoding is not supported.
+    fn retrieve_mvt_layer(
+        &self,
+        _tileset: &str,
+        _layer: &Layer,
+        _extent: &Extent,
+        _zoom: u8,
+        _grid: &Grid,
+    ) -> Option<Result<Vec<u8>, String>> {
+        None
+    }
+    /// Modification time of file based sources. Cached tiles older than the source are stale.
+    fn source_mtime(&self) -> Option<SystemTime> {
+        None
+    }
+    /// Data version returned by a query with a single value
+    fn query_version(&self, _sql: &str) -> Option<String> {
+        None
+    }
 }
 
 #[derive(Clone)]
@@ -72,11 +106,11 @@ impl DatasourceType for DummyDatasource {
         _zoom: u8,
         _grid: &Grid,
         _read: F,
-    ) -> u64
+    ) -> Result<u64, String>
     where
         F: FnMut(&dyn Feature),
     {
-        0
+        Ok(0)
     }
 }
 
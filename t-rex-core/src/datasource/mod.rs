@@ -8,6 +8,10 @@ mod postgis_ds;
 mod postgis_fields;
 #[cfg(test)]
 mod postgis_test;
+mod semaphore;
+#[cfg(test)]
+mod semaphore_test;
 
 pub use self::datasource::{DatasourceType, DummyDatasource};
 pub use self::postgis_ds::PostgisDatasource;
+pub use self::semaphore::{Semaphore, SemaphorePermit};
@@ -3,13 +3,18 @@
 // Licensed under the MIT License. See LICENSE file in the project root for full license information.
 //
 
-use crate::core::feature::{Feature, FeatureAttr, FeatureAttrValType};
+use crate::core::feature::{
+    fid_from_value, warn_non_integer_fid, Feature, FeatureAttr, FeatureAttrValType,
+};
 use crate::core::geom::*;
 use crate::core::layer::Layer;
 use postgres::types::{self, FromSql, Type};
 use postgres::Row;
 use std;
 
+/// Column with the label point of `label_point` layers
+pub(crate) const LABEL_POINT_FIELD: &str = "_label_point";
+
 impl GeometryType {
     /// Convert returned geometry to core::geom::GeometryType based on GeometryType name
     pub fn from_geom_field(row: &Row, idx: &str, type_name: &str) -> Result<GeometryType, String> {
@@ -55,11 +60,28 @@ impl GeometryType {
     }
 }
 
+/// Text of a `json` or `jsonb` value
+fn json_text(ty: &Type, raw: &[u8]) -> Result<String, Box<dyn std::error::Error + Sync + Send>> {
+    let text = if ty == &types::Type::JSONB {
+        // Binary format with version number prefix
+        match raw.split_first() {
+            Some((1, text)) => text,
+            _ => return Err("unsupported JSONB version".into()),
+        }
+    } else {
+        raw
+    };
+    Ok(std::str::from_utf8(text)?.to_string())
+}
+
 impl<'a> FromSql<'a> for FeatureAttrValType {
     fn accepts(ty: &Type) -> bool {
         match ty {
             &types::Type::VARCHAR
             | &types::Type::VARCHAR_ARRAY
+            | &types::Type::TEXT_ARRAY
+            | &types::Type::JSON
+            | &types::Type::JSONB
             | &types::Type::TEXT
             | &types::Type::CHAR_ARRAY
             | &types::Type::FLOAT4
@@ -78,6 +100,14 @@ impl<'a> FromSql<'a> for FeatureAttrValType {
             }
             &types::Type::VARCHAR_ARRAY => <Vec<String>>::from_sql(ty, raw)
                 .and_then(|v| Ok(FeatureAttrValType::VarcharArray(v))),
+            // Structured values as JSON string
+            &types::Type::TEXT_ARRAY => {
+                let v = <Vec<String>>::from_sql(ty, raw)?;
+                Ok(FeatureAttrValType::String(serde_json::to_string(&v)?))
+            }
+            &types::Type::JSON | &types::Type::JSONB => {
+                json_text(ty, raw).map(FeatureAttrValType::String)
+            }
             &types::Type::FLOAT4 => {
                 <f32>::from_sql(ty, raw).and_then(|v| Ok(FeatureAttrValType::Float(v)))
             }
@@ -113,10 +143,16 @@ pub(crate) struct FeatureRow<'a> {
 impl<'a> Feature for FeatureRow<'a> {
     fn fid(&self) -> Option<u64> {
         self.layer.fid_field.as_ref().and_then(|fid| {
-            let val = self.row.try_get::<_, FeatureAttrValType>(fid as &str);
-            match val {
-                Ok(FeatureAttrValType::Int(fid)) => Some(fid as u64),
-                _ => None,
+            match self
+                .row
+                .try_get::<_, Option<FeatureAttrValType>>(fid as &str)
+            {
+                Ok(val) => fid_from_value(self.layer, val),
+                // Unsupported column type
+                Err(_) => {
+                    warn_non_integer_fid(self.layer);
+                    None
+                }
             }
         })
     }
@@ -131,6 +167,7 @@ impl<'a> Feature for FeatureRow<'a> {
                     .as_ref()
                     .unwrap_or(&"".to_string())
                 && col.name() != self.layer.fid_field.as_ref().unwrap_or(&"".to_string())
+                && !(self.layer.has_label_layer() && col.name() == LABEL_POINT_FIELD)
             {
                 let val = self.row.try_get::<_, Option<FeatureAttrValType>>(i);
                 match val {
@@ -178,4 +215,11 @@ impl<'a> Feature for FeatureRow<'a> {
         }
         geom
     }
+    fn label_point(&self) -> Option<Point> {
+        // Selected on the unclipped geometry, so the point is the same in all tiles
+        match self.row.try_get::<_, Option<Point>>(LABEL_POINT_FIELD) {
+            Ok(point) => point,
+            Err(_) => point_on_surface(&self.geometry().ok()?),
+        }
+    }
 }
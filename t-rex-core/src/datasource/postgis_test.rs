@@ -6,7 +6,9 @@
 use crate::core::feature::FeatureAttrValType;
 use crate::core::geom::*;
 use crate::core::layer::{Layer, LayerQuery};
-use crate::datasource::postgis_ds::{PostgisDatasource, QueryParam};
+use crate::datasource::postgis_ds::{
+    parse_postgis_version, unsupported_postgis_features, PostgisDatasource, QueryParam,
+};
 use crate::datasource::DatasourceType;
 use postgres::{Client, NoTls};
 use std::env;
@@ -70,6 +72,50 @@ fn test_detect_layers() {
         .any(|ref layer| layer.name == "rivers_lake_centerlines"));
 }
 
+#[test]
+#[ignore]
+fn test_detect_views() {
+    let dbconn = match env::var("DBCONN") {
+        Result::Ok(val) => val,
+        Result::Err(_) => panic!("DBCONN undefined"),
+    };
+    let mut conn = Client::connect(&dbconn as &str, NoTls).unwrap();
+    // Untyped geometry column registered in geometry_columns with SRID 0
+    conn.batch_execute(
+        "DROP MATERIALIZED VIEW IF EXISTS ne.places_mview;
+         CREATE OR REPLACE VIEW ne.places_view AS
+           SELECT ogc_fid, wkb_geometry::geometry AS wkb_geometry FROM ne.ne_10m_populated_places;
+         CREATE MATERIALIZED VIEW ne.places_mview AS
+           SELECT ogc_fid, wkb_geometry::geometry AS wkb_geometry FROM ne.ne_10m_populated_places",
+    )
+    .unwrap();
+
+    let mut pg = PostgisDatasource::new(&dbconn, Some(1), None);
+    let srid = |layers: &[Layer], name: &str| {
+        layers
+            .iter()
+            .find(|layer| layer.name == name)
+            .and_then(|layer| layer.srid)
+    };
+    let layers = pg.connected().detect_layers(false);
+    assert_ne!(srid(&layers, "places_view"), Some(3857));
+
+    pg.detect_views = true;
+    let layers = pg.connected().detect_layers(false);
+    assert_eq!(srid(&layers, "places_view"), Some(3857));
+    assert_eq!(srid(&layers, "places_mview"), Some(3857));
+    let layer = layers.iter().find(|layer| layer.name == "places_mview").unwrap();
+    assert_eq!(layer.table_name, Some(r#""ne"."places_mview""#.to_string()));
+    assert_eq!(layer.geometry_field, Some("wkb_geometry".to_string()));
+    // Layers from geometry_columns are still detected
+    assert!(layers
+        .iter()
+        .any(|ref layer| layer.name == "rivers_lake_centerlines"));
+
+    conn.batch_execute("DROP VIEW ne.places_view; DROP MATERIALIZED VIEW ne.places_mview")
+        .unwrap();
+}
+
 #[test]
 #[ignore]
 fn test_detect_columns() {
@@ -118,6 +164,59 @@ fn test_extent_query() {
     );
 }
 
+#[test]
+fn test_postgis_version_requirements() {
+    assert_eq!(parse_postgis_version("3.4.2"), Some((3, 4, 2)));
+    assert_eq!(parse_postgis_version("3.5.0dev"), Some((3, 5, 0)));
+    assert_eq!(parse_postgis_version("2.5"), Some((2, 5, 0)));
+    assert_eq!(parse_postgis_version("unknown"), None);
+
+    let sql = "SELECT ST_AsMVTGeom(ST_MakeValid(geom, 'method=structure'), !bbox!)";
+    assert_eq!(
+        unsupported_postgis_features((2, 3, 0), sql),
+        vec![
+            ("ST_MakeValid(method=structure)", (3, 2)),
+            ("ST_AsMVTGeom", (2, 4))
+        ]
+    );
+    assert_eq!(
+        unsupported_postgis_features((3, 1, 4), sql),
+        vec![("ST_MakeValid(method=structure)", (3, 2))]
+    );
+    assert!(unsupported_postgis_features((3, 2, 0), sql).is_empty());
+}
+
+#[test]
+#[ignore]
+fn test_postgis_version() {
+    let pg: PostgisDatasource = match env::var("DBCONN") {
+        Result::Ok(val) => Some(PostgisDatasource::new(&val, Some(1), None).connected()),
+        Result::Err(_) => panic!("DBCONN undefined"),
+    }
+    .unwrap();
+    let version = pg.postgis_version().unwrap();
+    assert!(version >= (2, 0, 0));
+    assert!(unsupported_postgis_features(version, "SELECT ST_MakeEnvelope(0,0,1,1)").is_empty());
+    assert_eq!(pg.version_warnings(), Ok(Vec::new()));
+}
+
+#[test]
+fn test_json_attributes() {
+    use postgres::types::{FromSql, Type};
+
+    assert!(<FeatureAttrValType as FromSql>::accepts(&Type::JSONB));
+    assert!(<FeatureAttrValType as FromSql>::accepts(&Type::TEXT_ARRAY));
+    assert_eq!(
+        FeatureAttrValType::from_sql(&Type::JSONB, b"\x01{\"name\": \"Bern\"}").unwrap(),
+        FeatureAttrValType::String(r#"{"name": "Bern"}"#.to_string())
+    );
+    assert_eq!(
+        FeatureAttrValType::from_sql(&Type::JSON, b"[1, 2]").unwrap(),
+        FeatureAttrValType::String("[1, 2]".to_string())
+    );
+    assert!(FeatureAttrValType::from_sql(&Type::JSONB, b"\x02{}").is_err());
+}
+
 #[test]
 fn test_feature_query() {
     let pg = PostgisDatasource::new("postgresql://pi@localhost/osm2vectortiles", Some(1), None);
@@ -203,9 +302,25 @@ fn test_feature_query() {
     layer.query_limit = Some(1);
     assert_eq!(
         pg.build_query(&layer, 3857, 10, None).unwrap().sql,
-        // No LIMIT clause added - limited when retrieving records
-        "SELECT geometry FROM osm_place_point WHERE geometry && ST_MakeEnvelope($1,$2,$3,$4,3857)"
+        "SELECT geometry FROM osm_place_point WHERE geometry && ST_MakeEnvelope($1,$2,$3,$4,3857) LIMIT 1"
+    );
+    layer.order_by = Some("population".to_string());
+    layer.order_desc = true;
+    assert_eq!(
+        pg.build_query(&layer, 3857, 10, None).unwrap().sql,
+        "SELECT geometry FROM osm_place_point WHERE geometry && ST_MakeEnvelope($1,$2,$3,$4,3857) ORDER BY \"population\" DESC NULLS LAST LIMIT 1"
+    );
+    layer.order_desc = false;
+    assert_eq!(
+        pg.build_query(&layer, 3857, 10, None).unwrap().sql,
+        "SELECT geometry FROM osm_place_point WHERE geometry && ST_MakeEnvelope($1,$2,$3,$4,3857) ORDER BY \"population\" ASC NULLS LAST LIMIT 1"
     );
+    layer.order_by = Some(r#"pop "max""#.to_string());
+    assert_eq!(
+        pg.build_query(&layer, 3857, 10, None).unwrap().sql,
+        r#"SELECT geometry FROM osm_place_point WHERE geometry && ST_MakeEnvelope($1,$2,$3,$4,3857) ORDER BY "pop ""max""" ASC NULLS LAST LIMIT 1"#
+    );
+    layer.order_by = None;
 
     // user queries
     layer.query = vec![LayerQuery {
@@ -235,6 +350,21 @@ fn test_feature_query() {
                    .sql,
                "SELECT * FROM (SELECT * FROM osm_place_point WHERE name='Bern') AS _q WHERE geometry && ST_MakeEnvelope($1,$2,$3,$4,3857)");
 
+    // LIMIT applies to the outer query
+    layer.query_limit = Some(100);
+    assert_eq!(pg.build_query(&layer, 3857, 10, layer.query[0].sql.as_ref())
+                   .unwrap()
+                   .sql,
+               "SELECT * FROM (SELECT * FROM osm_place_point WHERE name='Bern') AS _q WHERE geometry && ST_MakeEnvelope($1,$2,$3,$4,3857) LIMIT 100");
+    layer.query[0].sql = Some(String::from(
+        "SELECT * FROM osm_place_point WHERE geometry && !bbox!",
+    ));
+    assert_eq!(pg.build_query(&layer, 3857, 10, layer.query[0].sql.as_ref())
+                   .unwrap()
+                   .sql,
+               "SELECT * FROM (SELECT * FROM osm_place_point WHERE geometry && ST_MakeEnvelope($1,$2,$3,$4,3857)) AS _q LIMIT 100");
+    layer.query_limit = None;
+
     // out of maxzoom
     //assert_eq!(pg.query(&layer, 23).unwrap().sql,
     //    "SELECT * FROM (SELECT geometry FROM osm_place_point) AS _q WHERE geometry && ST_MakeEnvelope($1,$2,$3,$4,3857)");
@@ -242,6 +372,161 @@ fn test_feature_query() {
     //assert!(pg.query(&layer, 23).is_none());
 }
 
+#[test]
+fn test_detect_geometry_type_cached() {
+    use crate::datasource::postgis_ds::detect_geometry_type_cached;
+    use std::collections::HashMap;
+
+    let layer = |name: &str, table: &str| {
+        let mut layer = Layer::new(name);
+        layer.table_name = Some(String::from(table));
+        layer.geometry_field = Some(String::from("geom"));
+        layer
+    };
+    let mut detected_types = HashMap::new();
+    let mut queries = 0;
+    let mut detect = |layer: &Layer, types: Vec<&str>| {
+        detect_geometry_type_cached(layer, &mut detected_types, |_| {
+            queries += 1;
+            types.iter().map(|t| t.to_string()).collect()
+        })
+    };
+    assert_eq!(
+        detect(&layer("roads", "osm"), vec!["LINESTRING"]),
+        "LINESTRING"
+    );
+    // Same table and column is detected only once
+    assert_eq!(detect(&layer("rails", "osm"), vec!["POINT"]), "LINESTRING");
+    assert_eq!(
+        detect(&layer("mixed", "mixed"), vec!["POINT", "POLYGON"]),
+        "GEOMETRY"
+    );
+    assert_eq!(detect(&layer("mixed2", "mixed"), vec![]), "GEOMETRY");
+    assert_eq!(queries, 2);
+}
+
+#[test]
+fn test_curve_query() {
+    use crate::core::layer::CurveTolerance;
+
+    let pg = PostgisDatasource::new("postgresql://pi@localhost/osm2vectortiles", Some(1), None);
+    let mut layer = Layer::new("areas");
+    layer.table_name = Some(String::from("curved_areas"));
+    layer.geometry_field = Some(String::from("geometry"));
+    layer.geometry_type = Some(String::from("CURVEPOLYGON"));
+    layer.srid = Some(3857);
+    assert_eq!(
+        pg.build_query(&layer, 3857, 10, None).unwrap().sql,
+        "SELECT ST_Multi(ST_CurveToLine(geometry)) AS geometry FROM curved_areas WHERE geometry && ST_MakeEnvelope($1,$2,$3,$4,3857)"
+    );
+
+    layer.tolerance = "!pixel_width!/2".to_string();
+    layer.curve_tolerance = Some(CurveTolerance::Tolerance);
+    let query = pg.build_query(&layer, 3857, 10, None).unwrap();
+    assert_eq!(
+        query.sql,
+        "SELECT ST_Multi(ST_CurveToLine(geometry, $5::FLOAT8/2, 1, 0)) AS geometry FROM curved_areas WHERE geometry && ST_MakeEnvelope($1,$2,$3,$4,3857)"
+    );
+    assert_eq!(
+        format!("{:?}", query.params),
+        "[Bbox, PixelWidth]".to_string()
+    );
+
+    layer.geometry_type = Some(String::from("COMPOUNDCURVE"));
+    layer.curve_tolerance = Some(CurveTolerance::Value("0.5".to_string()));
+    assert_eq!(
+        pg.build_query(&layer, 3857, 10, None).unwrap().sql,
+        "SELECT ST_Multi(ST_CurveToLine(geometry, 0.5, 1, 0)) AS geometry FROM curved_areas WHERE geometry && ST_MakeEnvelope($1,$2,$3,$4,3857)"
+    );
+}
+
+#[test]
+fn test_st_asmvtgeom_query() {
+    let pg = PostgisDatasource::new("postgresql://pi@localhost/osm2vectortiles", Some(1), None);
+    let mut layer = Layer::new("buildings");
+    layer.table_name = Some(String::from("osm_buildings"));
+    layer.geometry_field = Some(String::from("geometry"));
+    layer.geometry_type = Some(String::from("POLYGON"));
+    layer.srid = Some(3857);
+    layer.buffer_size = Some(10);
+    layer.simplify = true;
+    layer.tolerance = "!pixel_width!/2".to_string();
+    layer.use_st_asmvtgeom = true;
+    let query = pg.build_query(&layer, 3857, 10, None).unwrap();
+    assert_eq!(
+        query.sql,
+        "SELECT ST_AsMVTGeom(ST_Multi(geometry),ST_MakeEnvelope($1,$2,$3,$4,3857),4096,160,true) AS geometry FROM osm_buildings WHERE geometry && ST_MakeEnvelope($1-0.625*$5::FLOAT8,$2-0.625*$5::FLOAT8,$3+0.625*$5::FLOAT8,$4+0.625*$5::FLOAT8,3857)"
+    );
+
+    layer.srid = Some(2056);
+    layer.buffer_size = None;
+    layer.tile_size = 512;
+    assert_eq!(
+        pg.build_query(&layer, 3857, 10, None).unwrap().sql,
+        "SELECT ST_AsMVTGeom(ST_Transform(ST_Multi(geometry),3857),ST_MakeEnvelope($1,$2,$3,$4,3857),512,0,false) AS geometry FROM osm_buildings WHERE geometry && ST_Transform(ST_Segmentize(ST_MakeEnvelope($1,$2,$3,$4,3857), ($3-$1)/512), 2056)"
+    );
+}
+
+#[test]
+fn test_label_point_query() {
+    let pg = PostgisDatasource::new("postgresql://pi@localhost/osm2vectortiles", Some(1), None);
+    let mut layer = Layer::new("buildings");
+    layer.table_name = Some(String::from("osm_buildings"));
+    layer.geometry_field = Some(String::from("geometry"));
+    layer.geometry_type = Some(String::from("POLYGON"));
+    layer.srid = Some(2056);
+    layer.buffer_size = Some(0);
+    layer.label_point = true;
+    // Label point of the unclipped geometry
+    assert_eq!(
+        pg.build_query(&layer, 3857, 10, None).unwrap().sql,
+        "SELECT ST_Transform(ST_Multi(ST_Buffer(ST_Intersection(geometry,ST_Transform(ST_Segmentize(ST_MakeEnvelope($1,$2,$3,$4,3857), ($3-$1)/512), 2056)), 0.0)),3857) AS geometry,ST_Transform(ST_PointOnSurface(geometry),3857) AS _label_point FROM osm_buildings WHERE geometry && ST_Transform(ST_Segmentize(ST_MakeEnvelope($1,$2,$3,$4,3857), ($3-$1)/512), 2056)"
+    );
+
+    layer.srid = Some(3857);
+    layer.use_st_asmvtgeom = true;
+    let sql = String::from("SELECT geometry FROM osm_buildings");
+    assert_eq!(
+        pg.build_query(&layer, 3857, 10, Some(&sql)).unwrap().sql,
+        "SELECT *,ST_AsMVTGeom(ST_PointOnSurface(geometry),ST_MakeEnvelope($1,$2,$3,$4,3857),4096,0,true) AS _label_point FROM (SELECT geometry FROM osm_buildings) AS _q WHERE geometry && ST_MakeEnvelope($1,$2,$3,$4,3857)"
+    );
+
+    // Not supported with server-side MVT encoding
+    layer.server_side_mvt = true;
+    assert!(!pg
+        .build_query(&layer, 3857, 10, None)
+        .unwrap()
+        .sql
+        .contains("_label_point"));
+}
+
+#[test]
+fn test_mvt_query() {
+    let pg = PostgisDatasource::new("postgresql://pi@localhost/osm2vectortiles", Some(1), None);
+    let mut layer = Layer::new("points");
+    layer.table_name = Some(String::from("osm_place_point"));
+    layer.geometry_field = Some(String::from("geometry"));
+    layer.geometry_type = Some(String::from("POINT"));
+    layer.srid = Some(3857);
+    layer.server_side_mvt = true;
+    let query = pg.build_query(&layer, 3857, 10, None).unwrap();
+    assert_eq!(
+        query.sql,
+        "SELECT ST_AsMVTGeom(geometry,ST_MakeEnvelope($1,$2,$3,$4,3857),4096,0,false) AS geometry FROM osm_place_point WHERE geometry && ST_MakeEnvelope($1,$2,$3,$4,3857)"
+    );
+    assert_eq!(
+        pg.build_mvt_query(&layer, &query.sql),
+        format!("SELECT ST_AsMVT(_mvt, 'points', 4096, 'geometry') FROM ({}) AS _mvt WHERE geometry IS NOT NULL", query.sql)
+    );
+
+    layer.name = "o'points".to_string();
+    layer.fid_field = Some(String::from("o'id"));
+    assert_eq!(
+        pg.build_mvt_query(&layer, "SELECT 1"),
+        "SELECT ST_AsMVT(_mvt, 'o''points', 4096, 'geometry', 'o''id') FROM (SELECT 1) AS _mvt WHERE geometry IS NOT NULL"
+    );
+}
+
 #[test]
 fn test_config_template() {
     let pg = PostgisDatasource::new("postgresql://pi@localhost/osm2vectortiles", Some(1), None);
@@ -310,6 +595,62 @@ fn test_query_params() {
     assert_eq!(query.params, [QueryParam::Bbox, QueryParam::PixelWidth]);
 }
 
+#[test]
+fn test_statement_timeout_config() {
+    use crate::core::config::DatasourceCfg;
+    use crate::core::{parse_config, Config};
+
+    let toml = r#"
+        dbconn = "postgresql://pi@localhost/osm2vectortiles"
+        statement_timeout_ms = 500
+        "#;
+    let ds_cfg: DatasourceCfg = parse_config(toml.to_string(), "").unwrap();
+    let pg = PostgisDatasource::from_config(&ds_cfg).unwrap();
+    assert_eq!(pg.statement_timeout, Some(500));
+    assert!(pg
+        .gen_runtime_config()
+        .contains("statement_timeout_ms = 500\n"));
+    assert!(!pg.detect_views);
+
+    let toml = r#"
+        dbconn = "postgresql://pi@localhost/osm2vectortiles"
+        detect_views = true
+        "#;
+    let ds_cfg: DatasourceCfg = parse_config(toml.to_string(), "").unwrap();
+    let pg = PostgisDatasource::from_config(&ds_cfg).unwrap();
+    assert!(pg.detect_views);
+    assert!(pg.gen_runtime_config().contains("detect_views = true\n"));
+}
+
+#[test]
+#[ignore]
+fn test_statement_timeout() {
+    let mut pg = match env::var("DBCONN") {
+        Result::Ok(val) => PostgisDatasource::new(&val, Some(1), None),
+        Result::Err(_) => panic!("DBCONN undefined"),
+    };
+    pg.statement_timeout = Some(100);
+    let mut pg = pg.connected();
+
+    let mut layer = Layer::new("points");
+    layer.geometry_field = Some(String::from("wkb_geometry"));
+    layer.geometry_type = Some(String::from("POINT"));
+    layer.query = vec![LayerQuery {
+        minzoom: 0,
+        maxzoom: Some(22),
+        simplify: None,
+        tolerance: None,
+        sql: Some(String::from(
+            "SELECT wkb_geometry FROM ne.ne_10m_populated_places, pg_sleep(1)",
+        )),
+    }];
+    let grid = Grid::web_mercator();
+    pg.prepare_queries("ts", &layer, 3857);
+    // Canceled query fails instead of returning a partial result
+    let result = pg.retrieve_features("ts", &layer, &grid.extent, 10, &grid, |_| {});
+    assert!(result.unwrap_err().contains("statement timeout"));
+}
+
 #[test]
 #[ignore]
 fn test_retrieve_features() {
@@ -341,7 +682,8 @@ fn test_retrieve_features() {
         assert_eq!(4, feat.attributes().len());
         assert_eq!(None, feat.fid());
         reccnt += 1;
-    });
+    })
+    .unwrap();
     assert_eq!(1, reccnt);
 
     layer.query = vec![LayerQuery {
@@ -367,12 +709,211 @@ fn test_retrieve_features() {
             FeatureAttrValType::String("Bern".to_string())
         );
         assert_eq!(feat.fid(), Some(6478));
-    });
+    })
+    .unwrap();
 
-    let cnt = pg.retrieve_features("ts", &layer, &grid.extent, 10, &grid, |_| {});
+    let cnt = pg
+        .retrieve_features("ts", &layer, &grid.extent, 10, &grid, |_| {})
+        .unwrap();
     assert_eq!(cnt, 7321);
 }
 
+#[test]
+fn test_geometry_type_filter_query() {
+    let pg = PostgisDatasource::new("postgresql://pi@localhost/osm2vectortiles", Some(1), None);
+    let mut layer = Layer::new("buildings");
+    layer.table_name = Some(String::from("osm_features"));
+    layer.geometry_field = Some(String::from("geometry"));
+    layer.geometry_type = Some(String::from("GEOMETRY"));
+    layer.srid = Some(3857);
+    layer.geometry_type_filter = Some(String::from("POLYGON"));
+    assert_eq!(
+        pg.build_query(&layer, 3857, 10, None).unwrap().sql,
+        "SELECT ST_Multi(geometry) AS geometry FROM osm_features WHERE geometry && ST_MakeEnvelope($1,$2,$3,$4,3857) AND GeometryType(geometry) IN ('POLYGON','MULTIPOLYGON')"
+    );
+
+    // Clipping of polygons
+    layer.buffer_size = Some(0);
+    assert_eq!(
+        pg.build_query(&layer, 3857, 10, None).unwrap().sql,
+        "SELECT ST_Multi(ST_Buffer(ST_Intersection(geometry,ST_MakeEnvelope($1,$2,$3,$4,3857)), 0.0)) AS geometry FROM osm_features WHERE geometry && ST_MakeEnvelope($1,$2,$3,$4,3857) AND GeometryType(geometry) IN ('POLYGON','MULTIPOLYGON')"
+    );
+    layer.buffer_size = None;
+
+    // User queries
+    let sql = String::from("SELECT geometry FROM osm_features");
+    layer.geometry_type_filter = Some(String::from("LINESTRING"));
+    assert_eq!(
+        pg.build_query(&layer, 3857, 10, Some(&sql)).unwrap().sql,
+        "SELECT * FROM (SELECT geometry FROM osm_features) AS _q WHERE geometry && ST_MakeEnvelope($1,$2,$3,$4,3857) AND GeometryType(geometry) IN ('LINESTRING','MULTILINESTRING')"
+    );
+    let sql = String::from("SELECT geometry FROM osm_features WHERE geometry && !bbox!");
+    assert_eq!(
+        pg.build_query(&layer, 3857, 10, Some(&sql)).unwrap().sql,
+        "SELECT * FROM (SELECT geometry FROM osm_features WHERE geometry && ST_MakeEnvelope($1,$2,$3,$4,3857)) AS _q WHERE GeometryType(geometry) IN ('LINESTRING','MULTILINESTRING')"
+    );
+
+    // Explicit geometry type of layer
+    layer.geometry_type = Some(String::from("POINT"));
+    layer.geometry_type_filter = Some(String::from("POINT"));
+    assert_eq!(
+        pg.build_query(&layer, 3857, 10, None).unwrap().sql,
+        "SELECT geometry FROM osm_features WHERE geometry && ST_MakeEnvelope($1,$2,$3,$4,3857) AND GeometryType(geometry) IN ('POINT','MULTIPOINT')"
+    );
+}
+
+#[test]
+fn test_computed_attributes_query() {
+    use crate::core::layer::ComputedAttr;
+
+    let pg = PostgisDatasource::new("postgresql://pi@localhost/osm2vectortiles", Some(1), None);
+    let mut layer = Layer::new("buildings");
+    layer.table_name = Some(String::from("osm_buildings"));
+    layer.geometry_field = Some(String::from("geometry"));
+    layer.srid = Some(3857);
+    layer.computed = vec![
+        ComputedAttr {
+            name: String::from("area"),
+            expr: String::from("ST_Area(geometry)"),
+        },
+        ComputedAttr {
+            name: String::from(r#"height "m""#),
+            expr: String::from("levels * 3"),
+        },
+    ];
+    assert_eq!(
+        pg.build_query(&layer, 3857, 10, None).unwrap().sql,
+        r#"SELECT geometry,(ST_Area(geometry)) AS "area",(levels * 3) AS "height ""m""" FROM osm_buildings WHERE geometry && ST_MakeEnvelope($1,$2,$3,$4,3857)"#
+    );
+
+    let sql = String::from("SELECT geometry, levels FROM osm_buildings");
+    assert_eq!(
+        pg.build_query(&layer, 3857, 10, Some(&sql)).unwrap().sql,
+        r#"SELECT *,(ST_Area(geometry)) AS "area",(levels * 3) AS "height ""m""" FROM (SELECT geometry, levels FROM osm_buildings) AS _q WHERE geometry && ST_MakeEnvelope($1,$2,$3,$4,3857)"#
+    );
+
+    // Computed attributes are configured separately from the query template
+    assert_eq!(
+        pg.build_query_sql_template(&layer),
+        "SELECT geometry FROM osm_buildings"
+    );
+}
+
+#[test]
+#[ignore]
+fn test_computed_attributes_cast() {
+    use crate::core::layer::ComputedAttr;
+
+    let pg: PostgisDatasource = match env::var("DBCONN") {
+        Result::Ok(val) => Some(PostgisDatasource::new(&val, Some(1), None).connected()),
+        Result::Err(_) => panic!("DBCONN undefined"),
+    }
+    .unwrap();
+    let mut layer = Layer::new("points");
+    layer.table_name = Some(String::from("ne.ne_10m_populated_places"));
+    layer.geometry_field = Some(String::from("wkb_geometry"));
+    layer.computed = vec![
+        ComputedAttr {
+            name: String::from("ratio"),
+            expr: String::from("pop_max::numeric / 1000"),
+        },
+        ComputedAttr {
+            name: String::from("label"),
+            expr: String::from("upper(name)"),
+        },
+    ];
+    let sql = pg.build_query(&layer, 3857, 10, None).unwrap().sql;
+    // numeric results are cast like data columns
+    assert!(
+        sql.contains(r#",(pop_max::numeric / 1000)::FLOAT8 AS "ratio",(upper(name)) AS "label""#),
+        "{}",
+        sql
+    );
+}
+
+#[test]
+#[ignore]
+fn test_retrieve_filtered_geometry_types() {
+    let mut pg: PostgisDatasource = match env::var("DBCONN") {
+        Result::Ok(val) => Some(PostgisDatasource::new(&val, Some(1), None).connected()),
+        Result::Err(_) => panic!("DBCONN undefined"),
+    }
+    .unwrap();
+
+    // Points and polygons in one geometry column
+    let mut layer = Layer::new("places");
+    layer.geometry_field = Some(String::from("wkb_geometry"));
+    layer.geometry_type = Some(String::from("GEOMETRY"));
+    layer.srid = Some(3857);
+    layer.query = vec![LayerQuery {
+        minzoom: 0,
+        maxzoom: Some(22),
+        simplify: None,
+        tolerance: None,
+        sql: Some(String::from(
+            "SELECT wkb_geometry FROM ne.ne_10m_populated_places UNION ALL SELECT ST_Buffer(wkb_geometry, 1000) AS wkb_geometry FROM ne.ne_10m_populated_places",
+        )),
+    }];
+    let grid = Grid::web_mercator();
+    let extent = Extent {
+        minx: 821850.9,
+        miny: 5909499.5,
+        maxx: 860986.7,
+        maxy: 5948635.3,
+    };
+
+    pg.prepare_queries("ts", &layer, 3857);
+    let cnt = pg
+        .retrieve_features("ts", &layer, &extent, 10, &grid, |_| {})
+        .unwrap();
+    assert_eq!(cnt, 2);
+
+    for (filter, geometry_type) in &[("POLYGON", "MULTIPOLYGON"), ("POINT", "POINT")] {
+        layer.geometry_type_filter = Some(filter.to_string());
+        pg.prepare_queries("ts", &layer, 3857);
+        let cnt = pg
+            .retrieve_features("ts", &layer, &extent, 10, &grid, |feat| {
+                assert_eq!(feat.geometry().unwrap().type_name(), *geometry_type);
+            })
+            .unwrap();
+        assert_eq!(cnt, 1);
+    }
+}
+
+#[test]
+#[ignore]
+fn test_count_features() {
+    let mut pg: PostgisDatasource = match env::var("DBCONN") {
+        Result::Ok(val) => Some(PostgisDatasource::new(&val, Some(1), None).connected()),
+        Result::Err(_) => panic!("DBCONN undefined"),
+    }
+    .unwrap();
+
+    let mut layer = Layer::new("points");
+    layer.table_name = Some(String::from("ne.ne_10m_populated_places"));
+    layer.geometry_field = Some(String::from("wkb_geometry"));
+    layer.geometry_type = Some(String::from("POINT"));
+    let grid = Grid::web_mercator();
+    pg.prepare_queries("ts", &layer, 3857);
+    for zoom in 0..4 {
+        let extent = grid.tile_extent(1, 1, zoom);
+        let cnt = pg
+            .retrieve_features("ts", &layer, &extent, zoom, &grid, |_| {})
+            .unwrap();
+        assert!(cnt > 0);
+        assert_eq!(pg.count_features("ts", &layer, &extent, zoom, &grid), cnt);
+    }
+
+    // Count is limited by query_limit
+    layer.query_limit = Some(10);
+    pg.prepare_queries("ts", &layer, 3857);
+    assert_eq!(
+        pg.count_features("ts", &layer, &grid.extent, 10, &grid),
+        pg.retrieve_features("ts", &layer, &grid.extent, 10, &grid, |_| {})
+            .unwrap()
+    );
+}
+
 #[test]
 #[ignore]
 #[should_panic(expected = "geometry_field undefined")]
@@ -0,0 +1,70 @@
+//
+// Copyright (c) Pirmin Kalberer. All rights reserved.
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+//
+
+use crate::core::config::DatasourceCfg;
+use crate::core::Config;
+use crate::datasource::semaphore::Semaphore;
+use crate::datasource::PostgisDatasource;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn test_semaphore() {
+    let semaphore = Arc::new(Semaphore::new(2));
+    let active = Arc::new(AtomicUsize::new(0));
+    let max_active = Arc::new(AtomicUsize::new(0));
+    let threads = (0..8)
+        .map(|_| {
+            let semaphore = semaphore.clone();
+            let active = active.clone();
+            let max_active = max_active.clone();
+            thread::spawn(move || {
+                let _permit = semaphore.acquire();
+                let cnt = active.fetch_add(1, Ordering::SeqCst) + 1;
+                max_active.fetch_max(cnt, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(10));
+                active.fetch_sub(1, Ordering::SeqCst);
+            })
+        })
+        .collect::<Vec<_>>();
+    for t in threads {
+        t.join().unwrap();
+    }
+    assert!(max_active.load(Ordering::SeqCst) <= 2);
+    assert_eq!(active.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn test_max_concurrent_queries_config() {
+    let ds_cfg: DatasourceCfg = toml::from_str(
+        r#"
+        dbconn = "postgresql://pi@localhost/osm2vectortiles"
+        max_concurrent_queries = 4
+        "#,
+    )
+    .unwrap();
+    let ds = PostgisDatasource::from_config(&ds_cfg).unwrap();
+    assert_eq!(ds.max_concurrent_queries, Some(4));
+    assert!(ds
+        .gen_runtime_config()
+        .contains("max_concurrent_queries = 4\n"));
+}
+
+#[test]
+fn test_max_concurrent_queries_zero() {
+    let ds_cfg: DatasourceCfg = toml::from_str(
+        r#"
+        dbconn = "postgresql://pi@localhost/osm2vectortiles"
+        max_concurrent_queries = 0
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        PostgisDatasource::from_config(&ds_cfg).err(),
+        Some("Invalid max_concurrent_queries 0 (expected >= 1)".to_string())
+    );
+}
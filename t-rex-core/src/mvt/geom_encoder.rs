@@ -3,11 +3,15 @@
 // Licensed under the MIT License. See LICENSE file in the project root for full license information.
 //
 
-//! Encode geometries according to MVT spec
+//! Encode and decode geometries according to MVT spec
 //! https://github.com/mapbox/vector-tile-spec/tree/master/2.1
 
+use crate::core::geom::{self, GeometryType};
 use crate::core::screen;
+use crate::mvt::vector_tile;
+use postgis::ewkb;
 use std::vec::Vec;
+use tile_grid::Extent;
 
 /// Command to be executed and the number of times that the command will be executed
 /// https://github.com/mapbox/vector-tile-spec/tree/master/2.1#431-command-integers
@@ -23,11 +27,9 @@ impl CommandInteger {
     fn new(id: Command, count: u32) -> CommandInteger {
         CommandInteger(((id as u32) & 0x7) | (count << 3))
     }
-    #[cfg(test)]
     fn id(&self) -> u32 {
         self.0 & 0x7
     }
-    #[cfg(test)]
     fn count(&self) -> u32 {
         self.0 >> 3
     }
@@ -51,7 +53,6 @@ impl ParameterInteger {
     fn new(value: i32) -> ParameterInteger {
         ParameterInteger(((value << 1) ^ (value >> 31)) as u32)
     }
-    #[cfg(test)]
     fn value(&self) -> i32 {
         ((self.0 >> 1) as i32) ^ (-((self.0 & 1) as i32))
     }
@@ -193,3 +194,139 @@ impl EncodableGeom for screen::MultiPolygon {
         }
     }
 }
+
+// --- Decoding
+
+/// Decode command sequence into lines starting with a MoveTo command.
+/// ClosePath repeats the first point of a line.
+fn decode_lines(geom: &[u32]) -> Vec<screen::LineString> {
+    let mut lines: Vec<screen::LineString> = Vec::new();
+    let (mut x, mut y) = (0i32, 0i32);
+    let mut i = 0;
+    while i < geom.len() {
+        let cmd = CommandInteger(geom[i]);
+        i += 1;
+        let id = cmd.id();
+        if id == Command::MoveTo as u32 || id == Command::LineTo as u32 {
+            for _ in 0..cmd.count() {
+                if i + 1 >= geom.len() {
+                    warn!("Truncated geometry command sequence");
+                    return lines;
+                }
+                x = x.wrapping_add(ParameterInteger(geom[i]).value());
+                y = y.wrapping_add(ParameterInteger(geom[i + 1]).value());
+                i += 2;
+                if id == Command::MoveTo as u32 || lines.is_empty() {
+                    lines.push(screen::LineString { points: Vec::new() });
+                }
+                if let Some(line) = lines.last_mut() {
+                    line.points.push(screen::Point { x, y });
+                }
+            }
+        } else if id == Command::ClosePath as u32 {
+            if let Some(line) = lines.last_mut() {
+                if let Some(first) = line.points.first() {
+                    let first = screen::Point {
+                        x: first.x,
+                        y: first.y,
+                    };
+                    line.points.push(first);
+                }
+            }
+        } else {
+            warn!("Invalid geometry command {}", id);
+            return lines;
+        }
+    }
+    lines
+}
+
+/// Decode MVT command sequence into geometry in world coordinates
+pub fn decode_commands(
+    geom: &[u32],
+    geom_type: vector_tile::Tile_GeomType,
+    extent: &Extent,
+    tile_size: u32,
+) -> GeometryType {
+    let pixel_size_x = (extent.maxx - extent.minx) / tile_size as f64;
+    let pixel_size_y = (extent.maxy - extent.miny) / tile_size as f64;
+    let world_line = |line: &screen::LineString| geom::LineString {
+        points: line
+            .points
+            .iter()
+            .map(|p| {
+                geom::Point::new(
+                    extent.minx + p.x as f64 * pixel_size_x,
+                    extent.maxy - p.y as f64 * pixel_size_y,
+                    None,
+                )
+            })
+            .collect(),
+        srid: None,
+    };
+    let lines = decode_lines(geom);
+    match geom_type {
+        vector_tile::Tile_GeomType::POINT => {
+            let mut points = lines
+                .iter()
+                .flat_map(|line| world_line(line).points)
+                .collect::<Vec<_>>();
+            if points.len() == 1 {
+                GeometryType::Point(points.remove(0))
+            } else {
+                GeometryType::MultiPoint(ewkb::MultiPoint { points, srid: None })
+            }
+        }
+        vector_tile::Tile_GeomType::LINESTRING => {
+            // Skip invalid lines with a single point
+            let mut lines = lines
+                .iter()
+                .filter(|line| line.points.len() > 1)
+                .map(world_line)
+                .collect::<Vec<_>>();
+            if lines.len() == 1 {
+                GeometryType::LineString(lines.remove(0))
+            } else {
+                GeometryType::MultiLineString(ewkb::MultiLineString { lines, srid: None })
+            }
+        }
+        vector_tile::Tile_GeomType::POLYGON => {
+            // A ring with the winding order of the first ring starts a new polygon
+            let mut polygons: Vec<geom::Polygon> = Vec::new();
+            let exterior_positive = lines
+                .iter()
+                .map(|ring| ring.ring_area())
+                .find(|area| *area != 0)
+                .map_or(true, |area| area > 0);
+            for ring in &lines {
+                let area = ring.ring_area();
+                if area == 0 {
+                    continue;
+                }
+                if polygons.is_empty() || (area > 0) == exterior_positive {
+                    polygons.push(ewkb::Polygon {
+                        rings: Vec::new(),
+                        srid: None,
+                    });
+                }
+                if let Some(polygon) = polygons.last_mut() {
+                    polygon.rings.push(world_line(ring));
+                }
+            }
+            if polygons.len() == 1 {
+                GeometryType::Polygon(polygons.remove(0))
+            } else {
+                GeometryType::MultiPolygon(ewkb::MultiPolygon {
+                    polygons,
+                    srid: None,
+                })
+            }
+        }
+        vector_tile::Tile_GeomType::UNKNOWN => {
+            GeometryType::GeometryCollection(ewkb::GeometryCollection {
+                geometries: Vec::new(),
+                srid: None,
+            })
+        }
+    }
+}
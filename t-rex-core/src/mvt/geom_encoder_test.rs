@@ -3,6 +3,7 @@
 // Licensed under the MIT License. See LICENSE file in the project root for full license information.
 //
 
+use crate::core::geom::GeometryType;
 use crate::core::screen;
 use crate::mvt::geom_encoder::EncodableGeom;
 
@@ -135,3 +136,164 @@ fn test_overflow() {
         &[17, 9, 19, u32::MAX - 1, u32::MAX - 1]
     );
 }
+
+/// Encode geometry and decode it again
+fn roundtrip_wkt(wkt_geom: GeometryType) -> String {
+    use crate::core::layer::Layer;
+    use crate::mvt::geom_encoder::decode_commands;
+    use crate::mvt::tile::Tile;
+    use tile_grid::Extent;
+
+    // One world unit per pixel
+    let extent = Extent {
+        minx: 1000.0,
+        miny: 2000.0,
+        maxx: 5096.0,
+        maxy: 6096.0,
+    };
+    let mut tile = Tile::new(&extent, true);
+    let layer = Layer::new("roundtrip");
+    tile.new_layer(&layer);
+    let geom_type = wkt_geom.mvt_field_type();
+    let commands = tile.encode_geom(wkt_geom).vec();
+    decode_commands(&commands, geom_type, &extent, layer.tile_size).to_wkt()
+}
+
+#[test]
+fn test_decode_roundtrip() {
+    use crate::core::geom::Point;
+    use postgis::ewkb;
+
+    let line = |coords: &[(f64, f64)]| ewkb::LineString {
+        points: coords
+            .iter()
+            .map(|&(x, y)| Point::new(x, y, None))
+            .collect(),
+        srid: None,
+    };
+
+    let point = GeometryType::Point(Point::new(1025.0, 2017.0, None));
+    assert_eq!(roundtrip_wkt(point), "POINT(1025 2017)");
+
+    let multipoint = GeometryType::MultiPoint(ewkb::MultiPoint {
+        points: vec![
+            Point::new(1005.0, 2007.0, None),
+            Point::new(1003.0, 2002.0, None),
+        ],
+        srid: None,
+    });
+    assert_eq!(roundtrip_wkt(multipoint), "MULTIPOINT(1005 2007,1003 2002)");
+
+    let linestring = GeometryType::LineString(line(&[
+        (1002.0, 2002.0),
+        (1002.0, 2010.0),
+        (1010.0, 2010.0),
+    ]));
+    assert_eq!(
+        roundtrip_wkt(linestring),
+        "LINESTRING(1002 2002,1002 2010,1010 2010)"
+    );
+
+    let multilinestring = GeometryType::MultiLineString(ewkb::MultiLineString {
+        lines: vec![
+            line(&[(1002.0, 2002.0), (1002.0, 2010.0), (1010.0, 2010.0)]),
+            line(&[(1001.0, 2001.0), (1003.0, 2005.0)]),
+        ],
+        srid: None,
+    });
+    assert_eq!(
+        roundtrip_wkt(multilinestring),
+        "MULTILINESTRING((1002 2002,1002 2010,1010 2010),(1001 2001,1003 2005))"
+    );
+
+    // ClosePath restores the closing point of the rings.
+    // Rings are returned in MVT winding order (clockwise exterior ring).
+    let polygon = GeometryType::Polygon(ewkb::Polygon {
+        rings: vec![
+            line(&[
+                (1000.0, 2000.0),
+                (1010.0, 2000.0),
+                (1010.0, 2010.0),
+                (1000.0, 2010.0),
+                (1000.0, 2000.0),
+            ]),
+            line(&[
+                (1002.0, 2002.0),
+                (1002.0, 2008.0),
+                (1008.0, 2008.0),
+                (1008.0, 2002.0),
+                (1002.0, 2002.0),
+            ]),
+        ],
+        srid: None,
+    });
+    assert_eq!(
+        roundtrip_wkt(polygon),
+        "POLYGON((1000 2000,1000 2010,1010 2010,1010 2000,1000 2000),(1002 2002,1008 2002,1008 2008,1002 2008,1002 2002))"
+    );
+
+    let multipolygon = GeometryType::MultiPolygon(ewkb::MultiPolygon {
+        polygons: vec![
+            ewkb::Polygon {
+                rings: vec![line(&[
+                    (1000.0, 2000.0),
+                    (1010.0, 2000.0),
+                    (1010.0, 2010.0),
+                    (1000.0, 2000.0),
+                ])],
+                srid: None,
+            },
+            ewkb::Polygon {
+                rings: vec![line(&[
+                    (1020.0, 2020.0),
+                    (1030.0, 2020.0),
+                    (1030.0, 2030.0),
+                    (1020.0, 2020.0),
+                ])],
+                srid: None,
+            },
+        ],
+        srid: None,
+    });
+    assert_eq!(
+        roundtrip_wkt(multipolygon),
+        "MULTIPOLYGON(((1000 2000,1010 2010,1010 2000,1000 2000)),((1020 2020,1030 2030,1030 2020,1020 2020)))"
+    );
+}
+
+#[test]
+fn test_decode_empty() {
+    use crate::mvt::geom_encoder::decode_commands;
+    use crate::mvt::vector_tile::Tile_GeomType;
+    use tile_grid::Extent;
+
+    let extent = Extent {
+        minx: 0.0,
+        miny: 0.0,
+        maxx: 4096.0,
+        maxy: 4096.0,
+    };
+    let decode = |geom: &[u32], geom_type| decode_commands(geom, geom_type, &extent, 4096).to_wkt();
+    assert_eq!(decode(&[], Tile_GeomType::POINT), "MULTIPOINT EMPTY");
+    assert_eq!(
+        decode(&[], Tile_GeomType::LINESTRING),
+        "MULTILINESTRING EMPTY"
+    );
+    assert_eq!(decode(&[], Tile_GeomType::POLYGON), "MULTIPOLYGON EMPTY");
+    assert_eq!(
+        decode(&[], Tile_GeomType::UNKNOWN),
+        "GEOMETRYCOLLECTION EMPTY"
+    );
+    // Truncated parameters
+    assert_eq!(decode(&[9, 50], Tile_GeomType::POINT), "MULTIPOINT EMPTY");
+    // Line with a single point
+    assert_eq!(
+        decode(&[9, 4, 4], Tile_GeomType::LINESTRING),
+        "MULTILINESTRING EMPTY"
+    );
+    // Degenerate ring without area
+    assert_eq!(
+        decode(&[9, 4, 4, 18, 0, 16, 0, 16, 15], Tile_GeomType::POLYGON),
+        "MULTIPOLYGON EMPTY"
+    );
+}
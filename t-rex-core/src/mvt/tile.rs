@@ -3,7 +3,8 @@
 // Licensed under the MIT License. See LICENSE file in the project root for full license information.
 //
 
-use crate::core::feature::{Feature, FeatureAttrValType};
+use crate::core::config::{AttrCase, FeatureIdStrategy, ReservedAttrHandling};
+use crate::core::feature::{Feature, FeatureAttr, FeatureAttrValType};
 use crate::core::layer::Layer;
 use crate::core::screen;
 use crate::core::{geom, geom::GeometryType};
@@ -11,6 +12,8 @@ use crate::mvt::geom_encoder::{CommandSequence, EncodableGeom};
 use crate::mvt::vector_tile;
 use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 use protobuf::{error::ProtobufError, CodedOutputStream, Message};
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufReader, Read, Write};
 use tile_grid::Extent;
@@ -24,8 +27,101 @@ pub struct Tile<'a> {
     buffer_size: i32,
     pixel_size_x: f64,
     pixel_size_y: f64,
+    reserved_attr_handling: ReservedAttrHandling,
+    attr_case: AttrCase,
+    constant_attrs: Vec<FeatureAttr>,
+    clip_on_encode: bool,
+    split_antimeridian: bool,
+    attribute_precision: Option<u8>,
+    feature_id: FeatureIdStrategy,
+    /// Geometries are already in tile coordinates (ST_AsMVTGeom)
+    tile_coords: bool,
+    layer_index: RefCell<LayerIndex>,
 }
 
+/// Hashable tile value
+#[derive(PartialEq, Eq, Hash)]
+enum ValueKey {
+    String(String),
+    Float(u32),
+    Double(u64),
+    Int(i64),
+    UInt(u64),
+    SInt(i64),
+    Bool(bool),
+    Empty,
+}
+
+impl ValueKey {
+    /// Key of tile value. None for NaN values, which are never equal.
+    fn from_value(value: &vector_tile::Tile_Value) -> Option<ValueKey> {
+        // Adding 0.0 normalizes -0.0, which is equal to 0.0
+        let key = if value.has_string_value() {
+            ValueKey::String(value.get_string_value().to_string())
+        } else if value.has_float_value() {
+            let v = value.get_float_value();
+            if v.is_nan() {
+                return None;
+            }
+            ValueKey::Float((v + 0.0).to_bits())
+        } else if value.has_double_value() {
+            let v = value.get_double_value();
+            if v.is_nan() {
+                return None;
+            }
+            ValueKey::Double((v + 0.0).to_bits())
+        } else if value.has_int_value() {
+            ValueKey::Int(value.get_int_value())
+        } else if value.has_uint_value() {
+            ValueKey::UInt(value.get_uint_value())
+        } else if value.has_sint_value() {
+            ValueKey::SInt(value.get_sint_value())
+        } else if value.has_bool_value() {
+            ValueKey::Bool(value.get_bool_value())
+        } else {
+            ValueKey::Empty
+        };
+        Some(key)
+    }
+}
+
+/// Indices of keys and values in the key/value tables of a layer
+#[derive(Default)]
+struct LayerIndex {
+    keys: HashMap<String, u32>,
+    values: HashMap<ValueKey, u32>,
+    num_keys: usize,
+    num_values: usize,
+}
+
+impl LayerIndex {
+    /// Rebuild index if it doesn't match the tables of `mvt_layer`
+    fn sync(&mut self, mvt_layer: &vector_tile::Tile_Layer) {
+        if self.num_keys == mvt_layer.get_keys().len()
+            && self.num_values == mvt_layer.get_values().len()
+        {
+            return;
+        }
+        *self = LayerIndex::default();
+        for (idx, key) in mvt_layer.get_keys().iter().enumerate() {
+            self.keys.entry(key.clone()).or_insert(idx as u32);
+        }
+        for (idx, value) in mvt_layer.get_values().iter().enumerate() {
+            if let Some(valuekey) = ValueKey::from_value(value) {
+                self.values.entry(valuekey).or_insert(idx as u32);
+            }
+        }
+        self.num_keys = mvt_layer.get_keys().len();
+        self.num_values = mvt_layer.get_values().len();
+    }
+}
+
+/// Half width of the web mercator world extent
+const MERCATOR_HALF_WIDTH: f64 = 20037508.342789244;
+
+/// Attribute names with a special meaning in feature representations like GeoJSON
+const RESERVED_ATTR_NAMES: &[&str] = &["id", "geometry"];
+
 impl GeometryType {
     /// GeometryType to MVT geom type
     pub fn mvt_field_type(&self) -> vector_tile::Tile_GeomType {
@@ -100,6 +196,28 @@ impl ScreenGeom<geom::Polygon> for screen::Polygon {
                 .rings
                 .push(screen::LineString::from_geom(tile, line));
         }
+        // MVT expects the exterior ring first, followed by interior rings
+        let mut exterior = 0;
+        let mut max_area = 0;
+        for (i, ring) in screen_geom.rings.iter().enumerate() {
+            let area = ring.ring_area().abs();
+            if area > max_area {
+                exterior = i;
+                max_area = area;
+            }
+        }
+        if exterior > 0 {
+            let ring = screen_geom.rings.remove(exterior);
+            screen_geom.rings.insert(0, ring);
+        }
+        // Exterior ring has to be clockwise (positive area in screen coordinates),
+        // interior rings counter-clockwise
+        for (i, ring) in screen_geom.rings.iter_mut().enumerate() {
+            let area = ring.ring_area();
+            if (i == 0 && area < 0) || (i > 0 && area > 0) {
+                ring.points.reverse();
+            }
+        }
         screen_geom
     }
 }
@@ -118,6 +236,216 @@ impl ScreenGeom<geom::MultiPolygon> for screen::MultiPolygon {
     }
 }
 
+// --- Geometry clipping
+
+/// Cohen–Sutherland outcode of a point
+fn outcode(extent: &Extent, x: f64, y: f64) -> u8 {
+    let mut code = 0;
+    if x < extent.minx {
+        code |= 1;
+    } else if x > extent.maxx {
+        code |= 2;
+    }
+    if y < extent.miny {
+        code |= 4;
+    } else if y > extent.maxy {
+        code |= 8;
+    }
+    code
+}
+
+/// Clip line segment with Cohen–Sutherland algorithm
+fn clip_segment(
+    extent: &Extent,
+    mut p0: (f64, f64),
+    mut p1: (f64, f64),
+) -> Option<((f64, f64), (f64, f64))> {
+    let mut code0 = outcode(extent, p0.0, p0.1);
+    let mut code1 = outcode(extent, p1.0, p1.1);
+    loop {
+        if code0 | code1 == 0 {
+            return Some((p0, p1));
+        }
+        if code0 & code1 != 0 {
+            return None;
+        }
+        let code = if code0 != 0 { code0 } else { code1 };
+        let (dx, dy) = (p1.0 - p0.0, p1.1 - p0.1);
+        let p = if code & 8 != 0 {
+            (p0.0 + dx * (extent.maxy - p0.1) / dy, extent.maxy)
+        } else if code & 4 != 0 {
+            (p0.0 + dx * (extent.miny - p0.1) / dy, extent.miny)
+        } else if code & 2 != 0 {
+            (extent.maxx, p0.1 + dy * (extent.maxx - p0.0) / dx)
+        } else {
+            (extent.minx, p0.1 + dy * (extent.minx - p0.0) / dx)
+        };
+        if code == code0 {
+            p0 = p;
+            code0 = outcode(extent, p0.0, p0.1);
+        } else {
+            p1 = p;
+            code1 = outcode(extent, p1.0, p1.1);
+        }
+    }
+}
+
+/// Clip line to extent. Returns the parts inside the extent.
+fn clip_line(extent: &Extent, line: &geom::LineString) -> Vec<geom::LineString> {
+    let mut lines: Vec<geom::LineString> = Vec::new();
+    let mut last: Option<(f64, f64)> = None;
+    for segment in line.points.windows(2) {
+        let p0 = (segment[0].x, segment[0].y);
+        let p1 = (segment[1].x, segment[1].y);
+        match clip_segment(extent, p0, p1) {
+            Some((start, end)) => {
+                if last != Some(start) {
+                    lines.push(geom::LineString {
+                        points: vec![geom::Point::new(start.0, start.1, line.srid)],
+                        srid: line.srid,
+                    });
+                }
+                if let Some(part) = lines.last_mut() {
+                    part.points.push(geom::Point::new(end.0, end.1, line.srid));
+                }
+                last = Some(end);
+            }
+            None => last = None,
+        }
+    }
+    lines
+}
+
+/// Clip ring with Sutherland–Hodgman algorithm. Returns None for rings outside the extent.
+fn clip_ring(extent: &Extent, ring: &geom::LineString) -> Option<geom::LineString> {
+    let mut points: Vec<(f64, f64)> = ring.points.iter().map(|p| (p.x, p.y)).collect();
+    if points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    for edge in 0..4 {
+        if points.is_empty() {
+            break;
+        }
+        let inside = |p: &(f64, f64)| match edge {
+            0 => p.0 >= extent.minx,
+            1 => p.0 <= extent.maxx,
+            2 => p.1 >= extent.miny,
+            _ => p.1 <= extent.maxy,
+        };
+        let intersection = |a: &(f64, f64), b: &(f64, f64)| match edge {
+            0 | 1 => {
+                let x = if edge == 0 { extent.minx } else { extent.maxx };
+                (x, a.1 + (b.1 - a.1) * (x - a.0) / (b.0 - a.0))
+            }
+            _ => {
+                let y = if edge == 2 { extent.miny } else { extent.maxy };
+                (a.0 + (b.0 - a.0) * (y - a.1) / (b.1 - a.1), y)
+            }
+        };
+        let input = points;
+        points = Vec::with_capacity(input.len());
+        let mut prev = input[input.len() - 1];
+        for cur in input {
+            if inside(&cur) {
+                if !inside(&prev) {
+                    points.push(intersection(&prev, &cur));
+                }
+                points.push(cur);
+            } else if inside(&prev) {
+                points.push(intersection(&prev, &cur));
+            }
+            prev = cur;
+        }
+    }
+    if points.len() < 3 {
+        return None;
+    }
+    points.push(points[0]);
+    Some(geom::LineString {
+        points: points
+            .iter()
+            .map(|p| geom::Point::new(p.0, p.1, ring.srid))
+            .collect(),
+        srid: ring.srid,
+    })
+}
+
+/// Clip polygon to extent. Returns a polygon without rings if it is outside the extent.
+fn clip_polygon(extent: &Extent, polygon: &geom::Polygon) -> geom::Polygon {
+    geom::Polygon {
+        rings: polygon
+            .rings
+            .iter()
+            .filter_map(|ring| clip_ring(extent, ring))
+            .collect(),
+        srid: polygon.srid,
+    }
+}
+
+/// Split line at segments jumping across the antimeridian (web mercator coordinates)
+fn split_line_at_antimeridian(line: &geom::LineString) -> Vec<geom::LineString> {
+    let mut lines = Vec::new();
+    let mut points: Vec<geom::Point> = Vec::with_capacity(line.points.len());
+    for point in &line.points {
+        if let Some(prev) = points.last().copied() {
+            let dx = point.x - prev.x;
+            if dx.abs() > MERCATOR_HALF_WIDTH {
+                // Westward jump: crossing at +180, eastward jump: crossing at -180
+                let edge = if dx < 0.0 {
+                    MERCATOR_HALF_WIDTH
+                } else {
+                    -MERCATOR_HALF_WIDTH
+                };
+                let unwrapped_x = point.x + 2.0 * edge;
+                let t = (edge - prev.x) / (unwrapped_x - prev.x);
+                let y = prev.y + t * (point.y - prev.y);
+                points.push(geom::Point::new(edge, y, prev.srid));
+                lines.push(geom::LineString {
+                    points: std::mem::replace(
+                        &mut points,
+                        vec![geom::Point::new(-edge, y, prev.srid)],
+                    ),
+                    srid: line.srid,
+                });
+            }
+        }
+        points.push(*point);
+    }
+    lines.push(geom::LineString {
+        points,
+        srid: line.srid,
+    });
+    lines
+}
+
+/// FNV-1a hash of encoded geometry and attributes, independent of the key and value
+/// order of the layer
+fn feature_hash(
+    mvt_layer: &vector_tile::Tile_Layer,
+    mvt_feature: &vector_tile::Tile_Feature,
+) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut write = |bytes: &[u8]| {
+        for byte in bytes {
+            hash ^= *byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    };
+    write(&[mvt_feature.get_field_type() as u8]);
+    for cmd in mvt_feature.get_geometry() {
+        write(&cmd.to_le_bytes());
+    }
+    for tag in mvt_feature.get_tags().chunks(2) {
+        write(mvt_layer.get_keys()[tag[0] as usize].as_bytes());
+        write(
+            &mvt_layer.get_values()[tag[1] as usize]
+                .write_to_bytes()
+                .unwrap_or_default(),
+        );
+    }
+    hash
+}
+
 // --- Tile creation functions
 
 impl<'a> Tile<'a> {
@@ -131,6 +459,15 @@ impl<'a> Tile<'a> {
             buffer_size: 0,
             pixel_size_x: 0.0,
             pixel_size_y: 0.0,
+            reserved_attr_handling: ReservedAttrHandling::Keep,
+            attr_case: AttrCase::Preserve,
+            constant_attrs: Vec::new(),
+            clip_on_encode: false,
+            split_antimeridian: false,
+            attribute_precision: None,
+            feature_id: FeatureIdStrategy::Field,
+            tile_coords: false,
+            layer_index: RefCell::new(LayerIndex::default()),
         };
         let default_layer = Layer::new("");
         tile.calc_layer_values(&default_layer);
@@ -139,6 +476,7 @@ impl<'a> Tile<'a> {
 
     pub fn new_layer(&mut self, layer: &Layer) -> vector_tile::Tile_Layer {
         self.calc_layer_values(layer);
+        self.layer_index = RefCell::new(LayerIndex::default());
 
         let mut mvt_layer = vector_tile::Tile_Layer::new();
         mvt_layer.set_version(2);
@@ -152,9 +490,48 @@ impl<'a> Tile<'a> {
         self.buffer_size = layer.buffer_size.unwrap_or(0) as i32;
         self.pixel_size_x = (self.extent.maxx - self.extent.minx) / self.tile_size as f64;
         self.pixel_size_y = (self.extent.maxy - self.extent.miny) / self.tile_size as f64;
+        self.reserved_attr_handling = layer.reserved_attr_handling;
+        self.attr_case = layer.attr_case;
+        self.constant_attrs = layer.constant_attrs.clone();
+        self.clip_on_encode = false;
+        self.split_antimeridian = layer.shift_longitude;
+        self.attribute_precision = layer.attribute_precision;
+        self.feature_id = layer.feature_id;
+        self.tile_coords = layer.use_st_asmvtgeom;
+    }
+
+    /// Float value rounded to attribute precision of current layer
+    fn round_attribute(&self, value: f64) -> f64 {
+        match self.attribute_precision {
+            Some(precision) => {
+                let factor = 10f64.powi(precision as i32);
+                (value * factor).round() / factor
+            }
+            None => value,
+        }
+    }
+
+    /// Attribute key after applying case normalization and reserved name handling.
+    /// None if attribute is dropped.
+    fn attribute_key(&self, key: &str) -> Option<String> {
+        let key = self.attr_case.apply(key);
+        if !RESERVED_ATTR_NAMES.contains(&key.as_str()) {
+            return Some(key);
+        }
+        match self.reserved_attr_handling {
+            ReservedAttrHandling::Keep => Some(key),
+            ReservedAttrHandling::Prefix => Some(format!("_{}", key)),
+            ReservedAttrHandling::Drop => None,
+        }
     }
 
     pub fn point(&self, point: &geom::Point) -> screen::Point {
+        if self.tile_coords {
+            return screen::Point {
+                x: point.x.round() as i32,
+                y: point.y.round() as i32,
+            };
+        }
         let mut screen_geom = screen::Point {
             x: ((point.x - self.extent.minx) / self.pixel_size_x).floor() as i32,
             y: ((point.y - self.extent.miny) / self.pixel_size_y).floor() as i32,
@@ -172,7 +549,100 @@ impl<'a> Tile<'a> {
             && point.y <= self.tile_size + self.buffer_size
     }
 
+    /// Clip geometries of current layer to buffered tile extent when encoding
+    pub fn set_clip_on_encode(&mut self, clip: bool) {
+        self.clip_on_encode = clip;
+    }
+
+    /// Tile extent extended by buffer size of current layer
+    pub fn buffered_extent(&self) -> Extent {
+        let buffer_x = self.buffer_size as f64 * self.pixel_size_x;
+        let buffer_y = self.buffer_size as f64 * self.pixel_size_y;
+        Extent {
+            minx: self.extent.minx - buffer_x,
+            miny: self.extent.miny - buffer_y,
+            maxx: self.extent.maxx + buffer_x,
+            maxy: self.extent.maxy + buffer_y,
+        }
+    }
+
+    /// Clip lines and polygons to buffered tile extent
+    pub fn clip_geom(&self, geom: geom::GeometryType) -> geom::GeometryType {
+        let extent = self.buffered_extent();
+        match geom {
+            GeometryType::LineString(g) => {
+                let srid = g.srid;
+                let mut lines = clip_line(&extent, &g);
+                if lines.len() == 1 {
+                    GeometryType::LineString(lines.remove(0))
+                } else {
+                    GeometryType::MultiLineString(geom::MultiLineString { lines, srid })
+                }
+            }
+            GeometryType::MultiLineString(g) => {
+                GeometryType::MultiLineString(geom::MultiLineString {
+                    lines: g
+                        .lines
+                        .iter()
+                        .flat_map(|line| clip_line(&extent, line))
+                        .collect(),
+                    srid: g.srid,
+                })
+            }
+            GeometryType::Polygon(g) => GeometryType::Polygon(clip_polygon(&extent, &g)),
+            GeometryType::MultiPolygon(g) => GeometryType::MultiPolygon(geom::MultiPolygon {
+                polygons: g
+                    .polygons
+                    .iter()
+                    .map(|polygon| clip_polygon(&extent, polygon))
+                    .filter(|polygon| !polygon.rings.is_empty())
+                    .collect(),
+                srid: g.srid,
+            }),
+            geom => geom,
+        }
+    }
+
+    /// Split lines crossing the antimeridian into parts on both sides
+    pub fn split_antimeridian(&self, geom: geom::GeometryType) -> geom::GeometryType {
+        match geom {
+            GeometryType::LineString(g) => {
+                let mut lines = split_line_at_antimeridian(&g);
+                if lines.len() == 1 {
+                    GeometryType::LineString(lines.remove(0))
+                } else {
+                    GeometryType::MultiLineString(geom::MultiLineString {
+                        lines,
+                        srid: g.srid,
+                    })
+                }
+            }
+            GeometryType::MultiLineString(g) => {
+                GeometryType::MultiLineString(geom::MultiLineString {
+                    lines: g
+                        .lines
+                        .iter()
+                        .flat_map(split_line_at_antimeridian)
+                        .collect(),
+                    srid: g.srid,
+                })
+            }
+            geom => geom,
+        }
+    }
+
     pub fn encode_geom(&self, geom: geom::GeometryType) -> CommandSequence {
+        // Geometries in tile coordinates are already split and clipped
+        let geom = if self.split_antimeridian && !self.tile_coords {
+            self.split_antimeridian(geom)
+        } else {
+            geom
+        };
+        let geom = if self.clip_on_encode && !self.tile_coords {
+            self.clip_geom(geom)
+        } else {
+            geom
+        };
         match geom {
             GeometryType::Point(ref g) => {
                 let pt = self.point(g);
@@ -194,6 +664,8 @@ impl<'a> Tile<'a> {
         }
     }
 
+    /// Add feature attribute with a linear search in the key/value tables.
+    /// `add_feature` uses indexed lookups instead.
     pub fn add_feature_attribute(
         mvt_layer: &mut vector_tile::Tile_Layer,
         mvt_feature: &mut vector_tile::Tile_Feature,
@@ -201,7 +673,6 @@ impl<'a> Tile<'a> {
         mvt_value: vector_tile::Tile_Value,
     ) {
         let keyentry = mvt_layer.get_keys().iter().position(|k| *k == key);
-        // Optimization: maintain a hash table with key/index pairs
         let keyidx = match keyentry {
             None => {
                 mvt_layer.mut_keys().push(key);
@@ -212,7 +683,6 @@ impl<'a> Tile<'a> {
         mvt_feature.mut_tags().push(keyidx as u32);
 
         let valentry = mvt_layer.get_values().iter().position(|v| *v == mvt_value);
-        // Optimization: maintain a hash table with value/index pairs
         let validx = match valentry {
             None => {
                 mvt_layer.mut_values().push(mvt_value);
@@ -223,22 +693,72 @@ impl<'a> Tile<'a> {
         mvt_feature.mut_tags().push(validx as u32);
     }
 
+    /// Add feature attribute using the key and value indices of the current layer
+    fn add_indexed_attribute(
+        &self,
+        mvt_layer: &mut vector_tile::Tile_Layer,
+        mvt_feature: &mut vector_tile::Tile_Feature,
+        key: String,
+        mvt_value: vector_tile::Tile_Value,
+    ) {
+        let mut index = self.layer_index.borrow_mut();
+        index.sync(mvt_layer);
+
+        let keyidx = match index.keys.get(&key) {
+            Some(idx) => *idx,
+            None => {
+                let idx = mvt_layer.get_keys().len() as u32;
+                index.keys.insert(key.clone(), idx);
+                mvt_layer.mut_keys().push(key);
+                index.num_keys += 1;
+                idx
+            }
+        };
+        mvt_feature.mut_tags().push(keyidx);
+
+        let valuekey = ValueKey::from_value(&mvt_value);
+        let validx = match valuekey.as_ref().and_then(|vk| index.values.get(vk)) {
+            Some(idx) => *idx,
+            None => {
+                let idx = mvt_layer.get_values().len() as u32;
+                if let Some(valuekey) = valuekey {
+                    index.values.insert(valuekey, idx);
+                }
+                mvt_layer.mut_values().push(mvt_value);
+                index.num_values += 1;
+                idx
+            }
+        };
+        mvt_feature.mut_tags().push(validx);
+    }
+
     pub fn add_feature(&self, mut mvt_layer: &mut vector_tile::Tile_Layer, feature: &dyn Feature) {
         let mut mvt_feature = vector_tile::Tile_Feature::new();
         if let Some(fid) = feature.fid() {
             mvt_feature.set_id(fid);
         }
-        'attr: for attr in feature.attributes() {
+        let mut attrs = feature.attributes();
+        // Constant attributes don't override feature attributes
+        for attr in &self.constant_attrs {
+            if !attrs.iter().any(|a| a.key == attr.key) {
+                attrs.push(attr.clone());
+            }
+        }
+        'attr: for attr in attrs {
+            let key = match self.attribute_key(&attr.key) {
+                Some(key) => key,
+                None => continue 'attr,
+            };
             let mut mvt_value = vector_tile::Tile_Value::new();
             match attr.value {
                 FeatureAttrValType::String(ref v) => {
                     mvt_value.set_string_value(v.clone());
                 }
                 FeatureAttrValType::Double(v) => {
-                    mvt_value.set_double_value(v);
+                    mvt_value.set_double_value(self.round_attribute(v));
                 }
                 FeatureAttrValType::Float(v) => {
-                    mvt_value.set_float_value(v);
+                    mvt_value.set_float_value(self.round_attribute(v as f64) as f32);
                 }
                 FeatureAttrValType::Int(v) => {
                     mvt_value.set_int_value(v);
@@ -254,22 +774,17 @@ impl<'a> Tile<'a> {
                 }
                 FeatureAttrValType::VarcharArray(v) => {
                     for array_val in v {
-                        Tile::add_feature_attribute(
+                        self.add_indexed_attribute(
                             &mut mvt_layer,
                             &mut mvt_feature,
-                            format!("{}.{}", attr.key, array_val),
+                            format!("{}.{}", key, array_val),
                             mvt_value.clone(),
                         );
                     }
                     continue 'attr;
                 }
             }
-            Tile::add_feature_attribute(
-                &mut mvt_layer,
-                &mut mvt_feature,
-                attr.key.clone(),
-                mvt_value,
-            );
+            self.add_indexed_attribute(&mut mvt_layer, &mut mvt_feature, key, mvt_value);
         }
         if let Ok(geom) = feature.geometry() {
             let g_type = geom.mvt_field_type();
@@ -277,6 +792,17 @@ impl<'a> Tile<'a> {
             if !enc_geom.is_empty() {
                 mvt_feature.set_field_type(g_type);
                 mvt_feature.set_geometry(enc_geom);
+                if !mvt_feature.has_id() {
+                    match self.feature_id {
+                        FeatureIdStrategy::Field => {}
+                        FeatureIdStrategy::Auto => {
+                            mvt_feature.set_id(mvt_layer.get_features().len() as u64 + 1)
+                        }
+                        FeatureIdStrategy::Hash => {
+                            mvt_feature.set_id(feature_hash(mvt_layer, &mvt_feature))
+                        }
+                    }
+                }
                 mvt_layer.mut_features().push(mvt_feature);
             }
         }
@@ -286,6 +812,19 @@ impl<'a> Tile<'a> {
         self.mvt_tile.mut_layers().push(mvt_layer);
     }
 
+    /// Append layers of a serialized tile, e.g. encoded by the datasource.
+    /// Returns the number of added features.
+    pub fn add_encoded_layers(&mut self, data: &[u8]) -> Result<u64, ProtobufError> {
+        // Layers of concatenated tile messages are merged into one tile
+        let encoded = vector_tile::Tile::parse_from_bytes(data)?;
+        let mut num_features = 0;
+        for mvt_layer in encoded.layers.into_iter() {
+            num_features += mvt_layer.features.len() as u64;
+            self.add_layer(mvt_layer);
+        }
+        Ok(num_features)
+    }
+
     pub fn write_to(mut out: &mut dyn Write, mvt_tile: &vector_tile::Tile) {
         let mut os = CodedOutputStream::new(&mut out);
         let _ = mvt_tile.write_to(&mut os);
@@ -293,7 +832,12 @@ impl<'a> Tile<'a> {
     }
 
     pub fn write_gz_to(out: &mut dyn Write, mvt_tile: &vector_tile::Tile) {
-        let mut gz = GzEncoder::new(out, Compression::default());
+        Self::write_gz_level_to(out, mvt_tile, Compression::default().level());
+    }
+
+    /// Write gzip compressed tile with compression level 1-9
+    pub fn write_gz_level_to(out: &mut dyn Write, mvt_tile: &vector_tile::Tile, level: u32) {
+        let mut gz = GzEncoder::new(out, Compression::new(level));
         {
             let mut os = CodedOutputStream::new(&mut gz);
             let _ = mvt_tile.write_to(&mut os);
@@ -325,8 +869,24 @@ impl<'a> Tile<'a> {
         v
     }
 
+    /// Tile for storing in cache. Compression level 0 stores the tile uncompressed.
+    pub fn tile_bytevec_compressed(mvt_tile: &vector_tile::Tile, level: u32) -> Vec<u8> {
+        if level == 0 {
+            return Self::tile_bytevec(mvt_tile);
+        }
+        let mut v = Vec::with_capacity(mvt_tile.compute_size() as usize);
+        Self::write_gz_level_to(&mut v, &mvt_tile, level);
+        v
+    }
+
+    /// Check for gzip header. Uncompressed tiles start with a layer field tag.
+    pub fn is_gzip(data: &[u8]) -> bool {
+        data.starts_with(&[0x1f, 0x8b])
+    }
+
+    /// Tile content for client. Uncompressed tiles are returned as is.
     pub fn tile_content(tilegz: Vec<u8>, gzip: bool) -> Vec<u8> {
-        if gzip {
+        if gzip || !Self::is_gzip(&tilegz) {
             tilegz
         } else {
             let mut gz = GzDecoder::new(&tilegz[..]);
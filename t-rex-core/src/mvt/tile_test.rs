@@ -110,6 +110,8 @@ fn test_clipped_polygon() {
         )
     }
 
+    // Expected exterior rings are in clockwise orientation, i.e. reversed compared to the
+    // ST_GeomFromText references below
     let tile_extent = Grid::web_mercator().tile_extent_xyz(68, 40, 7);
     let mut tile = Tile::new(&tile_extent, false);
     let mut layer = Layer::new("");
@@ -134,7 +136,7 @@ fn test_clipped_polygon() {
     );
     let screen_geom = screen::MultiPolygon::from_geom(&tile, &geom);
     assert_eq!(screen_geom.polygons[0].rings.len(), 1);
-    assert_eq!(poly_to_wkt(&screen_geom.polygons[0].rings), "POLYGON((203 50,202 49,201 47,199 46,199 45,198 44,197 42,196 42,196 41,194 40,194 39,192 38,192 37,191 36,189 35,188 35,187 34,185 34,184 33,182 32,181 30,179 29,177 28,176 28,174 26,173 26,171 25,171 24,169 22,168 21,168 19,167 18,166 17,166 16,165 15,164 14,164 12,163 11,162 10,161 9,160 7,159 7,158 6,156 5,155 5,154 6,153 6,152 7,151 6,150 6,150 5,147 5,145 5,143 4,142 5,141 5,140 5,139 5,138 5,136 4,135 4,132 2,130 1,129 1,128 1,127 1,126 0,124 0,123 -1,121 -1,118 -3,117 -3,115 -4,113 -5,111 -5,110 -5,109 -5,108 -5,107 -5,104 -5,103 -5,102 -5,99 -4,98 -4,97 -4,96 -4,96 -3,95 -3,94 -3,93 -2,92 -2,90 -3,88 -3,87 -3,84 -3,82 -2,81 -2,79 -3,78 -3,76 -5,75 -6,74 -8,72 -9,72 -10,-10 -10,-10 77,-9 77,-7 78,-6 78,-4 78,-2 78,-1 78,2 77,3 77,4 76,8 75,11 75,49 79,52 76,62 66,72 52,76 52,92 53,93 53,94 53,95 53,128 53,137 57,139 58,153 64,161 68,164 68,167 68,183 89,185 95,224 100,228 100,227 99,227 98,226 95,225 95,224 91,223 87,222 87,221 84,221 82,221 81,220 80,219 77,218 76,217 75,217 74,216 74,214 71,213 70,212 70,210 68,210 66,209 66,208 64,208 63,207 61,206 60,206 59,205 58,203 54,203 53,203 52,203 51,203 50))");
+    assert_eq!(poly_to_wkt(&screen_geom.polygons[0].rings), "POLYGON((203 50,203 51,203 52,203 53,203 54,205 58,206 59,206 60,207 61,208 63,208 64,209 66,210 66,210 68,212 70,213 70,214 71,216 74,217 74,217 75,218 76,219 77,220 80,221 81,221 82,221 84,222 87,223 87,224 91,225 95,226 95,227 98,227 99,228 100,224 100,185 95,183 89,167 68,164 68,161 68,153 64,139 58,137 57,128 53,95 53,94 53,93 53,92 53,76 52,72 52,62 66,52 76,49 79,11 75,8 75,4 76,3 77,2 77,-1 78,-2 78,-4 78,-6 78,-7 78,-9 77,-10 77,-10 -10,72 -10,72 -9,74 -8,75 -6,76 -5,78 -3,79 -3,81 -2,82 -2,84 -3,87 -3,88 -3,90 -3,92 -2,93 -2,94 -3,95 -3,96 -3,96 -4,97 -4,98 -4,99 -4,102 -5,103 -5,104 -5,107 -5,108 -5,109 -5,110 -5,111 -5,113 -5,115 -4,117 -3,118 -3,121 -1,123 -1,124 0,126 0,127 1,128 1,129 1,130 1,132 2,135 4,136 4,138 5,139 5,140 5,141 5,142 5,143 4,145 5,147 5,150 5,150 6,151 6,152 7,153 6,154 6,155 5,156 5,158 6,159 7,160 7,161 9,162 10,163 11,164 12,164 14,165 15,166 16,166 17,167 18,168 19,168 21,169 22,171 24,171 25,173 26,174 26,176 28,177 28,179 29,181 30,182 32,184 33,185 34,187 34,188 35,189 35,191 36,192 37,192 38,194 39,194 40,196 41,196 42,197 42,198 44,199 45,199 46,201 47,202 49,203 50))");
     // SELECT ST_GeomFromText('POLYGON((203 50,202 49,201 47,199 46,199 45,198 44,197 42,196 42,196 41,194 40,194 39,192 38,192 37,191 36,189 35,188 35,187 34,185 34,184 33,182 32,181 30,179 29,177 28,176 28,174 26,173 26,171 25,171 24,169 22,168 21,168 19,167 18,166 17,166 16,165 15,164 14,164 12,163 11,162 10,161 9,160 7,159 7,158 6,156 5,155 5,154 6,153 6,152 7,151 6,150 6,150 5,147 5,145 5,143 4,142 5,141 5,140 5,139 5,138 5,136 4,135 4,132 2,130 1,129 1,128 1,127 1,126 0,124 0,123 0,121 0,118 -2,117 -2,115 -3,113 -4,111 -4,110 -4,109 -4,108 -4,107 -4,104 -4,103 -4,102 -4,99 -3,98 -3,97 -3,96 -3,96 -2,95 -2,94 -2,93 -1,92 -1,90 -2,88 -2,87 -2,84 -2,82 -1,81 -1,79 -2,78 -2,76 -4,75 -5,74 -7,72 -8,72 -9,-9 -9,-9 77,-8 77,-6 78,-5 78,-3 78,-1 78,0 78,2 77,3 77,4 76,8 75,11 75,49 79,52 76,62 66,72 52,76 52,92 53,93 53,94 53,95 53,128 53,137 57,139 58,153 64,161 68,164 68,167 68,183 89,185 95,224 100,228 100,227 99,227 98,226 95,225 95,224 91,223 87,222 87,221 84,221 82,221 81,220 80,219 77,218 76,217 75,217 74,216 74,214 71,213 70,212 70,210 68,210 66,209 66,208 64,208 63,207 61,206 60,206 59,205 58,203 54,203 53,203 52,203 51,203 50))')
     assert_eq!(screen_geom.polygons[0].rings[0].points.len(), 170);
 
@@ -150,7 +152,7 @@ fn test_clipped_polygon() {
     assert_eq!(geom.polygons[0].rings[0].points.len(), 197);
     let screen_geom = screen::MultiPolygon::from_geom(&tile, &geom);
     assert_eq!(screen_geom.polygons[0].rings.len(), 1);
-    assert_eq!(poly_to_wkt(&screen_geom.polygons[0].rings), "POLYGON((203 50,202 49,201 47,199 46,199 45,198 44,197 42,196 42,196 41,194 40,194 39,192 38,192 37,191 36,189 35,188 35,187 34,185 34,184 33,182 32,181 30,179 29,177 28,176 28,174 26,173 26,171 25,171 24,169 22,168 21,168 19,167 18,166 17,166 16,165 15,164 14,164 12,163 11,162 10,161 9,160 7,159 7,158 6,156 5,155 5,154 6,153 6,152 7,151 6,150 6,150 5,147 5,145 5,143 4,142 5,141 5,140 5,139 5,138 5,136 4,135 4,132 2,130 1,129 1,128 1,127 1,126 0,124 0,123 -1,121 -1,118 -3,117 -3,115 -4,113 -5,111 -5,110 -5,109 -5,108 -5,107 -5,104 -5,103 -5,102 -5,99 -4,98 -4,97 -4,96 -4,96 -3,95 -3,94 -3,93 -2,92 -2,90 -3,88 -3,87 -3,84 -3,82 -2,81 -2,79 -3,78 -3,76 -5,75 -6,74 -8,72 -9,70 -12,69 -13,69 -14,68 -15,67 -15,66 -16,65 -17,64 -18,63 -19,62 -19,61 -20,59 -20,58 -20,57 -20,0 -20,-4 -20,-8 -18,-11 -17,-14 -14,-17 -11,-18 -8,-20 -4,-20 0,-20 71,-14 74,-13 75,-12 77,-11 77,-10 77,-9 77,-7 78,-6 78,-4 78,-2 78,-1 78,2 77,3 77,4 76,8 75,11 75,49 79,52 76,62 66,72 52,76 52,92 53,93 53,94 53,95 53,128 53,137 57,139 58,153 64,161 68,164 68,167 68,183 89,185 95,224 100,228 100,227 99,227 98,226 95,225 95,224 91,223 87,222 87,221 84,221 82,221 81,220 80,219 77,218 76,217 75,217 74,216 74,214 71,213 70,212 70,210 68,210 66,209 66,208 64,208 63,207 61,206 60,206 59,205 58,203 54,203 53,203 52,203 51,203 50))");
+    assert_eq!(poly_to_wkt(&screen_geom.polygons[0].rings), "POLYGON((203 50,203 51,203 52,203 53,203 54,205 58,206 59,206 60,207 61,208 63,208 64,209 66,210 66,210 68,212 70,213 70,214 71,216 74,217 74,217 75,218 76,219 77,220 80,221 81,221 82,221 84,222 87,223 87,224 91,225 95,226 95,227 98,227 99,228 100,224 100,185 95,183 89,167 68,164 68,161 68,153 64,139 58,137 57,128 53,95 53,94 53,93 53,92 53,76 52,72 52,62 66,52 76,49 79,11 75,8 75,4 76,3 77,2 77,-1 78,-2 78,-4 78,-6 78,-7 78,-9 77,-10 77,-11 77,-12 77,-13 75,-14 74,-20 71,-20 0,-20 -4,-18 -8,-17 -11,-14 -14,-11 -17,-8 -18,-4 -20,0 -20,57 -20,58 -20,59 -20,61 -20,62 -19,63 -19,64 -18,65 -17,66 -16,67 -15,68 -15,69 -14,69 -13,70 -12,72 -9,74 -8,75 -6,76 -5,78 -3,79 -3,81 -2,82 -2,84 -3,87 -3,88 -3,90 -3,92 -2,93 -2,94 -3,95 -3,96 -3,96 -4,97 -4,98 -4,99 -4,102 -5,103 -5,104 -5,107 -5,108 -5,109 -5,110 -5,111 -5,113 -5,115 -4,117 -3,118 -3,121 -1,123 -1,124 0,126 0,127 1,128 1,129 1,130 1,132 2,135 4,136 4,138 5,139 5,140 5,141 5,142 5,143 4,145 5,147 5,150 5,150 6,151 6,152 7,153 6,154 6,155 5,156 5,158 6,159 7,160 7,161 9,162 10,163 11,164 12,164 14,165 15,166 16,166 17,167 18,168 19,168 21,169 22,171 24,171 25,173 26,174 26,176 28,177 28,179 29,181 30,182 32,184 33,185 34,187 34,188 35,189 35,191 36,192 37,192 38,194 39,194 40,196 41,196 42,197 42,198 44,199 45,199 46,201 47,202 49,203 50))");
     // SELECT ST_GeomFromText('POLYGON((203 50,202 49,201 47,199 46,199 45,198 44,197 42,196 42,196 41,194 40,194 39,192 38,192 37,191 36,189 35,188 35,187 34,185 34,184 33,182 32,181 30,179 29,177 28,176 28,174 26,173 26,171 25,171 24,169 22,168 21,168 19,167 18,166 17,166 16,165 15,164 14,164 12,163 11,162 10,161 9,160 7,159 7,158 6,156 5,155 5,154 6,153 6,152 7,151 6,150 6,150 5,147 5,145 5,143 4,142 5,141 5,140 5,139 5,138 5,136 4,135 4,132 2,130 1,129 1,128 1,127 1,126 0,124 0,123 -1,121 -1,118 -3,117 -3,115 -4,113 -5,111 -5,110 -5,109 -5,108 -5,107 -5,104 -5,103 -5,102 -5,99 -4,98 -4,97 -4,96 -4,96 -3,95 -3,94 -3,93 -2,92 -2,90 -3,88 -3,87 -3,84 -3,82 -2,81 -2,79 -3,78 -3,76 -5,75 -6,74 -8,72 -9,70 -12,69 -13,69 -14,68 -15,67 -15,66 -16,65 -17,64 -18,63 -19,62 -19,61 -20,59 -20,58 -20,57 -20,0 -20,-4 -20,-8 -18,-11 -17,-14 -14,-17 -11,-18 -8,-20 -4,-20 0,-20 71,-14 74,-13 75,-12 77,-11 77,-10 77,-9 77,-7 78,-6 78,-4 78,-2 78,-1 78,2 77,3 77,4 76,8 75,11 75,49 79,52 76,62 66,72 52,76 52,92 53,93 53,94 53,95 53,128 53,137 57,139 58,153 64,161 68,164 68,167 68,183 89,185 95,224 100,228 100,227 99,227 98,226 95,225 95,224 91,223 87,222 87,221 84,221 82,221 81,220 80,219 77,218 76,217 75,217 74,216 74,214 71,213 70,212 70,210 68,210 66,209 66,208 64,208 63,207 61,206 60,206 59,205 58,203 54,203 53,203 52,203 51,203 50))')
     assert_eq!(screen_geom.polygons[0].rings[0].points.len(), 196);
 
@@ -166,11 +168,53 @@ fn test_clipped_polygon() {
     assert_eq!(geom.polygons[0].rings[0].points.len(), 198);
     let screen_geom = screen::MultiPolygon::from_geom(&tile, &geom);
     assert_eq!(screen_geom.polygons[0].rings.len(), 1);
-    assert_eq!(poly_to_wkt(&screen_geom.polygons[0].rings), "POLYGON((203 50,202 49,201 47,199 46,199 45,198 44,197 42,196 42,196 41,194 40,194 39,192 38,192 37,191 36,189 35,188 35,187 34,185 34,184 33,182 32,181 30,179 29,177 28,176 28,174 26,173 26,171 25,171 24,169 22,168 21,168 19,167 18,166 17,166 16,165 15,164 14,164 12,163 11,162 10,161 9,160 7,159 7,158 6,156 5,155 5,154 6,153 6,152 7,151 6,150 6,150 5,147 5,145 5,143 4,142 5,141 5,140 5,139 5,138 5,136 4,135 4,132 2,130 1,129 1,128 1,127 1,126 0,124 0,123 -1,121 -1,118 -3,117 -3,115 -4,113 -5,111 -5,110 -5,109 -5,108 -5,107 -5,104 -5,103 -5,102 -5,99 -4,98 -4,97 -4,96 -4,96 -3,95 -3,94 -3,93 -2,92 -2,90 -3,88 -3,87 -3,84 -3,82 -2,81 -2,79 -3,78 -3,76 -5,75 -6,74 -8,72 -9,70 -12,69 -13,69 -14,68 -15,67 -15,66 -16,65 -17,64 -18,63 -19,62 -19,61 -20,59 -20,58 -20,57 -20,0 -20,-4 -20,-8 -18,-11 -17,-14 -14,-17 -11,-18 -8,-20 -4,-20 0,-20 71,-14 74,-13 75,-12 77,-11 77,-10 77,-9 77,-7 78,-6 78,-4 78,-2 78,-1 78,2 77,3 77,4 76,8 75,11 75,49 79,52 76,62 66,72 52,76 52,92 53,93 53,94 53,95 53,128 53,137 57,139 58,153 64,161 68,164 68,167 68,183 89,185 95,224 100,229 100,228 100,227 99,227 98,226 95,225 95,224 91,223 87,222 87,221 84,221 82,221 81,220 80,219 77,218 76,217 75,217 74,216 74,214 71,213 70,212 70,210 68,210 66,209 66,208 64,208 63,207 61,206 60,206 59,205 58,203 54,203 53,203 52,203 51,203 50))");
+    assert_eq!(poly_to_wkt(&screen_geom.polygons[0].rings), "POLYGON((203 50,203 51,203 52,203 53,203 54,205 58,206 59,206 60,207 61,208 63,208 64,209 66,210 66,210 68,212 70,213 70,214 71,216 74,217 74,217 75,218 76,219 77,220 80,221 81,221 82,221 84,222 87,223 87,224 91,225 95,226 95,227 98,227 99,228 100,229 100,224 100,185 95,183 89,167 68,164 68,161 68,153 64,139 58,137 57,128 53,95 53,94 53,93 53,92 53,76 52,72 52,62 66,52 76,49 79,11 75,8 75,4 76,3 77,2 77,-1 78,-2 78,-4 78,-6 78,-7 78,-9 77,-10 77,-11 77,-12 77,-13 75,-14 74,-20 71,-20 0,-20 -4,-18 -8,-17 -11,-14 -14,-11 -17,-8 -18,-4 -20,0 -20,57 -20,58 -20,59 -20,61 -20,62 -19,63 -19,64 -18,65 -17,66 -16,67 -15,68 -15,69 -14,69 -13,70 -12,72 -9,74 -8,75 -6,76 -5,78 -3,79 -3,81 -2,82 -2,84 -3,87 -3,88 -3,90 -3,92 -2,93 -2,94 -3,95 -3,96 -3,96 -4,97 -4,98 -4,99 -4,102 -5,103 -5,104 -5,107 -5,108 -5,109 -5,110 -5,111 -5,113 -5,115 -4,117 -3,118 -3,121 -1,123 -1,124 0,126 0,127 1,128 1,129 1,130 1,132 2,135 4,136 4,138 5,139 5,140 5,141 5,142 5,143 4,145 5,147 5,150 5,150 6,151 6,152 7,153 6,154 6,155 5,156 5,158 6,159 7,160 7,161 9,162 10,163 11,164 12,164 14,165 15,166 16,166 17,167 18,168 19,168 21,169 22,171 24,171 25,173 26,174 26,176 28,177 28,179 29,181 30,182 32,184 33,185 34,187 34,188 35,189 35,191 36,192 37,192 38,194 39,194 40,196 41,196 42,197 42,198 44,199 45,199 46,201 47,202 49,203 50))");
     // SELECT ST_GeomFromText('POLYGON((203 50,202 49,201 47,199 46,199 45,198 44,197 42,196 42,196 41,194 40,194 39,192 38,192 37,191 36,189 35,188 35,187 34,185 34,184 33,182 32,181 30,179 29,177 28,176 28,174 26,173 26,171 25,171 24,169 22,168 21,168 19,167 18,166 17,166 16,165 15,164 14,164 12,163 11,162 10,161 9,160 7,159 7,158 6,156 5,155 5,154 6,153 6,152 7,151 6,150 6,150 5,147 5,145 5,143 4,142 5,141 5,140 5,139 5,138 5,136 4,135 4,132 2,130 1,129 1,128 1,127 1,126 0,124 0,123 -1,121 -1,118 -3,117 -3,115 -4,113 -5,111 -5,110 -5,109 -5,108 -5,107 -5,104 -5,103 -5,102 -5,99 -4,98 -4,97 -4,96 -4,96 -3,95 -3,94 -3,93 -2,92 -2,90 -3,88 -3,87 -3,84 -3,82 -2,81 -2,79 -3,78 -3,76 -5,75 -6,74 -8,72 -9,70 -12,69 -13,69 -14,68 -15,67 -15,66 -16,65 -17,64 -18,63 -19,62 -19,61 -20,59 -20,58 -20,57 -20,0 -20,-4 -20,-8 -18,-11 -17,-14 -14,-17 -11,-18 -8,-20 -4,-20 0,-20 71,-14 74,-13 75,-12 77,-11 77,-10 77,-9 77,-7 78,-6 78,-4 78,-2 78,-1 78,2 77,3 77,4 76,8 75,11 75,49 79,52 76,62 66,72 52,76 52,92 53,93 53,94 53,95 53,128 53,137 57,139 58,153 64,161 68,164 68,167 68,183 89,185 95,224 100,229 100,228 100,227 99,227 98,226 95,225 95,224 91,223 87,222 87,221 84,221 82,221 81,220 80,219 77,218 76,217 75,217 74,216 74,214 71,213 70,212 70,210 68,210 66,209 66,208 64,208 63,207 61,206 60,206 59,205 58,203 54,203 53,203 52,203 51,203 50))')
     assert_eq!(screen_geom.polygons[0].rings[0].points.len(), 197);
 }
 
+#[test]
+fn test_polygon_ring_order() {
+    use postgis::ewkb;
+
+    fn ring(coords: &[(f64, f64)]) -> geom::LineString {
+        ewkb::LineString {
+            points: coords
+                .iter()
+                .map(|&(x, y)| Point::new(x, y, Some(3857)))
+                .collect(),
+            srid: Some(3857),
+        }
+    }
+
+    let extent = Extent {
+        minx: 0.0,
+        miny: 0.0,
+        maxx: 4096.0,
+        maxy: 4096.0,
+    };
+    let mut tile = Tile::new(&extent, false);
+    let layer = Layer::new("polygons");
+    let _ = tile.new_layer(&layer);
+
+    let hole = ring(&[(2.0, 2.0), (2.0, 4.0), (4.0, 4.0), (4.0, 2.0), (2.0, 2.0)]);
+    let exterior = ring(&[(1.0, 1.0), (9.0, 1.0), (9.0, 9.0), (1.0, 9.0), (1.0, 1.0)]);
+    let polygon = ewkb::Polygon {
+        rings: vec![hole, exterior],
+        srid: Some(3857),
+    };
+
+    let screen_geom = screen::Polygon::from_geom(&tile, &polygon);
+    assert_eq!(screen_geom.rings[0].points[1], screen::Point { x: 9, y: 1 });
+    assert_eq!(screen_geom.rings[1].points[1], screen::Point { x: 2, y: 4 });
+
+    // MoveTo(1,1) of exterior ring first
+    assert_eq!(
+        tile.encode_geom(GeometryType::Polygon(polygon)).vec(),
+        &[9, 2, 2, 26, 16, 0, 0, 16, 15, 0, 15, 9, 2, 13, 26, 0, 4, 4, 0, 0, 3, 15]
+    );
+}
+
 #[test]
 fn test_tile_values() {
     let mut value = vector_tile::Tile_Value::new();
@@ -203,6 +247,52 @@ fn test_read_from_file() {
     );
 }
 
+#[test]
+fn test_compression_level() {
+    let mut f = File::open("../t-rex-service/src/test/tile.pbf").unwrap();
+    let mvt_tile = Tile::read_from(&mut f).unwrap();
+    let uncompressed = Tile::tile_bytevec(&mvt_tile);
+
+    let fast = Tile::tile_bytevec_compressed(&mvt_tile, 1);
+    let best = Tile::tile_bytevec_compressed(&mvt_tile, 9);
+    assert!(Tile::is_gzip(&fast));
+    assert!(best.len() <= fast.len());
+    assert_eq!(Tile::tile_content(best, false), uncompressed);
+
+    // Level 0 stores uncompressed tiles, which are delivered as is
+    let stored = Tile::tile_bytevec_compressed(&mvt_tile, 0);
+    assert!(!Tile::is_gzip(&stored));
+    assert_eq!(stored, uncompressed);
+    assert_eq!(Tile::tile_content(stored.clone(), true), uncompressed);
+    assert_eq!(Tile::tile_content(stored, false), uncompressed);
+}
+
+#[test]
+fn test_decode_from_file() {
+    use crate::mvt::geom_encoder::decode_commands;
+
+    let mut f = File::open("../t-rex-service/src/test/tile.pbf").unwrap();
+    let tile = Tile::read_from(&mut f).unwrap();
+    let ref layer = tile.get_layers()[0];
+    let ref feature = layer.get_features()[1];
+    let extent = Extent {
+        minx: 0.0,
+        miny: 0.0,
+        maxx: 4096.0,
+        maxy: 4096.0,
+    };
+    let geom = decode_commands(
+        feature.get_geometry(),
+        feature.get_field_type(),
+        &extent,
+        layer.get_extent(),
+    );
+    assert_eq!(
+        geom.to_wkt(),
+        "POLYGON((4118 1633,4113 1621,4094 1632,4099 1636,4101 1646,4118 1633))"
+    );
+}
+
 // https://github.com/mapbox/vector-tile-spec/tree/master/2.1#45-example
 #[cfg(test)]
 const TILE_EXAMPLE: &'static str = r#"Tile {
@@ -448,6 +538,7 @@ impl Feature for FeatureStruct {
     fn geometry(&self) -> Result<GeometryType, String> {
         match self.geometry {
             GeometryType::Point(ref g) => Ok(GeometryType::Point(g.clone())),
+            GeometryType::MultiPolygon(ref g) => Ok(GeometryType::MultiPolygon(g.clone())),
             // Return dummy geometry because of missing Clone impl
             _ => Ok(GeometryType::Point(Point::new(0.0, 0.0, None))),
         }
@@ -554,3 +645,672 @@ fn clip_points() {
 
     assert_eq!(mvt_layer.get_features().len(), 1);
 }
+
+#[test]
+fn test_reserved_attr_handling() {
+    use crate::core::config::ReservedAttrHandling;
+
+    let extent = Extent {
+        minx: 958826.08,
+        miny: 5987771.04,
+        maxx: 978393.96,
+        maxy: 6007338.92,
+    };
+    let feature = || FeatureStruct {
+        fid: Some(1),
+        attributes: vec![
+            FeatureAttr {
+                key: String::from("id"),
+                value: FeatureAttrValType::Int(42),
+            },
+            FeatureAttr {
+                key: String::from("name"),
+                value: FeatureAttrValType::String(String::from("Bern")),
+            },
+        ],
+        geometry: GeometryType::Point(geom::Point::new(960000.0, 6002729.0, Some(3857))),
+    };
+    let mut tile = Tile::new(&extent, true);
+    let mut layer = Layer::new("points");
+
+    let mut mvt_layer = tile.new_layer(&layer);
+    tile.add_feature(&mut mvt_layer, &feature());
+    assert_eq!(mvt_layer.get_keys(), &["id", "name"]);
+
+    layer.reserved_attr_handling = ReservedAttrHandling::Prefix;
+    let mut mvt_layer = tile.new_layer(&layer);
+    tile.add_feature(&mut mvt_layer, &feature());
+    assert_eq!(mvt_layer.get_keys(), &["_id", "name"]);
+
+    layer.reserved_attr_handling = ReservedAttrHandling::Drop;
+    let mut mvt_layer = tile.new_layer(&layer);
+    tile.add_feature(&mut mvt_layer, &feature());
+    assert_eq!(mvt_layer.get_keys(), &["name"]);
+    assert_eq!(mvt_layer.get_features()[0].get_tags(), &[0, 0]);
+}
+
+#[test]
+fn test_attr_case() {
+    use crate::core::config::AttrCase;
+
+    let extent = Extent {
+        minx: 958826.08,
+        miny: 5987771.04,
+        maxx: 978393.96,
+        maxy: 6007338.92,
+    };
+    let feature = FeatureStruct {
+        fid: Some(1),
+        attributes: vec![
+            FeatureAttr {
+                key: String::from("NAME"),
+                value: FeatureAttrValType::String(String::from("Bern")),
+            },
+            FeatureAttr {
+                key: String::from("Pop_Max"),
+                value: FeatureAttrValType::Int(133_883),
+            },
+        ],
+        geometry: GeometryType::Point(geom::Point::new(960000.0, 6002729.0, Some(3857))),
+    };
+    let mut tile = Tile::new(&extent, true);
+    let mut layer = Layer::new("points");
+
+    let mut mvt_layer = tile.new_layer(&layer);
+    tile.add_feature(&mut mvt_layer, &feature);
+    assert_eq!(mvt_layer.get_keys(), &["NAME", "Pop_Max"]);
+
+    layer.attr_case = AttrCase::Lower;
+    let mut mvt_layer = tile.new_layer(&layer);
+    tile.add_feature(&mut mvt_layer, &feature);
+    assert_eq!(mvt_layer.get_keys(), &["name", "pop_max"]);
+
+    layer.attr_case = AttrCase::Upper;
+    let mut mvt_layer = tile.new_layer(&layer);
+    tile.add_feature(&mut mvt_layer, &feature);
+    assert_eq!(mvt_layer.get_keys(), &["NAME", "POP_MAX"]);
+}
+
+#[test]
+fn test_polygon_winding_order() {
+    use postgis::ewkb;
+
+    fn ring(coords: &[(f64, f64)]) -> geom::LineString {
+        ewkb::LineString {
+            points: coords
+                .iter()
+                .map(|&(x, y)| Point::new(x, y, Some(3857)))
+                .collect(),
+            srid: Some(3857),
+        }
+    }
+
+    let extent = Extent {
+        minx: 0.0,
+        miny: 0.0,
+        maxx: 4096.0,
+        maxy: 4096.0,
+    };
+    let mut tile = Tile::new(&extent, false);
+    let layer = Layer::new("polygons");
+    let _ = tile.new_layer(&layer);
+
+    // Counter-clockwise exterior ring and clockwise hole in screen coordinates
+    let exterior = ring(&[(1.0, 1.0), (1.0, 9.0), (9.0, 9.0), (9.0, 1.0), (1.0, 1.0)]);
+    let hole = ring(&[(2.0, 2.0), (4.0, 2.0), (4.0, 4.0), (2.0, 4.0), (2.0, 2.0)]);
+    let polygon = ewkb::Polygon {
+        rings: vec![exterior, hole],
+        srid: Some(3857),
+    };
+
+    let screen_geom = screen::Polygon::from_geom(&tile, &polygon);
+    assert!(screen_geom.rings[0].ring_area() > 0);
+    assert!(screen_geom.rings[1].ring_area() < 0);
+
+    // Same command sequence as for a polygon with correct winding order
+    assert_eq!(
+        tile.encode_geom(GeometryType::Polygon(polygon)).vec(),
+        &[9, 2, 2, 26, 16, 0, 0, 16, 15, 0, 15, 9, 2, 13, 26, 0, 4, 4, 0, 0, 3, 15]
+    );
+}
+
+#[test]
+fn test_label_point() {
+    use crate::core::feature::LabelPointFeature;
+    use crate::core::Config;
+    use postgis::ewkb;
+
+    let extent = Extent {
+        minx: 0.0,
+        miny: 0.0,
+        maxx: 4096.0,
+        maxy: 4096.0,
+    };
+    let exterior = ewkb::LineString {
+        points: [(1.0, 1.0), (9.0, 1.0), (9.0, 9.0), (1.0, 9.0), (1.0, 1.0)]
+            .iter()
+            .map(|&(x, y)| Point::new(x, y, Some(3857)))
+            .collect(),
+        srid: Some(3857),
+    };
+    let feature = FeatureStruct {
+        fid: Some(7),
+        attributes: vec![FeatureAttr {
+            key: "name".to_string(),
+            value: FeatureAttrValType::String("square".to_string()),
+        }],
+        geometry: GeometryType::MultiPolygon(ewkb::MultiPolygon {
+            polygons: vec![ewkb::Polygon {
+                rings: vec![exterior],
+                srid: Some(3857),
+            }],
+            srid: Some(3857),
+        }),
+    };
+    let mut layer = Layer::new("polygons");
+    layer.label_point = true;
+    assert!(layer.gen_runtime_config().contains("label_point = true"));
+    assert_eq!(layer.label_layer_name(), "polygons_label");
+
+    let label = LabelPointFeature::from_feature(&feature).unwrap();
+    assert_eq!(label.fid(), Some(7));
+    assert_eq!(label.attributes()[0].key, "name");
+    match label.geometry().unwrap() {
+        GeometryType::Point(pt) => assert_eq!((pt.x, pt.y), (5.0, 5.0)),
+        geom => panic!("Unexpected geometry {:?}", geom),
+    }
+
+    let mut tile = Tile::new(&extent, false);
+    let mut mvt_layer = tile.new_layer(&layer);
+    mvt_layer.set_name(layer.label_layer_name());
+    tile.add_feature(&mut mvt_layer, &label);
+    let mvt_feature = &mvt_layer.get_features()[0];
+    assert_eq!(
+        mvt_feature.get_field_type(),
+        vector_tile::Tile_GeomType::POINT
+    );
+    assert_eq!(mvt_feature.get_id(), 7);
+    assert_eq!(mvt_layer.get_keys(), &["name".to_string()]);
+
+    let point = FeatureStruct {
+        fid: None,
+        attributes: Vec::new(),
+        geometry: GeometryType::Point(Point::new(1.0, 1.0, Some(3857))),
+    };
+    assert!(LabelPointFeature::from_feature(&point).is_none());
+}
+
+#[test]
+fn test_constant_attrs() {
+    use crate::core::{parse_config, Config};
+
+    let toml = r#"
+        name = "roads"
+        table_name = "roads"
+        constant_attrs = { layer_group = "transport", rank = 3, scale = 0.5, visible = true }
+        "#;
+    let layer = Layer::from_config(&parse_config(toml.to_string(), "").unwrap()).unwrap();
+    assert!(layer.gen_runtime_config().contains(
+        r#"constant_attrs = { layer_group = "transport", rank = 3, scale = 0.5, visible = true }"#
+    ));
+
+    let extent = Extent {
+        minx: 958826.08,
+        miny: 5987771.04,
+        maxx: 978393.96,
+        maxy: 6007338.92,
+    };
+    let mut tile = Tile::new(&extent, true);
+    let mut mvt_layer = tile.new_layer(&layer);
+    for (fid, rank) in &[(1, None), (2, Some(5))] {
+        let mut attributes = vec![FeatureAttr {
+            key: String::from("name"),
+            value: FeatureAttrValType::String(format!("road {}", fid)),
+        }];
+        if let Some(rank) = rank {
+            attributes.push(FeatureAttr {
+                key: String::from("rank"),
+                value: FeatureAttrValType::Int(*rank),
+            });
+        }
+        let feature = FeatureStruct {
+            fid: Some(*fid),
+            attributes,
+            geometry: GeometryType::Point(geom::Point::new(960000.0, 6002729.0, Some(3857))),
+        };
+        tile.add_feature(&mut mvt_layer, &feature);
+    }
+
+    assert_eq!(mvt_layer.get_features().len(), 2);
+    let attr_value = |feature: &vector_tile::Tile_Feature, key: &str| {
+        let tags = feature.get_tags();
+        tags.chunks(2)
+            .find(|kv| mvt_layer.get_keys()[kv[0] as usize] == key)
+            .map(|kv| mvt_layer.get_values()[kv[1] as usize].clone())
+            .unwrap()
+    };
+    for feature in mvt_layer.get_features() {
+        assert_eq!(
+            attr_value(feature, "layer_group").get_string_value(),
+            "transport"
+        );
+        assert_eq!(attr_value(feature, "scale").get_double_value(), 0.5);
+        assert!(attr_value(feature, "visible").has_bool_value());
+        assert!(attr_value(feature, "visible").get_bool_value());
+    }
+    // Feature attributes take precedence over constant attributes
+    assert_eq!(
+        attr_value(&mvt_layer.get_features()[0], "rank").get_int_value(),
+        3
+    );
+    assert_eq!(
+        attr_value(&mvt_layer.get_features()[1], "rank").get_int_value(),
+        5
+    );
+    assert_eq!(mvt_layer.get_features()[1].get_tags().len(), 10);
+}
+
+#[test]
+fn test_clip_on_encode() {
+    use postgis::ewkb;
+
+    fn line(coords: &[(f64, f64)]) -> geom::LineString {
+        ewkb::LineString {
+            points: coords
+                .iter()
+                .map(|&(x, y)| Point::new(x, y, Some(3857)))
+                .collect(),
+            srid: Some(3857),
+        }
+    }
+    fn within(extent: &Extent, points: &[Point]) -> bool {
+        points.iter().all(|p| {
+            p.x >= extent.minx && p.x <= extent.maxx && p.y >= extent.miny && p.y <= extent.maxy
+        })
+    }
+
+    let extent = Extent {
+        minx: 0.0,
+        miny: 0.0,
+        maxx: 4096.0,
+        maxy: 4096.0,
+    };
+    let mut tile = Tile::new(&extent, true);
+    let mut layer = Layer::new("clipped");
+    // Layers without buffer of non-clipping datasources are clipped to the tile extent
+    assert!(layer.clip_on_encode(false));
+    layer.clip_on_encode = Some(true);
+    assert!(!layer.clip_on_encode(true));
+    layer.clip_on_encode = None;
+    layer.buffer_size = Some(64);
+    assert!(layer.clip_on_encode(false));
+    assert!(!layer.clip_on_encode(true));
+    let _ = tile.new_layer(&layer);
+    let buffered = tile.buffered_extent();
+    assert_eq!(buffered.minx, -64.0);
+    assert_eq!(buffered.maxy, 4160.0);
+
+    // Line leaving and reentering the buffered extent
+    let linestring = line(&[
+        (-1000.0, 2000.0),
+        (2000.0, 2000.0),
+        (2000.0, 5000.0),
+        (3000.0, 5000.0),
+        (3000.0, 3000.0),
+    ]);
+    match tile.clip_geom(GeometryType::LineString(linestring)) {
+        GeometryType::MultiLineString(g) => {
+            assert_eq!(g.lines.len(), 2);
+            assert_eq!(g.lines[0].points[0], Point::new(-64.0, 2000.0, Some(3857)));
+            assert_eq!(g.lines[0].points.len(), 3);
+            assert_eq!(g.lines[1].points.len(), 2);
+            for line in &g.lines {
+                assert!(within(&buffered, &line.points));
+            }
+        }
+        _ => panic!("MultiLineString expected"),
+    }
+
+    // Polygon straddling the lower left tile corner
+    let polygon = ewkb::Polygon {
+        rings: vec![line(&[
+            (-1000.0, -1000.0),
+            (2000.0, -1000.0),
+            (2000.0, 2000.0),
+            (-1000.0, 2000.0),
+            (-1000.0, -1000.0),
+        ])],
+        srid: Some(3857),
+    };
+    match tile.clip_geom(GeometryType::Polygon(polygon)) {
+        GeometryType::Polygon(g) => {
+            assert_eq!(g.rings.len(), 1);
+            assert_eq!(g.rings[0].points.len(), 5);
+            assert_eq!(g.rings[0].points.first(), g.rings[0].points.last());
+            assert!(within(&buffered, &g.rings[0].points));
+        }
+        _ => panic!("Polygon expected"),
+    }
+
+    // Polygon outside of buffered extent
+    let polygon = ewkb::Polygon {
+        rings: vec![line(&[
+            (5000.0, 5000.0),
+            (6000.0, 5000.0),
+            (6000.0, 6000.0),
+            (5000.0, 5000.0),
+        ])],
+        srid: Some(3857),
+    };
+    let encoded = tile.encode_geom(GeometryType::Polygon(polygon.clone()));
+    assert!(!encoded.vec().is_empty());
+    tile.set_clip_on_encode(true);
+    let encoded = tile.encode_geom(GeometryType::Polygon(polygon));
+    assert!(encoded.vec().is_empty());
+}
+
+#[test]
+fn test_split_antimeridian() {
+    use postgis::ewkb;
+    use tile_grid::Grid;
+
+    // Line from 179° to -179° at the equator
+    let x = 179.0_f64.to_radians() * 6378137.0;
+    let linestring = ewkb::LineString {
+        points: vec![
+            Point::new(x, 0.0, Some(3857)),
+            Point::new(-x, 100000.0, Some(3857)),
+        ],
+        srid: Some(3857),
+    };
+    let extent = Grid::web_mercator().tile_extent(0, 0, 0);
+    let mut tile = Tile::new(&extent, true);
+    let mut layer = Layer::new("lines");
+    let _ = tile.new_layer(&layer);
+    match tile.split_antimeridian(GeometryType::LineString(linestring.clone())) {
+        GeometryType::MultiLineString(g) => {
+            assert_eq!(g.lines.len(), 2);
+            assert_eq!(g.lines[0].points[1].x, 20037508.342789244);
+            assert_eq!(g.lines[1].points[0].x, -20037508.342789244);
+            assert!((g.lines[0].points[1].y - 50000.0).abs() < 1e-6);
+
+            let screen_geom = screen::MultiLineString::from_geom(&tile, &g);
+            // East of 179° on the right side of the tile
+            assert!(screen_geom.lines[0].points.iter().all(|p| p.x >= 4072));
+            // West of -179° on the left side of the tile
+            assert!(screen_geom.lines[1].points.iter().all(|p| p.x <= 24));
+        }
+        _ => panic!("MultiLineString expected"),
+    }
+
+    // Splitting is enabled with shift_longitude
+    let smear = tile
+        .encode_geom(GeometryType::LineString(linestring.clone()))
+        .vec();
+    layer.shift_longitude = true;
+    let _ = tile.new_layer(&layer);
+    let split = tile.encode_geom(GeometryType::LineString(linestring)).vec();
+    assert_eq!(smear.len(), 6); // MoveTo, LineTo
+    assert_eq!(split.len(), 12); // 2 x (MoveTo, LineTo)
+}
+
+#[test]
+fn test_attribute_precision() {
+    use crate::core::{parse_config, Config};
+
+    let toml = r#"
+        name = "places"
+        table_name = "places"
+        attribute_precision = 2
+        "#;
+    let mut layer = Layer::from_config(&parse_config(toml.to_string(), "").unwrap()).unwrap();
+    assert!(layer
+        .gen_runtime_config()
+        .contains("attribute_precision = 2"));
+
+    let extent = Extent {
+        minx: 958826.08,
+        miny: 5987771.04,
+        maxx: 978393.96,
+        maxy: 6007338.92,
+    };
+    let feature = FeatureStruct {
+        fid: Some(1),
+        attributes: vec![
+            FeatureAttr {
+                key: String::from("lon"),
+                value: FeatureAttrValType::Double(8.547674322),
+            },
+            FeatureAttr {
+                key: String::from("lat"),
+                value: FeatureAttrValType::Float(47.376888),
+            },
+        ],
+        geometry: GeometryType::Point(geom::Point::new(960000.0, 6002729.0, Some(3857))),
+    };
+    let mut tile = Tile::new(&extent, true);
+    let mut mvt_layer = tile.new_layer(&layer);
+    tile.add_feature(&mut mvt_layer, &feature);
+    assert_eq!(mvt_layer.get_values()[0].get_double_value(), 8.55);
+    assert_eq!(mvt_layer.get_values()[1].get_float_value(), 47.38);
+
+    // Full precision by default
+    layer.attribute_precision = None;
+    let mut mvt_layer = tile.new_layer(&layer);
+    tile.add_feature(&mut mvt_layer, &feature);
+    assert_eq!(mvt_layer.get_values()[0].get_double_value(), 8.547674322);
+    assert_eq!(mvt_layer.get_values()[1].get_float_value(), 47.376888);
+}
+
+#[test]
+fn test_indexed_attributes() {
+    let extent = Extent {
+        minx: 958826.08,
+        miny: 5987771.04,
+        maxx: 978393.96,
+        maxy: 6007338.92,
+    };
+    let features: Vec<FeatureStruct> = (0..100)
+        .map(|i| {
+            let mut attributes: Vec<FeatureAttr> = (0..20)
+                .map(|a| FeatureAttr {
+                    key: format!("attr{}", a),
+                    value: FeatureAttrValType::Int((i * a) % 7),
+                })
+                .collect();
+            attributes.push(FeatureAttr {
+                key: String::from("name"),
+                value: FeatureAttrValType::String(format!("name{}", i % 10)),
+            });
+            attributes.push(FeatureAttr {
+                key: String::from("value"),
+                value: FeatureAttrValType::Double(if i % 2 == 0 { 0.0 } else { -0.0 }),
+            });
+            attributes.push(FeatureAttr {
+                key: String::from("nan"),
+                value: FeatureAttrValType::Double(std::f64::NAN),
+            });
+            FeatureStruct {
+                fid: Some(i as u64),
+                attributes,
+                geometry: GeometryType::Point(geom::Point::new(
+                    960000.0 + i as f64,
+                    6002729.0,
+                    Some(3857),
+                )),
+            }
+        })
+        .collect();
+
+    let layer = Layer::new("points");
+    let mut tile = Tile::new(&extent, true);
+    let mut mvt_layer = tile.new_layer(&layer);
+    for feature in &features {
+        tile.add_feature(&mut mvt_layer, feature);
+    }
+
+    // Reference output with linear key/value lookups
+    let mut expected = tile.new_layer(&layer);
+    for feature in &features {
+        let mut mvt_feature = vector_tile::Tile_Feature::new();
+        mvt_feature.set_id(feature.fid().unwrap());
+        for attr in feature.attributes() {
+            let mut mvt_value = vector_tile::Tile_Value::new();
+            match attr.value {
+                FeatureAttrValType::String(v) => mvt_value.set_string_value(v),
+                FeatureAttrValType::Double(v) => mvt_value.set_double_value(v),
+                FeatureAttrValType::Int(v) => mvt_value.set_int_value(v),
+                _ => panic!("unexpected attribute type"),
+            }
+            Tile::add_feature_attribute(&mut expected, &mut mvt_feature, attr.key, mvt_value);
+        }
+        let geom = feature.geometry().unwrap();
+        mvt_feature.set_field_type(geom.mvt_field_type());
+        mvt_feature.set_geometry(tile.encode_geom(geom).vec());
+        expected.mut_features().push(mvt_feature);
+    }
+
+    assert_eq!(mvt_layer.get_keys(), expected.get_keys());
+    assert_eq!(mvt_layer.get_values().len(), expected.get_values().len());
+    assert_eq!(
+        format!("{:?}", mvt_layer.get_values()),
+        format!("{:?}", expected.get_values())
+    );
+    assert_eq!(
+        format!("{:?}", mvt_layer.get_features()),
+        format!("{:?}", expected.get_features())
+    );
+    // 7 integers, 10 names, 0.0 and 100 NaN values
+    assert_eq!(mvt_layer.get_values().len(), 118);
+}
+
+#[test]
+fn test_tile_coords() {
+    let extent = Extent {
+        minx: 958826.08,
+        miny: 5987771.04,
+        maxx: 978393.96,
+        maxy: 6007338.92,
+    };
+    let mut tile = Tile::new(&extent, true);
+    let mut layer = Layer::new("mvtgeom");
+    layer.buffer_size = Some(10);
+    layer.use_st_asmvtgeom = true;
+    let _ = tile.new_layer(&layer);
+    tile.set_clip_on_encode(true);
+
+    // Coordinates returned by ST_AsMVTGeom are encoded unchanged
+    let point = GeometryType::Point(Point::new(100.0, 4100.0, None));
+    let screen_pt = screen::Point { x: 100, y: 4100 };
+    assert_eq!(tile.encode_geom(point).vec(), screen_pt.encode().vec());
+}
+
+#[test]
+fn test_add_encoded_layers() {
+    let extent = Extent {
+        minx: 958826.08,
+        miny: 5987771.04,
+        maxx: 978393.96,
+        maxy: 6007338.92,
+    };
+    let feature = FeatureStruct {
+        fid: Some(1),
+        attributes: vec![],
+        geometry: GeometryType::Point(geom::Point::new(960000.0, 6002729.0, Some(3857))),
+    };
+    // Two serialized tiles with one layer each, like returned by ST_AsMVT
+    let encoded: Vec<Vec<u8>> = ["points", "places"]
+        .iter()
+        .map(|name| {
+            let mut tile = Tile::new(&extent, true);
+            let mut mvt_layer = tile.new_layer(&Layer::new(name));
+            tile.add_feature(&mut mvt_layer, &feature);
+            tile.add_layer(mvt_layer);
+            Tile::tile_bytevec(&tile.mvt_tile)
+        })
+        .collect();
+
+    let mut tile = Tile::new(&extent, true);
+    let mvt_layer = tile.new_layer(&Layer::new("rust"));
+    tile.add_layer(mvt_layer);
+    let mut concatenated = Tile::tile_bytevec(&tile.mvt_tile);
+    assert_eq!(tile.add_encoded_layers(&encoded[0]).unwrap(), 1);
+    assert_eq!(tile.add_encoded_layers(&encoded[1]).unwrap(), 1);
+    // Empty result of ST_AsMVT
+    assert_eq!(tile.add_encoded_layers(&[]).unwrap(), 0);
+    assert!(tile.add_encoded_layers(&[0xff]).is_err());
+    let names: Vec<&str> = tile
+        .mvt_tile
+        .get_layers()
+        .iter()
+        .map(|l| l.get_name())
+        .collect();
+    assert_eq!(names, vec!["rust", "points", "places"]);
+
+    // Merged tile is equal to concatenated serialized tiles
+    concatenated.extend_from_slice(&encoded[0]);
+    concatenated.extend_from_slice(&encoded[1]);
+    assert_eq!(Tile::tile_bytevec(&tile.mvt_tile), concatenated);
+}
+
+#[test]
+fn test_feature_id_strategy() {
+    use crate::core::config::FeatureIdStrategy;
+
+    let extent = Extent {
+        minx: 958826.08,
+        miny: 5987771.04,
+        maxx: 978393.96,
+        maxy: 6007338.92,
+    };
+    let feature = |fid: Option<u64>, name: &str| FeatureStruct {
+        fid,
+        attributes: vec![FeatureAttr {
+            key: String::from("name"),
+            value: FeatureAttrValType::String(String::from(name)),
+        }],
+        geometry: GeometryType::Point(geom::Point::new(960000.0, 6002729.0, Some(3857))),
+    };
+    let feature_ids = |strategy: FeatureIdStrategy, features: &[FeatureStruct]| {
+        let mut tile = Tile::new(&extent, true);
+        let mut layer = Layer::new("points");
+        layer.feature_id = strategy;
+        let mut mvt_layer = tile.new_layer(&layer);
+        for feature in features {
+            tile.add_feature(&mut mvt_layer, feature);
+        }
+        mvt_layer
+            .get_features()
+            .iter()
+            .map(|f| if f.has_id() { Some(f.get_id()) } else { None })
+            .collect::<Vec<_>>()
+    };
+    let features = vec![
+        feature(None, "a"),
+        feature(Some(42), "b"),
+        feature(None, "c"),
+        feature(None, "a"),
+    ];
+
+    assert_eq!(
+        feature_ids(FeatureIdStrategy::Field, &features),
+        vec![None, Some(42), None, None]
+    );
+    assert_eq!(
+        feature_ids(FeatureIdStrategy::Auto, &features),
+        vec![Some(1), Some(42), Some(3), Some(4)]
+    );
+
+    let ids = feature_ids(FeatureIdStrategy::Hash, &features);
+    assert_eq!(ids[1], Some(42));
+    // Identical features have identical ids
+    assert_eq!(ids[0], ids[3]);
+    assert_ne!(ids[0], ids[2]);
+    // Independent of other features in the layer
+    assert_eq!(
+        feature_ids(FeatureIdStrategy::Hash, &[feature(None, "c")]),
+        vec![ids[2]]
+    );
+    // Stable across runs
+    assert_eq!(ids[0], Some(12555314021529010008));
+}
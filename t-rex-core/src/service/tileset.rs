@@ -4,8 +4,9 @@
 //
 
 use crate::core::config::Config;
-use crate::core::config::{TilesetCacheCfg, TilesetCfg};
+use crate::core::config::{PlaceholderTileCfg, TilesetCacheCfg, TilesetCfg};
 use crate::core::layer::Layer;
+use std::fs;
 use tile_grid::Extent;
 
 #[derive(Clone, Debug)]
@@ -28,18 +29,56 @@ impl<'a> Config<'a, TilesetCacheCfg> for CacheLimits {
     }
 }
 
+/// Prebuilt tile served within a zoom band
+#[derive(Clone, Debug)]
+pub struct PlaceholderTile {
+    /// Tile content (gzip compressed or uncompressed)
+    pub data: Vec<u8>,
+    pub minzoom: u8,
+    pub maxzoom: u8,
+}
+
+impl<'a> Config<'a, PlaceholderTileCfg> for PlaceholderTile {
+    fn from_config(cfg: &PlaceholderTileCfg) -> Result<Self, String> {
+        let data =
+            fs::read(&cfg.path).map_err(|e| format!("Placeholder tile '{}': {}", cfg.path, e))?;
+        Ok(PlaceholderTile {
+            data,
+            minzoom: cfg.minzoom,
+            maxzoom: cfg.maxzoom,
+        })
+    }
+    fn gen_config() -> String {
+        "".to_string()
+    }
+}
+
 /// Collection of layers in one MVT
 #[derive(Clone)]
 pub struct Tileset {
     pub name: String,
+    /// Name of grid in `[grids]` (None: default grid)
+    pub grid: Option<String>,
     pub minzoom: Option<u8>,
     pub maxzoom: Option<u8>,
     pub attribution: Option<String>,
     pub extent: Option<Extent>,
+    /// Detect extent from layer data at startup, if no extent is configured
+    pub detect_extent: bool,
     pub center: Option<(f64, f64)>,
     pub start_zoom: Option<u8>,
     pub layers: Vec<Layer>,
     pub cache_limits: Option<CacheLimits>,
+    /// Tile served instead of generated tiles within its zoom band
+    pub placeholder_tile: Option<PlaceholderTile>,
+    /// Tiles are cached (false: tileset is never cached)
+    pub cache: bool,
+    /// Write tile presence bitmap when generating and use it for serving
+    pub tile_bitmap: bool,
+    /// Data version for cache busting
+    pub version: Option<String>,
+    /// Query returning the data version, used if no version is configured
+    pub version_query: Option<String>,
 }
 
 pub static WORLD_EXTENT: Extent = Extent {
@@ -63,8 +102,25 @@ impl Tileset {
                 .unwrap_or(22),
         )
     }
+    /// Tileset attribution followed by distinct attributions of enabled layers
     pub fn attribution(&self) -> String {
-        self.attribution.clone().unwrap_or("".to_string())
+        let layer_attributions = self
+            .layers
+            .iter()
+            .filter(|l| l.enabled)
+            .filter_map(|l| l.attribution.as_deref());
+        let mut attributions: Vec<&str> = Vec::new();
+        for attribution in self
+            .attribution
+            .as_deref()
+            .into_iter()
+            .chain(layer_attributions)
+        {
+            if !attributions.contains(&attribution) {
+                attributions.push(attribution);
+            }
+        }
+        attributions.join(" | ")
     }
     pub fn get_extent(&self) -> &Extent {
         self.extent.as_ref().unwrap_or(&WORLD_EXTENT)
@@ -83,7 +139,17 @@ impl Tileset {
     pub fn get_start_zoom(&self) -> u8 {
         self.start_zoom.unwrap_or(2)
     }
+    /// Placeholder tile content at zoom level
+    pub fn placeholder_tile_at(&self, zoom: u8) -> Option<&Vec<u8>> {
+        self.placeholder_tile
+            .as_ref()
+            .filter(|pt| pt.minzoom <= zoom && zoom <= pt.maxzoom)
+            .map(|pt| &pt.data)
+    }
     pub fn is_cachable_at(&self, zoom: u8) -> bool {
+        if !self.cache {
+            return false;
+        }
         match self.cache_limits {
             Some(ref cl) => !cl.no_cache && cl.minzoom <= zoom && cl.maxzoom.unwrap_or(99) >= zoom,
             None => true,
@@ -93,11 +159,13 @@ impl Tileset {
 
 impl<'a> Config<'a, TilesetCfg> for Tileset {
     fn from_config(tileset_cfg: &TilesetCfg) -> Result<Self, String> {
+        let tileset_err = |e| format!("Tileset '{}': {}", tileset_cfg.name, e);
         let layers = tileset_cfg
             .layers
             .iter()
-            .map(|layer| Layer::from_config(layer).unwrap())
-            .collect();
+            .map(|layer| Layer::from_config(layer))
+            .collect::<Result<_, _>>()
+            .map_err(tileset_err)?;
         let cache_limits: Option<CacheLimits> = match tileset_cfg.cache_limits {
             Some(ref cfg) => match CacheLimits::from_config(&cfg) {
                 Ok(cl) => Some(cl),
@@ -105,20 +173,31 @@ impl<'a> Config<'a, TilesetCfg> for Tileset {
             },
             None => None,
         };
+        let placeholder_tile = match tileset_cfg.placeholder_tile {
+            Some(ref cfg) => Some(PlaceholderTile::from_config(cfg).map_err(tileset_err)?),
+            None => None,
+        };
         let extent = match &tileset_cfg.extent {
             Some(cfg) => Some(Extent::from(cfg)),
             None => None,
         };
         Ok(Tileset {
             name: tileset_cfg.name.clone(),
+            grid: tileset_cfg.grid.clone(),
             minzoom: tileset_cfg.minzoom.clone(),
             maxzoom: tileset_cfg.maxzoom.clone(),
             attribution: tileset_cfg.attribution.clone(),
             extent,
+            detect_extent: tileset_cfg.detect_extent,
             center: tileset_cfg.center.clone(),
             start_zoom: tileset_cfg.start_zoom.clone(),
             layers: layers,
             cache_limits: cache_limits,
+            placeholder_tile,
+            cache: tileset_cfg.cache,
+            tile_bitmap: tileset_cfg.tile_bitmap,
+            version: tileset_cfg.version.clone(),
+            version_query: tileset_cfg.version_query.clone(),
         })
     }
     fn gen_config() -> String {
@@ -143,6 +222,7 @@ fn test_zoom() {
     layer.geometry_type = Some(String::from("POINT"));
     let mut tileset = Tileset {
         name: "points".to_string(),
+        grid: None,
         minzoom: None,
         maxzoom: None,
         center: None,
@@ -156,6 +236,12 @@ fn test_zoom() {
         }),
         layers: vec![layer],
         cache_limits: None,
+        detect_extent: false,
+        placeholder_tile: None,
+        cache: true,
+        tile_bitmap: false,
+        version: None,
+        version_query: None,
     };
 
     assert_eq!(tileset.minzoom(), 0);
@@ -5,16 +5,20 @@
 
 use crate::gdal_fields::*;
 use gdal::spatial_ref::{CoordTransform, SpatialRef};
-use gdal::vector::Geometry;
 use gdal::vector::LayerAccess;
+use gdal::vector::{FieldValue, Geometry};
 use gdal::Dataset;
+use std::cmp::{self, Ordering};
 use std::collections::BTreeMap;
+use std::fs;
 use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
 use t_rex_core::core::config::DatasourceCfg;
 use t_rex_core::core::feature::Feature;
 use t_rex_core::core::layer::Layer;
 use t_rex_core::core::Config;
-use t_rex_core::datasource::DatasourceType;
+use t_rex_core::datasource::{DatasourceType, Semaphore};
 use tile_grid::Extent;
 use tile_grid::Grid;
 
@@ -24,6 +28,9 @@ pub struct GdalDatasource {
     // We don't store the Dataset, because we need mut access for getting layers
     /// SpatialRef WKT for layers which need CoordTransform
     geom_transform: BTreeMap<String, String>,
+    /// Maximal number of concurrent feature queries
+    pub max_concurrent_queries: Option<usize>,
+    query_semaphore: Option<Arc<Semaphore>>,
 }
 
 impl GdalDatasource {
@@ -31,8 +38,56 @@ impl GdalDatasource {
         GdalDatasource {
             path: path.to_string(),
             geom_transform: BTreeMap::new(),
+            max_concurrent_queries: None,
+            query_semaphore: None,
         }
     }
+    /// Spatial filter in layer SRS and transformation of features into grid SRS
+    fn spatial_filter(
+        &self,
+        layer: &Layer,
+        extent: &Extent,
+        zoom: u8,
+        grid: &Grid,
+    ) -> Option<(Geometry, Option<CoordTransform>)> {
+        let mut bbox_extent = if let Some(pixels) = layer.buffer_size {
+            let pixel_width = grid.pixel_width(zoom);
+            let buf = f64::from(pixels) * pixel_width;
+            Extent {
+                minx: extent.minx - buf,
+                miny: extent.miny - buf,
+                maxx: extent.maxx + buf,
+                maxy: extent.maxy + buf,
+            }
+        } else {
+            extent.clone()
+        };
+
+        // CoordTransform for features
+        let mut transformation = None;
+        if let Some(ref wkt) = self.geom_transform.get(&layer.name) {
+            let grid_sref = sref(grid.srid as u32).unwrap();
+            let layer_sref = SpatialRef::from_wkt(wkt).unwrap();
+            // Spatial filter must be in layer SRS
+            let bbox_tr = CoordTransform::new(&grid_sref, &layer_sref).unwrap();
+            match transform_extent_tr(&bbox_extent, &bbox_tr) {
+                Ok(extent) => bbox_extent = extent,
+                Err(e) => {
+                    error!("Unable to transform {:?}: {:?}", bbox_extent, e);
+                    return None;
+                }
+            }
+            transformation = CoordTransform::new(&layer_sref, &grid_sref).ok();
+        }
+        let bbox = Geometry::bbox(
+            bbox_extent.minx,
+            bbox_extent.miny,
+            bbox_extent.maxx,
+            bbox_extent.maxy,
+        )
+        .unwrap();
+        Some((bbox, transformation))
+    }
 }
 
 impl DatasourceType for GdalDatasource {
@@ -41,6 +96,10 @@ impl DatasourceType for GdalDatasource {
         GdalDatasource {
             path: self.path.clone(),
             geom_transform: BTreeMap::new(),
+            max_concurrent_queries: self.max_concurrent_queries,
+            query_semaphore: self
+                .max_concurrent_queries
+                .map(|permits| Arc::new(Semaphore::new(permits))),
         }
     }
     fn detect_layers(&self, _detect_geometry_types: bool) -> Vec<Layer> {
@@ -106,10 +165,17 @@ impl DatasourceType for GdalDatasource {
             Ok(sref) => sref,
         };
 
-        let layer_sref = geom_spatialref(&ogr_layer, layer.geometry_field.as_ref());
-        let src_sref = match layer_sref {
-            Some(ref sref) if !layer.no_transform => sref,
-            _ => &grid_sref,
+        let src_sref = match layer.effective_extent_srid(grid_srid) {
+            Some(srid) if srid != grid_srid => match sref(srid as u32) {
+                Err(e) => {
+                    error!("Unable to get layer spatial reference: {:?}", e);
+                    return None;
+                }
+                Ok(sref) => sref,
+            },
+            Some(_) => grid_sref,
+            // Fall back to spatial reference of data source
+            None => geom_spatialref(&ogr_layer, layer.geometry_field.as_ref()).unwrap_or(grid_sref),
         };
 
         let wgs84_sref = match sref(4326) {
@@ -121,7 +187,7 @@ impl DatasourceType for GdalDatasource {
         };
 
         match extent {
-            Some(extent) => match transform_extent_sref(&extent, src_sref, &wgs84_sref) {
+            Some(extent) => match transform_extent_sref(&extent, &src_sref, &wgs84_sref) {
                 Ok(extent) => Some(extent),
                 Err(e) => {
                     error!("Unable to transform {:?}: {:?}", extent, e);
@@ -145,6 +211,12 @@ impl DatasourceType for GdalDatasource {
             return;
         }
         let dataset = dataset.unwrap();
+        if !layer.computed.is_empty() {
+            warn!(
+                "Layer '{}': Computed attributes are not supported by GDAL datasources",
+                layer.name
+            );
+        }
         if layer.table_name.is_none() {
             error!("Layer '{}': table_name missing", layer.name);
             return;
@@ -206,6 +278,12 @@ impl DatasourceType for GdalDatasource {
                 );
             }
         }
+        if layer.server_side_mvt {
+            warn!(
+                "Layer '{}': server_side_mvt not supported for GDAL layers",
+                layer.name
+            );
+        }
     }
     fn retrieve_features<F>(
         &self,
@@ -215,58 +293,39 @@ impl DatasourceType for GdalDatasource {
         zoom: u8,
         grid: &Grid,
         mut read: F,
-    ) -> u64
+    ) -> Result<u64, String>
     where
         F: FnMut(&dyn Feature),
     {
+        let _permit = self.query_semaphore.as_ref().map(|s| s.acquire());
         let dataset = Dataset::open(Path::new(&self.path)).unwrap();
         let layer_name = layer.table_name.as_ref().unwrap();
         debug!("retrieve_features layer: {}", layer_name);
         let mut ogr_layer = dataset.layer_by_name(layer_name).unwrap();
 
-        let mut bbox_extent = if let Some(pixels) = layer.buffer_size {
-            let pixel_width = grid.pixel_width(zoom);
-            let buf = f64::from(pixels) * pixel_width;
-            Extent {
-                minx: extent.minx - buf,
-                miny: extent.miny - buf,
-                maxx: extent.maxx + buf,
-                maxy: extent.maxy + buf,
-            }
-        } else {
-            extent.clone()
+        let (bbox, transformation) = match self.spatial_filter(layer, extent, zoom, grid) {
+            Some(filter) => filter,
+            None => return Ok(0),
         };
-
-        // CoordTransform for features
-        let mut transformation = None;
-        if let Some(ref wkt) = self.geom_transform.get(&layer.name) {
-            let grid_sref = sref(grid.srid as u32).unwrap();
-            let layer_sref = SpatialRef::from_wkt(wkt).unwrap();
-            // Spatial filter must be in layer SRS
-            let bbox_tr = CoordTransform::new(&grid_sref, &layer_sref).unwrap();
-            match transform_extent_tr(&bbox_extent, &bbox_tr) {
-                Ok(extent) => bbox_extent = extent,
-                Err(e) => {
-                    error!("Unable to transform {:?}: {:?}", bbox_extent, e);
-                    return 0;
-                }
-            }
-            transformation = CoordTransform::new(&layer_sref, &grid_sref).ok();
-        }
-        let bbox = Geometry::bbox(
-            bbox_extent.minx,
-            bbox_extent.miny,
-            bbox_extent.maxx,
-            bbox_extent.maxy,
-        )
-        .unwrap();
         ogr_layer.set_spatial_filter(&bbox);
 
         let ogr_layer_for_defn = dataset.layer_by_name(layer_name).unwrap();
         let fields_defn = ogr_layer_for_defn.defn().fields().collect::<Vec<_>>();
         let mut cnt = 0;
         let query_limit = layer.query_limit.unwrap_or(0);
-        for feature in ogr_layer.features() {
+        let features: Box<dyn Iterator<Item = gdal::vector::Feature>> =
+            if let Some(ref order_by) = layer.order_by {
+                // Sort in memory before applying query_limit
+                let mut sorted = ogr_layer
+                    .features()
+                    .map(|f| (OrderKey::from(f.field(order_by).ok().flatten()), f))
+                    .collect::<Vec<_>>();
+                sorted.sort_by(|a, b| a.0.cmp_with(&b.0, layer.order_desc));
+                Box::new(sorted.into_iter().map(|(_, f)| f))
+            } else {
+                Box::new(ogr_layer.features())
+            };
+        for feature in features {
             let feat = VectorFeature {
                 layer: layer,
                 fields_defn: &fields_defn,
@@ -274,6 +333,12 @@ impl DatasourceType for GdalDatasource {
                 transform: transformation.as_ref(),
                 feature: &feature,
             };
+            if layer.geometry_type_filter.is_some() {
+                match feat.geometry() {
+                    Ok(ref geom) if layer.accepts_geometry_type(geom.type_name()) => {}
+                    _ => continue,
+                }
+            }
             read(&feat);
             cnt += 1;
             if cnt == query_limit as u64 {
@@ -284,7 +349,77 @@ impl DatasourceType for GdalDatasource {
                 break;
             }
         }
-        cnt
+        Ok(cnt)
+    }
+    fn count_features(
+        &self,
+        tileset: &str,
+        layer: &Layer,
+        extent: &Extent,
+        zoom: u8,
+        grid: &Grid,
+    ) -> u64 {
+        if layer.geometry_type_filter.is_some() {
+            // Geometry types are only known after reading the features
+            return self
+                .retrieve_features(tileset, layer, extent, zoom, grid, |_| {})
+                .unwrap_or(0);
+        }
+        let _permit = self.query_semaphore.as_ref().map(|s| s.acquire());
+        let dataset = Dataset::open(Path::new(&self.path)).unwrap();
+        let layer_name = layer.table_name.as_ref().unwrap();
+        let mut ogr_layer = dataset.layer_by_name(layer_name).unwrap();
+        let bbox = match self.spatial_filter(layer, extent, zoom, grid) {
+            Some((bbox, _)) => bbox,
+            None => return 0,
+        };
+        ogr_layer.set_spatial_filter(&bbox);
+        let cnt = ogr_layer.feature_count();
+        match layer.query_limit {
+            Some(limit) if limit > 0 => cmp::min(cnt, limit as u64),
+            _ => cnt,
+        }
+    }
+    fn source_mtime(&self) -> Option<SystemTime> {
+        fs::metadata(&self.path).and_then(|m| m.modified()).ok()
+    }
+}
+
+/// Sort key of an `order_by` field value
+enum OrderKey {
+    Number(f64),
+    Text(String),
+    Null,
+}
+
+impl OrderKey {
+    fn from(value: Option<FieldValue>) -> OrderKey {
+        match value {
+            Some(FieldValue::IntegerValue(v)) => OrderKey::Number(f64::from(v)),
+            Some(FieldValue::Integer64Value(v)) => OrderKey::Number(v as f64),
+            Some(FieldValue::RealValue(v)) => OrderKey::Number(v),
+            Some(v) => v.into_string().map_or(OrderKey::Null, OrderKey::Text),
+            None => OrderKey::Null,
+        }
+    }
+    /// Compare keys with NULL values last
+    fn cmp_with(&self, other: &OrderKey, descending: bool) -> Ordering {
+        let ord = match (self, other) {
+            (OrderKey::Null, OrderKey::Null) => return Ordering::Equal,
+            (OrderKey::Null, _) => return Ordering::Greater,
+            (_, OrderKey::Null) => return Ordering::Less,
+            (OrderKey::Number(a), OrderKey::Number(b)) => {
+                a.partial_cmp(b).unwrap_or(Ordering::Equal)
+            }
+            (OrderKey::Number(_), OrderKey::Text(_)) => Ordering::Less,
+            (OrderKey::Text(_), OrderKey::Number(_)) => Ordering::Greater,
+            (OrderKey::Text(a), OrderKey::Text(b)) => a.cmp(b),
+        };
+        if descending {
+            ord.reverse()
+        } else {
+            ord
+        }
     }
 }
 
@@ -339,7 +474,12 @@ fn transform_extent_tr(
 
 impl<'a> Config<'a, DatasourceCfg> for GdalDatasource {
     fn from_config(ds_cfg: &DatasourceCfg) -> Result<Self, String> {
-        Ok(GdalDatasource::new(ds_cfg.path.as_ref().unwrap()))
+        if ds_cfg.max_concurrent_queries == Some(0) {
+            return Err("Invalid max_concurrent_queries 0 (expected >= 1)".to_string());
+        }
+        let mut ds = GdalDatasource::new(ds_cfg.path.as_ref().unwrap());
+        ds.max_concurrent_queries = ds_cfg.max_concurrent_queries;
+        Ok(ds)
     }
 
     fn gen_config() -> String {
@@ -352,12 +492,19 @@ path = "<filename-or-connection-spec>"
         toml.to_string()
     }
     fn gen_runtime_config(&self) -> String {
-        format!(
+        let mut config = format!(
             r#"
 [[datasource]]
 path = "{}"
 "#,
             self.path
-        )
+        );
+        if let Some(max_concurrent_queries) = self.max_concurrent_queries {
+            config.push_str(&format!(
+                "max_concurrent_queries = {}\n",
+                max_concurrent_queries
+            ));
+        }
+        config
     }
 }
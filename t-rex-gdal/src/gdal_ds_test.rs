@@ -52,15 +52,15 @@ fn test_detect_layers() {
     assert_eq!(layers.len(), 3);
     assert_eq!(
         format!("{:?}", layers[0]),
-        r#"Layer { name: "ne_10m_populated_places", datasource: None, geometry_field: Some("geom"), geometry_type: Some("POINT"), srid: Some(3857), no_transform: false, fid_field: None, table_name: Some("ne_10m_populated_places"), query_limit: None, query: [], minzoom: None, maxzoom: None, tile_size: 4096, simplify: false, tolerance: "", buffer_size: None, make_valid: false, shift_longitude: false, style: None }"#
+        r#"Layer { name: "ne_10m_populated_places", enabled: true, datasource: None, geometry_field: Some("geom"), geometry_type: Some("POINT"), geometry_type_filter: None, srid: Some(3857), no_transform: false, fid_field: None, table_name: Some("ne_10m_populated_places"), query_limit: None, order_by: None, order_desc: false, query: [], computed: [], minzoom: None, maxzoom: None, overzoom: false, tile_size: 4096, simplify: false, tolerance: "", tolerance_ranges: [], curve_tolerance: None, buffer_size: None, make_valid: false, shift_longitude: false, clip_on_encode: None, use_st_asmvtgeom: false, server_side_mvt: false, label_point: false, reserved_attr_handling: Keep, attr_case: Preserve, constant_attrs: [], attribute_precision: None, feature_id: Field, style: None, style_file: None, attribution: None }"#
     );
     assert_eq!(
         format!("{:?}", layers[1]),
-        r#"Layer { name: "ne_10m_rivers_lake_centerlines", datasource: None, geometry_field: Some("geom"), geometry_type: Some("LINE"), srid: Some(3857), no_transform: false, fid_field: None, table_name: Some("ne_10m_rivers_lake_centerlines"), query_limit: None, query: [], minzoom: None, maxzoom: None, tile_size: 4096, simplify: false, tolerance: "", buffer_size: None, make_valid: false, shift_longitude: false, style: None }"#
+        r#"Layer { name: "ne_10m_rivers_lake_centerlines", enabled: true, datasource: None, geometry_field: Some("geom"), geometry_type: Some("LINE"), geometry_type_filter: None, srid: Some(3857), no_transform: false, fid_field: None, table_name: Some("ne_10m_rivers_lake_centerlines"), query_limit: None, order_by: None, order_desc: false, query: [], computed: [], minzoom: None, maxzoom: None, overzoom: false, tile_size: 4096, simplify: false, tolerance: "", tolerance_ranges: [], curve_tolerance: None, buffer_size: None, make_valid: false, shift_longitude: false, clip_on_encode: None, use_st_asmvtgeom: false, server_side_mvt: false, label_point: false, reserved_attr_handling: Keep, attr_case: Preserve, constant_attrs: [], attribute_precision: None, feature_id: Field, style: None, style_file: None, attribution: None }"#
     );
     assert_eq!(
         format!("{:?}", layers[2]),
-        r#"Layer { name: "ne_110m_admin_0_countries", datasource: None, geometry_field: Some("geom"), geometry_type: Some("POLYGON"), srid: Some(3857), no_transform: false, fid_field: None, table_name: Some("ne_110m_admin_0_countries"), query_limit: None, query: [], minzoom: None, maxzoom: None, tile_size: 4096, simplify: false, tolerance: "", buffer_size: None, make_valid: false, shift_longitude: false, style: None }"#
+        r#"Layer { name: "ne_110m_admin_0_countries", enabled: true, datasource: None, geometry_field: Some("geom"), geometry_type: Some("POLYGON"), geometry_type_filter: None, srid: Some(3857), no_transform: false, fid_field: None, table_name: Some("ne_110m_admin_0_countries"), query_limit: None, order_by: None, order_desc: false, query: [], computed: [], minzoom: None, maxzoom: None, overzoom: false, tile_size: 4096, simplify: false, tolerance: "", tolerance_ranges: [], curve_tolerance: None, buffer_size: None, make_valid: false, shift_longitude: false, clip_on_encode: None, use_st_asmvtgeom: false, server_side_mvt: false, label_point: false, reserved_attr_handling: Keep, attr_case: Preserve, constant_attrs: [], attribute_precision: None, feature_id: Field, style: None, style_file: None, attribution: None }"#
     );
 }
 
@@ -104,7 +104,8 @@ fn test_gdal_retrieve_points() {
             assert_eq!(feat.fid(), Some(4));
         }
         reccnt += 1;
-    });
+    })
+    .unwrap();
     assert_eq!(reccnt, 1);
 }
 
@@ -171,7 +172,8 @@ fn test_coord_transformation() {
             );
         }
         reccnt += 1;
-    });
+    })
+    .unwrap();
     assert_eq!(reccnt, 1);
 }
 
@@ -203,7 +205,8 @@ fn test_gdal_retrieve_multilines() {
     // without buffer
     ds.retrieve_features("ds", &layer, &extent, 10, &grid, |_| {
         reccnt += 1;
-    });
+    })
+    .unwrap();
     assert_eq!(reccnt, 0);
 
     // with buffer
@@ -211,7 +214,8 @@ fn test_gdal_retrieve_multilines() {
 
     ds.retrieve_features("ds", &layer, &extent, 22, &grid, |_| {
         reccnt += 1;
-    });
+    })
+    .unwrap();
     assert_eq!(reccnt, 0);
 
     let mut reccnt = 0;
@@ -235,7 +239,8 @@ fn test_gdal_retrieve_multilines() {
             }
         }
         reccnt += 1;
-    });
+    })
+    .unwrap();
     assert_eq!(reccnt, 5);
 }
 
@@ -277,10 +282,34 @@ fn test_gdal_retrieve_multipolys() {
             assert_eq!(None, feat.fid());
         }
         reccnt += 1;
-    });
+    })
+    .unwrap();
     assert_eq!(reccnt, 1);
 }
 
+#[test]
+fn test_gdal_count_geometry_type_filter() {
+    let mut layer = Layer::new("multipolys");
+    layer.table_name = Some(String::from("ne_110m_admin_0_countries"));
+    layer.geometry_field = Some(String::from("geom"));
+    layer.srid = Some(3857);
+    let grid = Grid::web_mercator();
+    let extent = Extent {
+        minx: 821850.9,
+        miny: 5909499.5,
+        maxx: 860986.7,
+        maxy: 5948635.3,
+    };
+
+    let mut ds = GdalDatasource::new("../data/natural_earth.gpkg");
+    layer.geometry_type_filter = Some(String::from("POLYGON"));
+    ds.prepare_queries("ds", &layer, grid.srid);
+    assert_eq!(ds.count_features("ds", &layer, &extent, 10, &grid), 1);
+
+    layer.geometry_type_filter = Some(String::from("POINT"));
+    assert_eq!(ds.count_features("ds", &layer, &extent, 10, &grid), 0);
+}
+
 #[test]
 fn test_no_transform() {
     let mut layer = Layer::new("g1k18");
@@ -9,7 +9,9 @@ use gdal::vector::{FieldValue, Geometry};
 use gdal::Dataset;
 use gdal_sys;
 use std::path::Path;
-use t_rex_core::core::feature::{Feature, FeatureAttr, FeatureAttrValType};
+use t_rex_core::core::feature::{
+    fid_from_value, warn_non_integer_fid, Feature, FeatureAttr, FeatureAttrValType,
+};
 use t_rex_core::core::geom::{self, GeometryType};
 use t_rex_core::core::layer::Layer;
 
@@ -366,8 +368,17 @@ impl<'a> Feature for VectorFeature<'a> {
         self.layer.fid_field.as_ref().and_then(|fid| {
             let field_value = self.feature.field(&fid);
             match field_value {
-                Ok(Some(FieldValue::IntegerValue(v))) => Some(v as u64),
-                _ => None,
+                Ok(Some(FieldValue::IntegerValue(v))) => {
+                    fid_from_value(self.layer, Some(FeatureAttrValType::Int(v as i64)))
+                }
+                Ok(Some(FieldValue::Integer64Value(v))) => {
+                    fid_from_value(self.layer, Some(FeatureAttrValType::Int(v)))
+                }
+                Ok(None) => None,
+                _ => {
+                    warn_non_integer_fid(self.layer);
+                    None
+                }
             }
         })
     }
@@ -5,6 +5,7 @@
 
 use clap::ArgMatches;
 use std::collections::HashMap;
+use std::time::SystemTime;
 use t_rex_core::core::config::{ApplicationCfg, DatasourceCfg};
 use t_rex_core::core::feature::Feature;
 use t_rex_core::core::layer::Layer;
@@ -20,6 +21,8 @@ use tile_grid::{Extent, Grid};
 pub enum Datasource {
     Postgis(PostgisDatasource),
     Gdal(GdalDatasource),
+    #[cfg(test)]
+    Spy(SpyDatasource),
 }
 
 impl DatasourceType for Datasource {
@@ -27,18 +30,24 @@ impl DatasourceType for Datasource {
         match self {
             &Datasource::Postgis(ref ds) => Datasource::Postgis(ds.connected()),
             &Datasource::Gdal(ref ds) => Datasource::Gdal(ds.connected()),
+            #[cfg(test)]
+            &Datasource::Spy(ref ds) => Datasource::Spy(ds.connected()),
         }
     }
     fn detect_layers(&self, detect_geometry_types: bool) -> Vec<Layer> {
         match self {
             &Datasource::Postgis(ref ds) => ds.detect_layers(detect_geometry_types),
             &Datasource::Gdal(ref ds) => ds.detect_layers(detect_geometry_types),
+            #[cfg(test)]
+            &Datasource::Spy(ref ds) => ds.detect_layers(detect_geometry_types),
         }
     }
     fn detect_data_columns(&self, layer: &Layer, sql: Option<&String>) -> Vec<(String, String)> {
         match self {
             &Datasource::Postgis(ref ds) => ds.detect_data_columns(layer, sql),
             &Datasource::Gdal(ref ds) => ds.detect_data_columns(layer, sql),
+            #[cfg(test)]
+            &Datasource::Spy(ref ds) => ds.detect_data_columns(layer, sql),
         }
     }
     fn reproject_extent(
@@ -50,18 +59,24 @@ impl DatasourceType for Datasource {
         match self {
             &Datasource::Postgis(ref ds) => ds.reproject_extent(extent, dest_srid, src_srid),
             &Datasource::Gdal(ref ds) => ds.reproject_extent(extent, dest_srid, src_srid),
+            #[cfg(test)]
+            &Datasource::Spy(ref ds) => ds.reproject_extent(extent, dest_srid, src_srid),
         }
     }
     fn layer_extent(&self, layer: &Layer, grid_srid: i32) -> Option<Extent> {
         match self {
             &Datasource::Postgis(ref ds) => ds.layer_extent(layer, grid_srid),
             &Datasource::Gdal(ref ds) => ds.layer_extent(layer, grid_srid),
+            #[cfg(test)]
+            &Datasource::Spy(ref ds) => ds.layer_extent(layer, grid_srid),
         }
     }
     fn prepare_queries(&mut self, tileset: &str, layer: &Layer, grid_srid: i32) {
         match self {
             &mut Datasource::Postgis(ref mut ds) => ds.prepare_queries(tileset, layer, grid_srid),
             &mut Datasource::Gdal(ref mut ds) => ds.prepare_queries(tileset, layer, grid_srid),
+            #[cfg(test)]
+            &mut Datasource::Spy(ref mut ds) => ds.prepare_queries(tileset, layer, grid_srid),
         }
     }
     fn retrieve_features<F>(
@@ -72,7 +87,7 @@ impl DatasourceType for Datasource {
         zoom: u8,
         grid: &Grid,
         read: F,
-    ) -> u64
+    ) -> Result<u64, String>
     where
         F: FnMut(&dyn Feature),
     {
@@ -83,6 +98,87 @@ impl DatasourceType for Datasource {
             &Datasource::Gdal(ref ds) => {
                 ds.retrieve_features(tileset, layer, extent, zoom, grid, read)
             }
+            #[cfg(test)]
+            &Datasource::Spy(ref ds) => {
+                ds.retrieve_features(tileset, layer, extent, zoom, grid, read)
+            }
+        }
+    }
+    fn count_features(
+        &self,
+        tileset: &str,
+        layer: &Layer,
+        extent: &Extent,
+        zoom: u8,
+        grid: &Grid,
+    ) -> u64 {
+        match self {
+            &Datasource::Postgis(ref ds) => ds.count_features(tileset, layer, extent, zoom, grid),
+            &Datasource::Gdal(ref ds) => ds.count_features(tileset, layer, extent, zoom, grid),
+            #[cfg(test)]
+            &Datasource::Spy(ref ds) => ds.count_features(tileset, layer, extent, zoom, grid),
+        }
+    }
+    fn retrieve_mvt_layer(
+        &self,
+        tileset: &str,
+        layer: &Layer,
+        extent: &Extent,
+        zoom: u8,
+        grid: &Grid,
+    ) -> Option<Result<Vec<u8>, String>> {
+        match self {
+            &Datasource::Postgis(ref ds) => {
+                ds.retrieve_mvt_layer(tileset, layer, extent, zoom, grid)
+            }
+            &Datasource::Gdal(ref ds) => ds.retrieve_mvt_layer(tileset, layer, extent, zoom, grid),
+            #[cfg(test)]
+            &Datasource::Spy(ref ds) => ds.retrieve_mvt_layer(tileset, layer, extent, zoom, grid),
+        }
+    }
+    fn source_mtime(&self) -> Option<SystemTime> {
+        match self {
+            &Datasource::Postgis(ref ds) => ds.source_mtime(),
+            &Datasource::Gdal(ref ds) => ds.source_mtime(),
+            #[cfg(test)]
+            &Datasource::Spy(ref ds) => ds.source_mtime(),
+        }
+    }
+    fn query_version(&self, sql: &str) -> Option<String> {
+        match self {
+            &Datasource::Postgis(ref ds) => ds.query_version(sql),
+            &Datasource::Gdal(ref ds) => ds.query_version(sql),
+            #[cfg(test)]
+            &Datasource::Spy(ref ds) => ds.query_version(sql),
+        }
+    }
+}
+
+impl Datasource {
+    /// Check for identical connection settings
+    pub fn same_connection(&self, other: &Datasource) -> bool {
+        match (self, other) {
+            (&Datasource::Postgis(ref ds), &Datasource::Postgis(ref other)) => {
+                ds.connection_url == other.connection_url
+                    && ds.pool_size == other.pool_size
+                    && ds.connection_timeout == other.connection_timeout
+                    && ds.statement_timeout == other.statement_timeout
+                    && ds.max_concurrent_queries == other.max_concurrent_queries
+                    && ds.srid_check == other.srid_check
+            }
+            (&Datasource::Gdal(ref ds), &Datasource::Gdal(ref other)) => {
+                ds.gen_runtime_config() == other.gen_runtime_config()
+            }
+            _ => false,
+        }
+    }
+    /// Datasource clips geometries to the buffered tile extent
+    pub fn clips_geometries(&self) -> bool {
+        match self {
+            &Datasource::Postgis(_) => true,
+            &Datasource::Gdal(_) => false,
+            #[cfg(test)]
+            &Datasource::Spy(_) => false,
         }
     }
 }
@@ -108,10 +204,65 @@ impl<'a> Config<'a, DatasourceCfg> for Datasource {
         match self {
             &Datasource::Postgis(ref ds) => ds.gen_runtime_config(),
             &Datasource::Gdal(ref ds) => ds.gen_runtime_config(),
+            #[cfg(test)]
+            &Datasource::Spy(_) => String::new(),
         }
     }
 }
 
+/// Datasource recording the zoom levels of feature queries and version queries
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub struct SpyDatasource {
+    pub queried_zooms: std::sync::Arc<std::sync::Mutex<Vec<u8>>>,
+    pub version_queries: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+}
+
+#[cfg(test)]
+impl DatasourceType for SpyDatasource {
+    fn connected(&self) -> SpyDatasource {
+        self.clone()
+    }
+    fn detect_layers(&self, _detect_geometry_types: bool) -> Vec<Layer> {
+        Vec::new()
+    }
+    fn detect_data_columns(&self, _layer: &Layer, _sql: Option<&String>) -> Vec<(String, String)> {
+        Vec::new()
+    }
+    fn reproject_extent(
+        &self,
+        _extent: &Extent,
+        _dest_srid: i32,
+        _src_srid: Option<i32>,
+    ) -> Option<Extent> {
+        None
+    }
+    fn layer_extent(&self, _layer: &Layer, _grid_srid: i32) -> Option<Extent> {
+        None
+    }
+    fn prepare_queries(&mut self, _tileset: &str, _layer: &Layer, _grid_srid: i32) {}
+    fn retrieve_features<F>(
+        &self,
+        _tileset: &str,
+        _layer: &Layer,
+        _extent: &Extent,
+        zoom: u8,
+        _grid: &Grid,
+        _read: F,
+    ) -> Result<u64, String>
+    where
+        F: FnMut(&dyn Feature),
+    {
+        self.queried_zooms.lock().unwrap().push(zoom);
+        Ok(0)
+    }
+    fn query_version(&self, sql: &str) -> Option<String> {
+        let mut queries = self.version_queries.lock().unwrap();
+        queries.push(sql.to_string());
+        Some(format!("v{}", queries.len()))
+    }
+}
+
 #[derive(Clone)]
 pub struct Datasources {
     pub datasources: HashMap<String, Datasource>,
@@ -124,7 +275,11 @@ impl<'a> Config<'a, ApplicationCfg> for Datasources {
         let default_name = "<noname>".to_string();
         for ds_cfg in &app_cfg.datasource {
             let name = ds_cfg.name.as_ref().unwrap_or(&default_name);
-            let ds = Datasource::from_config(&ds_cfg).unwrap();
+            let mut ds = Datasource::from_config(ds_cfg).unwrap();
+            // Cancel queries of tiles exceeding the tile timeout
+            if let Datasource::Postgis(ref mut ds) = ds {
+                ds.statement_timeout = ds.statement_timeout.or(app_cfg.service.mvt.tile_timeout_ms);
+            }
             datasources.add(name, ds);
             if ds_cfg.default.unwrap_or(false) {
                 datasources.default = Some(name.clone());
@@ -165,10 +320,9 @@ impl Datasources {
     pub fn from_args(args: &ArgMatches) -> Self {
         let mut datasources = Datasources::new();
         if let Some(dbconn) = args.value_of("dbconn") {
-            datasources.add(
-                &"dbconn".to_string(),
-                Datasource::Postgis(PostgisDatasource::new(dbconn, None, None)),
-            );
+            let mut ds = PostgisDatasource::new(dbconn, None, None);
+            ds.detect_views = args.value_of("detect-views") == Some("true");
+            datasources.add(&"dbconn".to_string(), Datasource::Postgis(ds));
         }
         if let Some(datasource) = args.value_of("datasource") {
             #[cfg(feature = "with-gdal")]
@@ -207,6 +361,20 @@ impl Datasources {
             None => None,
         }
     }
+    /// Names of datasources in `other` which are new or have different connection settings
+    pub fn changed(&self, other: &Datasources) -> Vec<String> {
+        let mut names: Vec<String> = other
+            .datasources
+            .iter()
+            .filter(|(name, ds)| match self.datasources.get(*name) {
+                Some(current) => !current.same_connection(ds),
+                None => true,
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        names.sort();
+        names
+    }
 }
 
 #[cfg(test)]
@@ -233,6 +401,95 @@ fn test_datasource_from_config() {
     );
 }
 
+#[test]
+fn test_changed_datasources() {
+    use t_rex_core::core::parse_config;
+
+    let datasources = |toml: &str| {
+        let config: ApplicationCfg = parse_config(toml.to_string(), "").unwrap();
+        Datasources::from_config(&config).unwrap()
+    };
+    let toml = r#"
+        tileset = []
+        [service.mvt]
+        viewer = false
+        [[datasource]]
+        name = "ds1"
+        dbconn = "postgresql://pi@localhost/db1"
+        [[datasource]]
+        name = "ds2"
+        dbconn = "postgresql://pi@localhost/db2"
+        [grid]
+        predefined = "web_mercator"
+        [webserver]
+        port = 6767
+        "#;
+    let current = datasources(toml);
+    assert!(current.changed(&datasources(toml)).is_empty());
+
+    let changed = datasources(&toml.replace("localhost/db2", "dbhost/db2"));
+    assert_eq!(current.changed(&changed), vec!["ds2".to_string()]);
+
+    let added = datasources(&toml.replace(
+        "[grid]",
+        "[[datasource]]\nname = \"ds3\"\ndbconn = \"postgresql://pi@localhost/db3\"\n[grid]",
+    ));
+    assert_eq!(current.changed(&added), vec!["ds3".to_string()]);
+
+    let pool = datasources(&toml.replace("localhost/db1\"", "localhost/db1\"\npool = 2"));
+    assert_eq!(current.changed(&pool), vec!["ds1".to_string()]);
+
+    let statement_timeout = datasources(&toml.replace(
+        "localhost/db2\"",
+        "localhost/db2\"\nstatement_timeout_ms = 1000",
+    ));
+    assert_eq!(current.changed(&statement_timeout), vec!["ds2".to_string()]);
+
+    let max_queries = datasources(&toml.replace(
+        "localhost/db1\"",
+        "localhost/db1\"\nmax_concurrent_queries = 4",
+    ));
+    assert_eq!(current.changed(&max_queries), vec!["ds1".to_string()]);
+
+    let srid_check =
+        datasources(&toml.replace("localhost/db1\"", "localhost/db1\"\nsrid_check = \"warn\""));
+    assert_eq!(current.changed(&srid_check), vec!["ds1".to_string()]);
+}
+
+#[test]
+fn test_tile_timeout_statement_timeout() {
+    use t_rex_core::core::parse_config;
+
+    let statement_timeout = |toml: &str| {
+        let config: ApplicationCfg = parse_config(toml.to_string(), "").unwrap();
+        match Datasources::from_config(&config).unwrap().default() {
+            Some(Datasource::Postgis(pg)) => pg.statement_timeout,
+            _ => panic!(),
+        }
+    };
+    let toml = r#"
+        tileset = []
+        [service.mvt]
+        viewer = false
+        tile_timeout_ms = 500
+        [[datasource]]
+        dbconn = "postgresql://pi@localhost/db1"
+        [grid]
+        predefined = "web_mercator"
+        [webserver]
+        port = 6767
+        "#;
+    assert_eq!(statement_timeout(toml), Some(500));
+    assert_eq!(
+        statement_timeout(&toml.replace("db1\"", "db1\"\nstatement_timeout_ms = 100")),
+        Some(100)
+    );
+    assert_eq!(
+        statement_timeout(&toml.replace("tile_timeout_ms = 500", "")),
+        None
+    );
+}
+
 #[test]
 fn test_datasource_config_errors() {
     assert_eq!(
@@ -3,9 +3,12 @@
 // Licensed under the MIT License. See LICENSE file in the project root for full license information.
 //
 
-use crate::mvt_service::MvtService;
+use crate::mvt_service::{tile_scheme, MvtService};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use serde_json;
 use std::cmp;
+use t_rex_core::core::config::TileScheme;
+use t_rex_core::core::TileMatrixSet;
 use t_rex_core::datasource::DatasourceType;
 use tile_grid::Grid;
 
@@ -24,6 +27,9 @@ impl MvtService {
             tilejson: String,
             tileurl: String,
             bounds: [f64; 4],
+            minzoom: u8,
+            maxzoom: u8,
+            attribution: String,
             layers: Vec<LayerInfo>,
             supported: bool,
         }
@@ -37,17 +43,22 @@ impl MvtService {
             .tilesets
             .iter()
             .map(|set| {
-                let layerinfos = set
-                    .layers
-                    .iter()
-                    .map(|l| LayerInfo {
+                let mut layerinfos = Vec::new();
+                for l in set.layers.iter().filter(|l| l.enabled) {
+                    layerinfos.push(LayerInfo {
                         name: l.name.clone(),
-                        geometry_type: l.geometry_type.clone(),
-                    })
-                    .collect();
-                let supported = set.layers.iter().any(|l| {
-                    let geom_type = l.geometry_type.clone().unwrap_or("UNKNOWN".to_string());
-                    ["POINT", "LINESTRING", "POLYGON"].contains(&(&geom_type as &str))
+                        geometry_type: l.feature_geometry_type().map(|t| t.to_string()),
+                    });
+                    if l.has_label_layer() {
+                        layerinfos.push(LayerInfo {
+                            name: l.label_layer_name(),
+                            geometry_type: Some("POINT".to_string()),
+                        });
+                    }
+                }
+                let supported = set.layers.iter().filter(|l| l.enabled).any(|l| {
+                    let geom_type = l.feature_geometry_type().unwrap_or("UNKNOWN");
+                    ["POINT", "LINESTRING", "POLYGON"].contains(&geom_type)
                 });
                 let ext = set.get_extent();
                 TilesetInfo {
@@ -55,6 +66,9 @@ impl MvtService {
                     tilejson: format!("{}.json", set.name),
                     tileurl: format!("/{}/{{z}}/{{x}}/{{y}}.pbf", set.name),
                     bounds: [ext.minx, ext.miny, ext.maxx, ext.maxy],
+                    minzoom: set.minzoom(),
+                    maxzoom: set.maxzoom(),
+                    attribution: set.attribution(),
                     layers: layerinfos,
                     supported: supported,
                 }
@@ -73,6 +87,11 @@ impl MvtService {
         let ext = ts.get_extent();
         let center = ts.get_center();
         let zoom = ts.get_start_zoom();
+        let scheme = self
+            .tile_schemes
+            .get(&ts.grid)
+            .cloned()
+            .unwrap_or_else(|| tile_scheme(grid));
         let mut meta = json!({
             "id": tileset,
             "name": tileset,
@@ -81,7 +100,7 @@ impl MvtService {
             "format": "pbf",
             "version": "2.0.0", //edition of the software, keep 2.0 for backwards compat
             "tilejson": "2.2.0", //edition of the tilejson standard adopted
-            "scheme": "xyz",
+            "scheme": scheme.as_str(),
             "bounds": [ext.minx,
                        ext.miny,
                        ext.maxx,
@@ -131,7 +150,13 @@ impl MvtService {
                     meta_json["fields"]
                         .as_object_mut()
                         .unwrap()
-                        .insert(field.clone(), json!(""));
+                        .insert(layer.attr_case.apply(field), json!(""));
+                }
+                for computed in &layer.computed {
+                    meta_json["fields"]
+                        .as_object_mut()
+                        .unwrap()
+                        .insert(layer.attr_case.apply(&computed.name), json!(""));
                 }
                 meta_json
             })
@@ -147,7 +172,7 @@ impl MvtService {
         let layers = self.get_tileset_layers(tileset);
         let vector_layers: Vec<serde_json::Value> = layers
             .iter()
-            .map(|layer| {
+            .flat_map(|layer| {
                 let meta = layer.metadata();
                 let query = layer.query(layer.maxzoom(22));
                 let mut layer_json = json!({
@@ -170,26 +195,92 @@ impl MvtService {
                     layer_json["fields"]
                         .as_object_mut()
                         .unwrap()
-                        .insert(field.clone(), json!(""));
+                        .insert(layer.attr_case.apply(field), json!(""));
+                }
+                for computed in &layer.computed {
+                    layer_json["fields"]
+                        .as_object_mut()
+                        .unwrap()
+                        .insert(layer.attr_case.apply(&computed.name), json!(""));
                 }
-                layer_json
+                // Label points have the attributes of the polygons
+                let label_layer_json = if layer.has_label_layer() {
+                    let mut label_json = layer_json.clone();
+                    label_json["id"] = json!(layer.label_layer_name());
+                    Some(label_json)
+                } else {
+                    None
+                };
+                std::iter::once(layer_json).chain(label_layer_json)
             })
             .collect();
         Ok(json!(vector_layers))
     }
     /// TileJSON metadata (https://github.com/mapbox/tilejson-spec)
+    /// `tilecompression` ("gzip" or "none") tells clients whether tiles have to be decompressed.
     // -> {tileset}.json
-    pub fn get_tilejson(&self, baseurl: &str, tileset: &str, grid: &Grid) -> JsonResult {
+    pub fn get_tilejson(
+        &self,
+        baseurl: &str,
+        tileset: &str,
+        grid: &Grid,
+        tilecompression: &str,
+    ) -> JsonResult {
         let mut metadata = self.get_tilejson_metadata(tileset, grid)?;
         let vector_layers = self.get_tilejson_vector_layers(tileset)?;
-        let url = json!([format!("{}/{}/{{z}}/{{x}}/{{y}}.pbf", baseurl, tileset)]);
+        let mut url = format!("{}/{}/{{z}}/{{x}}/{{y}}.pbf", baseurl, tileset);
+        // Clients fetch fresh tiles after a data update
+        if let Some(version) = self.tileset_version(tileset) {
+            url.push_str(&format!(
+                "?v={}",
+                utf8_percent_encode(&version, NON_ALPHANUMERIC)
+            ));
+        }
+        let url = json!([url]);
         let obj = metadata.as_object_mut().unwrap();
         obj.insert("tiles".to_string(), url);
+        obj.insert("tilecompression".to_string(), json!(tilecompression));
         obj.insert("vector_layers".to_string(), vector_layers);
         Ok(json!(obj))
     }
     /// MapboxGL Style JSON (https://www.mapbox.com/mapbox-gl-style-spec/)
     pub fn get_stylejson(&self, baseurl: &str, tileset: &str) -> JsonResult {
+        let mut stylejson = self.stylejson_root(baseurl);
+        stylejson["sources"] = json!({
+            tileset: {
+                "url": format!("{}/{}.json", baseurl, tileset),
+                "type": "vector"
+            }
+        });
+        let mut layer_styles = vec![Self::background_style()];
+        layer_styles.extend(self.get_layer_styles(tileset, None));
+        stylejson["layers"] = json!(layer_styles);
+        Ok(stylejson)
+    }
+
+    /// MapboxGL Style JSON with all tilesets as sources
+    // -> style.json
+    pub fn get_combined_stylejson(&self, baseurl: &str) -> JsonResult {
+        let mut stylejson = self.stylejson_root(baseurl);
+        let mut sources = serde_json::Map::new();
+        let mut layer_styles = vec![Self::background_style()];
+        for tileset in &self.tilesets {
+            sources.insert(
+                tileset.name.clone(),
+                json!({
+                    "url": format!("{}/{}.json", baseurl, tileset.name),
+                    "type": "vector"
+                }),
+            );
+            // Layer ids are prefixed with the tileset name to keep them unique
+            layer_styles.extend(self.get_layer_styles(&tileset.name, Some(&tileset.name)));
+        }
+        stylejson["sources"] = json!(sources);
+        stylejson["layers"] = json!(layer_styles);
+        Ok(stylejson)
+    }
+
+    fn stylejson_root(&self, baseurl: &str) -> serde_json::Value {
         let mut stylejson = json!({
             "version": 8,
             "name": "t-rex",
@@ -199,33 +290,42 @@ impl MvtService {
                 "maputnik:renderer": "mbgljs"
             },
             "glyphs": format!("{}/fonts/{{fontstack}}/{{range}}.pbf", baseurl),
-            "sources": {
-                tileset: {
-                    "url": format!("{}/{}.json", baseurl, tileset),
-                    "type": "vector"
-                }
-            }
         });
-        let background_layer = json!({
+        if self.sprite {
+            stylejson["sprite"] = json!(format!("{}/sprite", baseurl));
+        }
+        stylejson
+    }
+
+    fn background_style() -> serde_json::Value {
+        json!({
           "id": "background_",
           "type": "background",
           "paint": {
             "background-color": "rgba(255, 255, 255, 1)"
           }
-        }); // TODO: add style.background-color element
+        }) // TODO: add style.background-color element
+    }
+
+    /// Style layers of tileset, referencing `tileset` as source
+    fn get_layer_styles(&self, tileset: &str, id_prefix: Option<&str>) -> Vec<serde_json::Value> {
         let layers = self.get_tileset_layers(tileset);
-        let mut layer_styles: Vec<serde_json::Value> = layers
+        layers
             .iter()
-            .map(|layer| {
+            .flat_map(|layer| {
                 let mut layerjson = if let Some(ref style) = layer.style {
                     serde_json::from_str(&style).unwrap()
                 } else {
                     json!({})
                 };
+                let id = match id_prefix {
+                    Some(prefix) => format!("{}.{}", prefix, layer.name),
+                    None => layer.name.clone(),
+                };
                 layerjson
                     .as_object_mut()
                     .unwrap()
-                    .insert("id".to_string(), json!(layer.name));
+                    .insert("id".to_string(), json!(&id));
                 layerjson
                     .as_object_mut()
                     .unwrap()
@@ -246,8 +346,8 @@ impl MvtService {
                 // or we could add style.minzoom, style.maxzoom elements
 
                 // Default paint type
-                let default_type = if let Some(ref geomtype) = layer.geometry_type {
-                    match &geomtype as &str {
+                let default_type = if let Some(geomtype) = layer.feature_geometry_type() {
+                    match geomtype {
                         "POINT" => "circle",
                         _ => "line",
                     }
@@ -261,14 +361,19 @@ impl MvtService {
                     .entry("type".to_string())
                     .or_insert(json!(default_type));
 
-                layerjson
+                let label_layerjson = if layer.has_label_layer() {
+                    Some(json!({
+                        "id": format!("{}_label", id),
+                        "type": "circle",
+                        "source": tileset,
+                        "source-layer": layer.label_layer_name()
+                    }))
+                } else {
+                    None
+                };
+                std::iter::once(layerjson).chain(label_layerjson)
             })
-            .collect();
-        layer_styles.insert(0, background_layer);
-        // Insert layers in stylejson
-        let obj = stylejson.as_object_mut().unwrap();
-        obj.insert("layers".to_string(), json!(layer_styles));
-        Ok(json!(obj))
+            .collect()
     }
 
     /// MBTiles metadata.json (https://github.com/mapbox/mbtiles-spec/blob/master/1.3/spec.md)
@@ -301,6 +406,144 @@ impl MvtService {
         );
         Ok(json!(obj))
     }
+    /// OGC TileMatrixSet of tileset grid. None for unknown tilesets.
+    // -> {tileset}/grid.json
+    pub fn get_tilematrixset(&self, tileset: &str) -> Option<serde_json::Value> {
+        let ts = self.get_tileset(tileset)?;
+        let grid_name = ts.grid.clone().unwrap_or("default".to_string());
+        let mut tms = self.tileset_grid(tileset).as_tilematrixset_json();
+        tms["id"] = json!(grid_name);
+        Some(tms)
+    }
+    /// Tileset offered by WMTS (tilesets of the default grid)
+    pub fn is_wmts_tileset(&self, tileset: &str) -> bool {
+        self.tilesets
+            .iter()
+            .any(|ts| ts.name == tileset && ts.grid.is_none())
+    }
+    /// WMTS capabilities with tilesets of the default grid (https://www.ogc.org/standard/wmts/)
+    // -> wmts?SERVICE=WMTS&REQUEST=GetCapabilities
+    pub fn get_wmts_capabilities(&self, baseurl: &str) -> String {
+        let kvp_url = xml_escape(&format!("{}/wmts?", baseurl));
+        let layers: String = self
+            .tilesets
+            .iter()
+            .filter(|ts| self.is_wmts_tileset(&ts.name))
+            .map(|ts| {
+                let ext = ts.get_extent();
+                format!(
+                    r#"
+    <Layer>
+      <ows:Title>{name}</ows:Title>
+      <ows:WGS84BoundingBox>
+        <ows:LowerCorner>{} {}</ows:LowerCorner>
+        <ows:UpperCorner>{} {}</ows:UpperCorner>
+      </ows:WGS84BoundingBox>
+      <ows:Identifier>{name}</ows:Identifier>
+      <Style isDefault="true">
+        <ows:Identifier>default</ows:Identifier>
+      </Style>
+      <Format>application/vnd.mapbox-vector-tile</Format>
+      <TileMatrixSetLink>
+        <TileMatrixSet>default</TileMatrixSet>
+      </TileMatrixSetLink>
+    </Layer>"#,
+                    ext.minx,
+                    ext.miny,
+                    ext.maxx,
+                    ext.maxy,
+                    name = xml_escape(&ts.name)
+                )
+            })
+            .collect();
+        let tms = self.grid.as_tilematrixset_json();
+        // WMTS corner coordinates are in CRS axis order (lat/lon for EPSG:4326)
+        let corner = |x: f64, y: f64| {
+            if self.grid.srid == 4326 {
+                format!("{} {}", y, x)
+            } else {
+                format!("{} {}", x, y)
+            }
+        };
+        let matrices: String = tms["tileMatrices"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|matrix| {
+                let origin = &matrix["pointOfOrigin"];
+                let (x, mut y) = (origin[0].as_f64().unwrap(), origin[1].as_f64().unwrap());
+                // WMTS tile rows are counted from the top left corner
+                if matrix["cornerOfOrigin"] == "bottomLeft" {
+                    y += matrix["matrixHeight"].as_f64().unwrap()
+                        * matrix["tileHeight"].as_f64().unwrap()
+                        * matrix["cellSize"].as_f64().unwrap();
+                }
+                format!(
+                    r#"
+      <TileMatrix>
+        <ows:Identifier>{}</ows:Identifier>
+        <ScaleDenominator>{}</ScaleDenominator>
+        <TopLeftCorner>{}</TopLeftCorner>
+        <TileWidth>{}</TileWidth>
+        <TileHeight>{}</TileHeight>
+        <MatrixWidth>{}</MatrixWidth>
+        <MatrixHeight>{}</MatrixHeight>
+      </TileMatrix>"#,
+                    matrix["id"].as_str().unwrap(),
+                    matrix["scaleDenominator"],
+                    corner(x, y),
+                    matrix["tileWidth"],
+                    matrix["tileHeight"],
+                    matrix["matrixWidth"],
+                    matrix["matrixHeight"]
+                )
+            })
+            .collect();
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<Capabilities xmlns="http://www.opengis.net/wmts/1.0" xmlns:ows="http://www.opengis.net/ows/1.1" xmlns:xlink="http://www.w3.org/1999/xlink" version="1.0.0">
+  <ows:ServiceIdentification>
+    <ows:Title>t-rex</ows:Title>
+    <ows:ServiceType>OGC WMTS</ows:ServiceType>
+    <ows:ServiceTypeVersion>1.0.0</ows:ServiceTypeVersion>
+  </ows:ServiceIdentification>
+  <ows:OperationsMetadata>
+    <ows:Operation name="GetCapabilities">
+      <ows:DCP><ows:HTTP><ows:Get xlink:href="{kvp_url}"><ows:Constraint name="GetEncoding"><ows:AllowedValues><ows:Value>KVP</ows:Value></ows:AllowedValues></ows:Constraint></ows:Get></ows:HTTP></ows:DCP>
+    </ows:Operation>
+    <ows:Operation name="GetTile">
+      <ows:DCP><ows:HTTP><ows:Get xlink:href="{kvp_url}"><ows:Constraint name="GetEncoding"><ows:AllowedValues><ows:Value>KVP</ows:Value></ows:AllowedValues></ows:Constraint></ows:Get></ows:HTTP></ows:DCP>
+    </ows:Operation>
+  </ows:OperationsMetadata>
+  <Contents>{layers}
+    <TileMatrixSet>
+      <ows:Identifier>default</ows:Identifier>
+      <ows:SupportedCRS>urn:ogc:def:crs:EPSG::{srid}</ows:SupportedCRS>{matrices}
+    </TileMatrixSet>
+  </Contents>
+</Capabilities>
+"#,
+            kvp_url = kvp_url,
+            layers = layers,
+            srid = self.grid.srid,
+            matrices = matrices
+        )
+    }
+    /// Tileset y of WMTS tile row, which is counted from the top
+    pub fn wmts_ytile(&self, tileset: &str, tilerow: u32, zoom: u8) -> u32 {
+        match self.tileset_scheme(tileset) {
+            TileScheme::Xyz => tilerow,
+            TileScheme::Tms => self.tileset_grid(tileset).ytile_from_xyz(tilerow, zoom),
+        }
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
 }
 
 #[cfg(test)]
@@ -317,6 +560,7 @@ fn test_mvt_metadata() {
     let expected = r#"{
   "tilesets": [
     {
+      "attribution": "",
       "bounds": [
         -180.0,
         -90.0,
@@ -337,6 +581,8 @@ fn test_mvt_metadata() {
           "name": "admin_0_countries"
         }
       ],
+      "maxzoom": 22,
+      "minzoom": 0,
       "name": "osm",
       "supported": true,
       "tilejson": "osm.json",
@@ -348,6 +594,67 @@ fn test_mvt_metadata() {
     assert_eq!(metadata, expected);
 }
 
+#[test]
+fn test_tilejson_scheme() {
+    use t_rex_core::core::read_config;
+
+    let config = read_config("src/test/example.toml").unwrap();
+    let service = MvtService::from_config(&config).unwrap();
+
+    let metadata = service
+        .get_tilejson_metadata("osm", &Grid::web_mercator())
+        .unwrap();
+    assert_eq!(metadata["scheme"], "xyz");
+
+    let metadata = service
+        .get_tilejson_metadata("osm", &Grid::wgs84())
+        .unwrap();
+    assert_eq!(metadata["scheme"], "tms");
+}
+
+#[test]
+fn test_tilematrixset() {
+    use t_rex_core::core::read_config;
+
+    let config = read_config("src/test/example.toml").unwrap();
+    let service = MvtService::from_config(&config).unwrap();
+
+    let tms = service.get_tilematrixset("osm").unwrap();
+    assert_eq!(tms["id"], "default");
+    assert!(service.get_tilematrixset("unknown").is_none());
+    assert_eq!(tms["crs"], "http://www.opengis.net/def/crs/EPSG/0/3857");
+    assert_eq!(tms["tileMatrices"][2]["matrixWidth"], 4);
+    assert_eq!(tms["tileMatrices"][2]["cornerOfOrigin"], "bottomLeft");
+}
+
+#[test]
+fn test_wmts_capabilities() {
+    use t_rex_core::core::read_config;
+
+    let config = read_config("src/test/example.toml").unwrap();
+    let mut service = MvtService::from_config(&config).unwrap();
+
+    let xml = service.get_wmts_capabilities("http://127.0.0.1:6767");
+    assert!(xml.contains("<ows:Identifier>osm</ows:Identifier>"));
+    assert!(xml.contains(r#"<ows:Get xlink:href="http://127.0.0.1:6767/wmts?">"#));
+    assert!(xml.contains("<ows:SupportedCRS>urn:ogc:def:crs:EPSG::3857</ows:SupportedCRS>"));
+    assert!(xml.contains("<MatrixWidth>4</MatrixWidth>"));
+    assert!(xml.contains("<TopLeftCorner>-20037508.342789248 20037508.342789248</TopLeftCorner>"));
+    // WMTS rows are counted from the top like XYZ tiles
+    assert_eq!(service.wmts_ytile("osm", 1, 2), 1);
+
+    // Values from the request are escaped
+    let xml = service.get_wmts_capabilities(r#"http://"><x a="#);
+    assert!(xml.contains(r#"xlink:href="http://&quot;&gt;&lt;x a=/wmts?""#));
+
+    // Tilesets of named grids are not offered
+    assert!(service.is_wmts_tileset("osm"));
+    service.tilesets[0].grid = Some("wgs84".to_string());
+    assert!(!service.is_wmts_tileset("osm"));
+    let xml = service.get_wmts_capabilities("http://127.0.0.1:6767");
+    assert!(!xml.contains("<ows:Identifier>osm</ows:Identifier>"));
+}
+
 #[test]
 #[ignore]
 fn test_tilejson() {
@@ -362,7 +669,7 @@ fn test_tilejson() {
     let metadata = format!(
         "{:#}",
         service
-            .get_tilejson("http://127.0.0.1", "osm", &service.grid)
+            .get_tilejson("http://127.0.0.1", "osm", &service.grid, "none")
             .unwrap()
     );
     println!("{}", metadata);
@@ -387,6 +694,7 @@ fn test_tilejson() {
   "minzoom": 0,
   "name": "osm",
   "scheme": "xyz",
+  "tilecompression": "none",
   "tilejson": "2.2.0",
   "tiles": [
     "http://127.0.0.1/osm/{z}/{x}/{y}.pbf"
@@ -419,6 +727,29 @@ fn test_tilejson() {
     assert_eq!(metadata, expected);
 }
 
+#[test]
+fn test_tilejson_attribution() {
+    use t_rex_core::core::config::ApplicationCfg;
+    use t_rex_core::core::read_config;
+
+    let mut config: ApplicationCfg = read_config("src/test/example.toml").unwrap();
+    config.tilesets[0].attribution = Some("© OpenStreetMap contributors".to_string());
+    config.tilesets[0].layers[0].attribution = Some("Made with Natural Earth".to_string());
+    config.tilesets[0].layers[1].attribution = Some("© OpenStreetMap contributors".to_string());
+    config.tilesets[0].layers[2].attribution = Some("Made with Natural Earth".to_string());
+    let service = MvtService::from_config(&config).unwrap();
+    let tilejson = service.get_tilejson_metadata("osm", &service.grid).unwrap();
+    assert_eq!(
+        tilejson["attribution"],
+        "© OpenStreetMap contributors | Made with Natural Earth"
+    );
+    let metadata = service.get_mvt_metadata().unwrap();
+    assert_eq!(
+        metadata["tilesets"][0]["attribution"],
+        tilejson["attribution"]
+    );
+}
+
 #[test]
 fn test_stylejson() {
     use t_rex_core::core::read_config;
@@ -466,6 +797,132 @@ fn test_stylejson() {
     assert!(json.contains(expected));
 }
 
+#[test]
+fn test_stylejson_style_file() {
+    use t_rex_core::core::config::ApplicationCfg;
+    use t_rex_core::core::read_config;
+
+    let mut config: ApplicationCfg = read_config("src/test/example.toml").unwrap();
+    config.tilesets[0].layers[1].style_file = Some("src/test/buildings_style.json".to_string());
+    let service = MvtService::from_config(&config).unwrap();
+    let json = format!(
+        "{:#}",
+        service.get_stylejson("http://127.0.0.1", "osm").unwrap()
+    );
+    println!("{}", json);
+    let expected = r##"
+      "id": "buildings",
+      "paint": {
+        "line-color": "#8d6e63",
+        "line-width": 2
+      },
+      "source": "osm",
+      "source-layer": "buildings",
+      "type": "line"
+    }"##;
+    assert!(json.contains(expected));
+}
+
+#[test]
+fn test_combined_stylejson() {
+    use t_rex_core::core::config::ApplicationCfg;
+    use t_rex_core::core::read_config;
+
+    let mut config: ApplicationCfg = read_config("src/test/example.toml").unwrap();
+    let mut tileset = config.tilesets[0].clone();
+    tileset.name = "countries".to_string();
+    tileset.layers.retain(|l| l.name == "admin_0_countries");
+    config.tilesets.push(tileset);
+    let service = MvtService::from_config(&config).unwrap();
+    let json = service.get_combined_stylejson("http://127.0.0.1").unwrap();
+    println!("{:#}", json);
+
+    let sources = json["sources"].as_object().unwrap();
+    assert_eq!(sources.len(), 2);
+    assert_eq!(sources["osm"]["url"], "http://127.0.0.1/osm.json");
+    assert_eq!(
+        sources["countries"]["url"],
+        "http://127.0.0.1/countries.json"
+    );
+
+    let layers: Vec<(&str, &str, &str)> = json["layers"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .skip(1)
+        .map(|l| {
+            (
+                l["id"].as_str().unwrap(),
+                l["source"].as_str().unwrap(),
+                l["source-layer"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        layers,
+        vec![
+            ("osm.points", "osm", "points"),
+            ("osm.buildings", "osm", "buildings"),
+            ("osm.admin_0_countries", "osm", "admin_0_countries"),
+            (
+                "countries.admin_0_countries",
+                "countries",
+                "admin_0_countries"
+            ),
+        ]
+    );
+    assert_eq!(json["layers"][0]["id"], "background_");
+    assert_eq!(json["layers"][4]["type"], "fill");
+}
+
+#[test]
+fn test_label_layer_metadata() {
+    use t_rex_core::core::read_config;
+
+    let config = read_config("src/test/example.toml").unwrap();
+    let mut service = MvtService::from_config(&config).unwrap();
+    service.tilesets[0].layers[2].label_point = true;
+
+    let metadata = service.get_mvt_metadata().unwrap();
+    let layers = &metadata["tilesets"][0]["layers"];
+    assert_eq!(layers[3]["name"], "admin_0_countries_label");
+    assert_eq!(layers[3]["geometry_type"], "POINT");
+    let stylejson = service.get_stylejson("http://127.0.0.1", "osm").unwrap();
+    assert_eq!(stylejson["layers"][4]["id"], "admin_0_countries_label");
+    assert_eq!(
+        stylejson["layers"][4]["source-layer"],
+        "admin_0_countries_label"
+    );
+    assert_eq!(stylejson["layers"][4]["type"], "circle");
+}
+
+#[test]
+fn test_disabled_layers() {
+    use t_rex_core::core::read_config;
+
+    let config = read_config("src/test/example.toml").unwrap();
+    let mut service = MvtService::from_config(&config).unwrap();
+    service.tilesets[0].layers[1].enabled = false;
+
+    let metadata = service.get_mvt_metadata().unwrap();
+    let layers = &metadata["tilesets"][0]["layers"];
+    assert_eq!(layers.as_array().unwrap().len(), 2);
+    assert!(!layers.to_string().contains("buildings"));
+    let stylejson = service.get_stylejson("http://127.0.0.1", "osm").unwrap();
+    assert!(!stylejson["layers"].to_string().contains("buildings"));
+
+    // Tiles and TileJSON without enabled layers don't access the datasources
+    for layer in service.tilesets[0].layers.iter_mut() {
+        layer.enabled = false;
+    }
+    let mvt_tile = service.tile("osm", 33, 41, 6, None).unwrap();
+    assert!(mvt_tile.get_layers().is_empty());
+    let tilejson = service
+        .get_tilejson("http://127.0.0.1", "osm", &service.grid, "none")
+        .unwrap();
+    assert_eq!(tilejson["vector_layers"], json!([]));
+}
+
 #[test]
 #[ignore]
 fn test_mbtiles_metadata() {
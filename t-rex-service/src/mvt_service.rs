@@ -6,30 +6,245 @@
 use crate::datasources::{Datasource, Datasources};
 use pbr::ProgressBar;
 use percent_encoding::percent_decode;
+use rand::Rng;
 use serde_json;
 use std::cmp;
+use std::collections::{HashMap, HashSet};
 use std::io::{stderr, Stderr, Stdout};
-use std::time::Instant;
-use t_rex_core::cache::{Cache, Tilecache};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use t_rex_core::cache::{Cache, TileBitmap, Tilecache};
+use t_rex_core::core::config::{
+    EmptyTiles, SridCheck, TileScheme, TilesetType, DEFAULT_COMPRESSION_LEVEL,
+};
+use t_rex_core::core::feature::LabelPointFeature;
 use t_rex_core::core::layer::Layer;
 use t_rex_core::core::stats::Statistics;
 use t_rex_core::core::{ApplicationCfg, Config};
 use t_rex_core::datasource::DatasourceType;
+use t_rex_core::mvt::geom_encoder::decode_commands;
 use t_rex_core::mvt::tile::Tile;
 use t_rex_core::mvt::vector_tile;
 use t_rex_core::service::tileset::{Tileset, WORLD_EXTENT};
-use tile_grid::{extent_wgs84_to_merc, Extent, ExtentInt, Grid, GridIterator};
-use tokio::task;
+use tile_grid::{extent_wgs84_to_merc, Extent, ExtentInt, Grid, GridIterator, Origin};
+use tokio::{signal, task};
+
+/// Tile counts of a `generate` run for one tileset
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct GenerateSummary {
+    pub tileset: String,
+    /// Tiles written into the cache
+    pub generated: u64,
+    /// Tiles skipped because they were already cached
+    pub skipped_existing: u64,
+    /// Tiles skipped because they contained no features
+    pub skipped_empty: u64,
+    /// Tiles not written because a layer query failed
+    pub failed: u64,
+    /// Tile (z, x, y) at which generation was interrupted by a signal
+    pub interrupted: Option<(u8, u32, u32)>,
+}
+
+impl GenerateSummary {
+    fn new(tileset: &str) -> Self {
+        GenerateSummary {
+            tileset: tileset.to_string(),
+            ..Default::default()
+        }
+    }
+    /// Count result of a generation task
+    fn add_generated(
+        &mut self,
+        (zoom, xtile, ytile, stored): (u8, u32, u32, Result<bool, String>),
+        bitmap: Option<&mut TileBitmap>,
+    ) {
+        match stored {
+            Ok(true) => {
+                self.generated += 1;
+                if let Some(bitmap) = bitmap {
+                    bitmap.set(zoom, xtile, ytile);
+                }
+            }
+            Ok(false) => self.skipped_empty += 1,
+            Err(err) => {
+                error!("Tile {}/{}/{}: {}", zoom, xtile, ytile, err);
+                self.failed += 1;
+            }
+        }
+    }
+}
+
+/// Tile returned by `MvtService::tile_cached`
+#[derive(Clone, PartialEq, Debug)]
+pub struct CachedTile {
+    /// Tile content (None for empty tiles)
+    pub data: Option<Vec<u8>>,
+    /// Tile was served from cache or tile bitmap
+    pub cache_hit: bool,
+    /// Time for reading or generating the tile
+    pub elapsed: Duration,
+}
+
+/// Default tile adressing scheme of a grid. Rows of grids with top left origin are counted from the
+/// top (XYZ scheme), others from the bottom (TMS scheme). Web Mercator tiles are adressed in XYZ
+/// scheme by convention.
+pub fn tile_scheme(grid: &Grid) -> TileScheme {
+    if grid.origin == Origin::TopLeft || grid.srid == 3857 {
+        TileScheme::Xyz
+    } else {
+        TileScheme::Tms
+    }
+}
+
+/// Extrapolated cache size of a tileset
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct SizeEstimate {
+    pub tileset: String,
+    /// Number of tiles in extent and zoom range
+    pub tiles: u64,
+    /// Number of generated sample tiles
+    pub samples: u64,
+    /// Estimated total size of cached tiles in bytes
+    pub total_bytes: u64,
+}
+
+/// Number of tiles within tile limits of zoom levels `minzoom` to `maxzoom`
+pub fn tile_count(limits: &[ExtentInt], minzoom: u8, maxzoom: u8) -> u64 {
+    (minzoom..=maxzoom)
+        .filter_map(|zoom| limits.get(zoom as usize))
+        .map(|limit| {
+            limit.maxx.saturating_sub(limit.minx) as u64
+                * limit.maxy.saturating_sub(limit.miny) as u64
+        })
+        .sum()
+}
+
+/// Random tile (z, x, y) within tile limits. Every tile has the same probability.
+pub fn random_tile<R: Rng>(
+    limits: &[ExtentInt],
+    minzoom: u8,
+    maxzoom: u8,
+    rng: &mut R,
+) -> Option<(u8, u32, u32)> {
+    let count = tile_count(limits, minzoom, maxzoom);
+    if count == 0 {
+        return None;
+    }
+    let mut idx = rng.gen_range(0..count);
+    for zoom in minzoom..=maxzoom {
+        let limit = limits.get(zoom as usize)?;
+        let height = limit.maxy.saturating_sub(limit.miny) as u64;
+        let tiles = limit.maxx.saturating_sub(limit.minx) as u64 * height;
+        if idx < tiles {
+            let xtile = limit.minx + (idx / height) as u32;
+            let ytile = limit.miny + (idx % height) as u32;
+            return Some((zoom, xtile, ytile));
+        }
+        idx -= tiles;
+    }
+    None
+}
+
+/// Extrapolate total size of tiles within tile limits from the sizes of `samples` random tiles
+pub fn estimate_size<R, F>(
+    limits: &[ExtentInt],
+    minzoom: u8,
+    maxzoom: u8,
+    samples: u64,
+    rng: &mut R,
+    mut tile_size: F,
+) -> u64
+where
+    R: Rng,
+    F: FnMut(u8, u32, u32) -> u64,
+{
+    let count = tile_count(limits, minzoom, maxzoom);
+    if count == 0 || samples == 0 {
+        return 0;
+    }
+    let mut sampled_bytes = 0;
+    for _ in 0..samples {
+        if let Some((zoom, xtile, ytile)) = random_tile(limits, minzoom, maxzoom, rng) {
+            sampled_bytes += tile_size(zoom, xtile, ytile);
+        }
+    }
+    (sampled_bytes as f64 / samples as f64 * count as f64).round() as u64
+}
 
 /// Mapbox Vector Tile Service
 #[derive(Clone)]
 pub struct MvtService {
     pub datasources: Datasources,
+    /// Default grid
     pub grid: Grid,
+    /// Named grids referenced by tilesets
+    pub grids: HashMap<String, Grid>,
+    /// Configured tile adressing schemes by grid name (None: default grid)
+    pub tile_schemes: HashMap<Option<String>, TileScheme>,
     pub tilesets: Vec<Tileset>,
     pub cache: Tilecache,
+    /// Tile presence bitmaps of tilesets
+    pub tile_bitmaps: HashMap<String, TileBitmap>,
+    /// Gzip compression level of cached tiles (0: uncompressed)
+    pub compression_level: u32,
+    /// Handling of tiles without features
+    pub empty_tiles: EmptyTiles,
+    /// Sprite served by web server and referenced in styles
+    pub sprite: bool,
+    /// Tileset versions queried with `version_query`
+    pub version_cache: VersionCache,
+}
+
+/// Time a queried tileset version is reused
+const VERSION_QUERY_TTL: Duration = Duration::from_secs(60);
+
+struct QueriedVersion {
+    version: Option<String>,
+    queried: Instant,
+}
+
+/// Tileset versions queried from datasources, reused for `VERSION_QUERY_TTL`
+#[derive(Clone, Default)]
+pub struct VersionCache {
+    versions: Arc<Mutex<HashMap<String, QueriedVersion>>>,
 }
 
+impl VersionCache {
+    /// Version of tileset, if queried less than `VERSION_QUERY_TTL` before `now`
+    fn get(&self, tileset: &str, now: Instant) -> Option<Option<String>> {
+        let versions = self.versions.lock().unwrap();
+        versions
+            .get(tileset)
+            .filter(|entry| now.duration_since(entry.queried) < VERSION_QUERY_TTL)
+            .map(|entry| entry.version.clone())
+    }
+    fn insert(&self, tileset: &str, version: Option<String>, now: Instant) {
+        let entry = QueriedVersion {
+            version,
+            queried: now,
+        };
+        self.versions
+            .lock()
+            .unwrap()
+            .insert(tileset.to_string(), entry);
+    }
+}
+
+/// Output format of decoded tile features
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DumpFormat {
+    /// GeoJSON with optional splitting of lines at the antimeridian (WGS84 grid only)
+    GeoJson {
+        antimeridian_split: bool,
+    },
+    Wkt,
+}
+
+/// Tile bitmap file name in tileset cache directory
+const TILE_BITMAP_FILE: &str = "tiles.bitmap";
+
 impl MvtService {
     /// Connect all datasources
     // Needed before calling methods on PostGIS datasources like prepare_feature_queries or get_mbtiles_metadata
@@ -43,6 +258,172 @@ impl MvtService {
         datasources.setup();
         self.datasources = datasources;
     }
+    /// Set the extent of tilesets with `detect_extent` and no configured extent
+    /// to the union of their layer extents.
+    // Call after connect
+    pub fn detect_extents(&mut self) {
+        let extents: Vec<Option<Extent>> = self
+            .tilesets
+            .iter()
+            .map(|tileset| {
+                if !tileset.detect_extent || tileset.extent.is_some() {
+                    return None;
+                }
+                tileset
+                    .layers
+                    .iter()
+                    .filter(|layer| layer.enabled)
+                    .filter_map(|layer| {
+                        let extent = self.ds(layer).and_then(|ds| ds.layer_extent(layer, 4326));
+                        if extent.is_none() {
+                            warn!("Layer '{}': Unable to detect extent", layer.name);
+                        }
+                        extent
+                    })
+                    .reduce(|a, b| Extent {
+                        minx: a.minx.min(b.minx),
+                        miny: a.miny.min(b.miny),
+                        maxx: a.maxx.max(b.maxx),
+                        maxy: a.maxy.max(b.maxy),
+                    })
+            })
+            .collect();
+        for (tileset, extent) in self.tilesets.iter_mut().zip(extents) {
+            if let Some(extent) = extent {
+                info!(
+                    "Tileset '{}': detected extent [{:.5}, {:.5}, {:.5}, {:.5}]",
+                    tileset.name, extent.minx, extent.miny, extent.maxx, extent.maxy
+                );
+                tileset.extent = Some(extent);
+            }
+        }
+    }
+    /// Check existence of grid and layer SRIDs in datasources with `srid_check` enabled.
+    /// Returns an error for missing SRIDs with `srid_check = "error"`.
+    // Call after connect
+    pub fn check_srids(&self) -> Result<(), String> {
+        let mut names: Vec<&String> = self.datasources.datasources.keys().collect();
+        names.sort();
+        for name in names {
+            let (ds, srid_check) = match self.datasources.datasources.get(name) {
+                Some(&Datasource::Postgis(ref ds)) if ds.srid_check.is_some() => {
+                    (ds, ds.srid_check.unwrap())
+                }
+                _ => continue,
+            };
+            let mut srids = vec![self.grid.srid];
+            srids.extend(self.grids.values().map(|grid| grid.srid));
+            for tileset in &self.tilesets {
+                for layer in &tileset.layers {
+                    let layer_ds = layer
+                        .datasource
+                        .as_ref()
+                        .or(self.datasources.default.as_ref());
+                    match layer.srid {
+                        Some(srid) if srid > 0 && layer_ds == Some(name) => srids.push(srid),
+                        _ => {}
+                    }
+                }
+            }
+            srids.sort();
+            srids.dedup();
+            let missing = ds
+                .missing_srids(&srids)
+                .map_err(|e| format!("Datasource '{}': {}", name, e))?;
+            if missing.is_empty() {
+                continue;
+            }
+            let msg = format!(
+                "Datasource '{}': SRID(s) {} not found in spatial_ref_sys",
+                name,
+                missing
+                    .iter()
+                    .map(|srid| srid.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            match srid_check {
+                SridCheck::Warn => warn!("{}", msg),
+                SridCheck::Error => return Err(msg),
+            }
+        }
+        Ok(())
+    }
+    /// Check installed PostGIS versions against features used in layer queries.
+    /// Returns logged warnings.
+    // Call after prepare_feature_queries
+    pub fn check_versions(&self) -> Vec<String> {
+        let mut names: Vec<&String> = self.datasources.datasources.keys().collect();
+        names.sort();
+        let mut warnings = Vec::new();
+        for name in names {
+            if let Some(&Datasource::Postgis(ref ds)) = self.datasources.datasources.get(name) {
+                match ds.version_warnings() {
+                    Ok(msgs) => {
+                        for msg in msgs {
+                            warnings.push(format!("Datasource '{}': {}", name, msg));
+                        }
+                    }
+                    Err(e) => warnings.push(format!(
+                        "Datasource '{}': PostGIS version check failed: {}",
+                        name, e
+                    )),
+                }
+            }
+        }
+        for msg in &warnings {
+            warn!("{}", msg);
+        }
+        warnings
+    }
+    /// Clamp layer maxzoom to the maximal zoom level of the tileset grid.
+    /// Returns a warning for each clamped layer.
+    pub fn clamp_zoom_levels(&mut self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        let default_grid = &self.grid;
+        let grids = &self.grids;
+        for tileset in self.tilesets.iter_mut() {
+            let grid = tileset
+                .grid
+                .as_ref()
+                .and_then(|name| grids.get(name))
+                .unwrap_or(default_grid);
+            let grid_maxzoom = grid.maxzoom();
+            for layer in tileset.layers.iter_mut() {
+                let maxzoom = layer.maxzoom(grid_maxzoom);
+                if maxzoom > grid_maxzoom {
+                    warnings.push(format!(
+                        "Layer '{}': maxzoom {} exceeds grid maxzoom {}",
+                        layer.name, maxzoom, grid_maxzoom
+                    ));
+                    layer.maxzoom = Some(grid_maxzoom);
+                }
+            }
+        }
+        warnings
+    }
+    /// Replace datasources with reloaded ones.
+    /// Only new datasources and datasources with changed connection settings are connected,
+    /// others keep their existing connection pool. Returns the names of connected datasources.
+    // Call prepare_feature_queries afterwards
+    pub fn reload_datasources(&mut self, datasources: Datasources) -> Vec<String> {
+        let changed = self.datasources.changed(&datasources);
+        let mut reloaded = Datasources::new();
+        reloaded.default = datasources.default.clone();
+        for (name, ds) in datasources.datasources {
+            let current = self.datasources.datasources.remove(&name);
+            match current {
+                Some(current) if !changed.contains(&name) => reloaded.add(&name, current),
+                _ => {
+                    info!("Connecting datasource '{}'", name);
+                    reloaded.add(&name, ds.connected());
+                }
+            }
+        }
+        reloaded.setup();
+        self.datasources = reloaded;
+        changed
+    }
     pub(crate) fn ds(&self, layer: &Layer) -> Option<&Datasource> {
         self.datasources.datasource(&layer.datasource)
     }
@@ -51,28 +432,108 @@ impl MvtService {
         let dec_name = percent_decode(name.as_bytes()).decode_utf8().unwrap();
         self.tilesets.iter().find(|t| t.name == dec_name)
     }
-    /// Get layers (as reference) of given tileset
+    /// Get enabled layers (as reference) of given tileset
     pub(crate) fn get_tileset_layers(&self, name: &str) -> Vec<&Layer> {
         match self.get_tileset(name) {
-            Some(set) => set.layers.iter().map(|l| l).collect(),
+            Some(set) => set.layers.iter().filter(|l| l.enabled).collect(),
             None => Vec::new(),
         }
     }
     /// Prepare datasource queries. Must be called before requesting tiles.
     pub fn prepare_feature_queries(&mut self) {
         for tileset in &self.tilesets {
-            for layer in &tileset.layers {
+            let grid_srid = self.tileset_grid(&tileset.name).srid;
+            for layer in tileset.layers.iter().filter(|l| l.enabled) {
                 let ds = self.datasources.datasource_mut(&layer.datasource);
                 if ds.is_none() {
                     error!("Datasource of layer `{}` not found", layer.name);
                     continue;
                 }
                 ds.unwrap()
-                    .prepare_queries(&tileset.name, &layer, self.grid.srid);
+                    .prepare_queries(&tileset.name, &layer, grid_srid);
+            }
+        }
+    }
+    /// Grid of tileset. Unknown tilesets use the default grid.
+    pub fn tileset_grid(&self, tileset: &str) -> &Grid {
+        self.get_tileset(tileset)
+            .and_then(|ts| ts.grid.as_ref())
+            .and_then(|name| self.grids.get(name))
+            .unwrap_or(&self.grid)
+    }
+    /// Cache path of tileset. Tilesets with a named grid are stored in a subdirectory of the grid.
+    pub fn tileset_cache_path(&self, tileset: &str) -> String {
+        match self.get_tileset(tileset).and_then(|ts| ts.grid.as_ref()) {
+            Some(grid_name) => format!("{}/{}", grid_name, tileset),
+            None => tileset.to_string(),
+        }
+    }
+    /// Latest modification time of the file based datasources of a tileset
+    pub fn tileset_source_mtime(&self, tileset: &str) -> Option<SystemTime> {
+        self.get_tileset_layers(tileset)
+            .iter()
+            .filter_map(|layer| self.ds(layer).and_then(|ds| ds.source_mtime()))
+            .max()
+    }
+    /// Data version of tileset, configured or queried from the datasource of its first layer
+    pub fn tileset_version(&self, tileset: &str) -> Option<String> {
+        let ts = self.get_tileset(tileset)?;
+        if ts.version.is_some() {
+            return ts.version.clone();
+        }
+        let sql = ts.version_query.as_ref()?;
+        let now = Instant::now();
+        if let Some(version) = self.version_cache.get(tileset, now) {
+            return version;
+        }
+        let layer = ts.layers.iter().find(|layer| layer.enabled)?;
+        let version = self.ds(layer).and_then(|ds| ds.query_version(sql));
+        self.version_cache.insert(tileset, version.clone(), now);
+        version
+    }
+    /// Cached entry is older than its source
+    fn cache_stale(&self, path: &str, source_mtime: Option<SystemTime>) -> bool {
+        match source_mtime {
+            Some(source_mtime) => self
+                .cache
+                .modified(path)
+                .map_or(false, |cache_mtime| cache_mtime < source_mtime),
+            None => false,
+        }
+    }
+    /// Tile adressing scheme of tileset grid
+    pub fn tileset_scheme(&self, tileset: &str) -> TileScheme {
+        let grid_name = self.get_tileset(tileset).and_then(|ts| ts.grid.clone());
+        self.tile_schemes
+            .get(&grid_name)
+            .cloned()
+            .unwrap_or_else(|| tile_scheme(self.tileset_grid(tileset)))
+    }
+    /// Configured tileset extent in grid SRS, if known without reprojecting with a datasource
+    fn tileset_grid_extent(&self, tileset: &Tileset) -> Option<Extent> {
+        let extent = tileset
+            .extent
+            .as_ref()
+            .filter(|ext| **ext != WORLD_EXTENT)?;
+        match self.tileset_grid(&tileset.name).srid {
+            3857 => Some(extent_wgs84_to_merc(extent)),
+            4326 => Some(extent.clone()),
+            _ => None,
+        }
+    }
+    /// Convert y between adressing scheme of tileset grid and row of grid origin
+    pub fn grid_ytile(&self, tileset: &str, ytile: u32, zoom: u8) -> u32 {
+        let grid = self.tileset_grid(tileset);
+        // Reverse y if rows are counted from the other side than the grid origin
+        match (self.tileset_scheme(tileset), &grid.origin) {
+            (TileScheme::Xyz, Origin::BottomLeft) | (TileScheme::Tms, Origin::TopLeft) => {
+                grid.ytile_from_xyz(ytile, zoom)
             }
+            _ => ytile,
         }
     }
-    /// Create vector tile from input at x, y, z in TMS adressing scheme
+    /// Create vector tile from input at x, y, z in TMS adressing scheme.
+    /// Fails if a layer query fails, so that incomplete tiles are never cached.
     pub fn tile(
         &self,
         tileset: &str,
@@ -80,27 +541,60 @@ impl MvtService {
         ytile: u32,
         zoom: u8,
         mut stats: Option<&mut Statistics>,
-    ) -> vector_tile::Tile {
-        let extent = self.grid.tile_extent(xtile, ytile, zoom);
+    ) -> Result<vector_tile::Tile, String> {
+        let grid = self.tileset_grid(tileset);
+        let extent = grid.tile_extent(xtile, ytile, zoom);
         debug!(
             "{}/{}/{}/{} retrieving with {:?}",
             tileset, zoom, xtile, ytile, extent
         );
         let mut tile = Tile::new(&extent, true);
         for layer in self.get_tileset_layers(tileset) {
-            if zoom >= layer.minzoom() && zoom <= layer.maxzoom(self.grid.maxzoom()) {
-                let mut mvt_layer = tile.new_layer(layer);
+            if let Some(data_zoom) = layer.data_zoom(zoom, grid.maxzoom()) {
+                let ds = self.ds(&layer).unwrap();
                 let now = Instant::now();
-                let num_features = self.ds(&layer).unwrap().retrieve_features(
-                    tileset,
-                    &layer,
-                    &extent,
-                    zoom,
-                    &self.grid,
-                    |feat| {
-                        tile.add_feature(&mut mvt_layer, feat);
-                    },
-                );
+                let encoded = if layer.server_side_mvt {
+                    ds.retrieve_mvt_layer(tileset, &layer, &extent, data_zoom, grid)
+                        .transpose()
+                        .map_err(|err| format!("Layer '{}': {}", layer.name, err))?
+                } else {
+                    None
+                };
+                let num_features = if let Some(data) = encoded {
+                    tile.add_encoded_layers(&data).unwrap_or_else(|err| {
+                        error!("Layer '{}': Invalid vector tile: {}", layer.name, err);
+                        0
+                    })
+                } else {
+                    let mut mvt_layer = tile.new_layer(layer);
+                    // Server-side clipping of overzoomed layers uses the buffer of data_zoom
+                    let clips_geometries = ds.clips_geometries() && data_zoom == zoom;
+                    tile.set_clip_on_encode(layer.clip_on_encode(clips_geometries));
+                    let mut label_points = Vec::new();
+                    let num_features = ds
+                        .retrieve_features(tileset, layer, &extent, data_zoom, grid, |feat| {
+                            tile.add_feature(&mut mvt_layer, feat);
+                            if layer.label_point {
+                                label_points.extend(LabelPointFeature::from_feature(feat));
+                            }
+                        })
+                        .map_err(|err| format!("Layer '{}': {}", layer.name, err))?;
+                    if num_features > 0 {
+                        tile.add_layer(mvt_layer);
+                    }
+                    if !label_points.is_empty() {
+                        let mut label_layer = tile.new_layer(layer);
+                        label_layer.set_name(layer.label_layer_name());
+                        tile.set_clip_on_encode(layer.clip_on_encode(clips_geometries));
+                        for feat in &label_points {
+                            tile.add_feature(&mut label_layer, feat);
+                        }
+                        if !label_layer.get_features().is_empty() {
+                            tile.add_layer(label_layer);
+                        }
+                    }
+                    num_features
+                };
                 let elapsed = now.elapsed();
                 if let Some(ref mut stats) = stats {
                     stats.add(
@@ -116,14 +610,12 @@ impl MvtService {
                     "{}/{}/{}/{} layer {}: {} features",
                     tileset, zoom, xtile, ytile, layer.name, num_features
                 );
-                if num_features > 0 {
-                    tile.add_layer(mvt_layer);
-                }
             }
         }
-        tile.mvt_tile
+        Ok(tile.mvt_tile)
     }
-    /// Fetch or create vector tile from input at x, y, z
+    /// Fetch or create vector tile from input at x, y, z.
+    /// Fails without caching the tile if a layer query fails.
     pub fn tile_cached(
         &self,
         tileset: &str,
@@ -132,47 +624,83 @@ impl MvtService {
         zoom: u8,
         gzip: bool,
         stats: Option<&mut Statistics>,
-    ) -> Option<Vec<u8>> {
-        // Reverse y for XYZ scheme (TODO: protocol instead of CRS dependent?)
-        let y = if self.grid.srid == 3857 {
-            self.grid.ytile_from_xyz(ytile, zoom)
-        } else {
-            ytile
+    ) -> Result<CachedTile, String> {
+        let now = Instant::now();
+        let cached_tile = |data, cache_hit| CachedTile {
+            data,
+            cache_hit,
+            elapsed: now.elapsed(),
         };
-        let path = format!("{}/{}/{}/{}.pbf", tileset, zoom, xtile, ytile);
+        let y = self.grid_ytile(tileset, ytile, zoom);
+        let path = format!(
+            "{}/{}/{}/{}.pbf",
+            self.tileset_cache_path(tileset),
+            zoom,
+            xtile,
+            ytile
+        );
 
         let ts = self
             .get_tileset(tileset)
             .expect(&format!("Tileset '{}' not found", tileset));
 
         if zoom < ts.minzoom() || zoom > ts.maxzoom() {
-            return None;
+            return Ok(cached_tile(None, false));
+        }
+
+        if let Some(data) = ts.placeholder_tile_at(zoom) {
+            debug!("{} - Serving placeholder tile", path);
+            return Ok(cached_tile(
+                Some(Tile::tile_content(data.clone(), gzip)),
+                true,
+            ));
+        }
+
+        // Same tile limits as used for generating the cache
+        if let Some(extent) = self.tileset_grid_extent(ts) {
+            let limits = self.tileset_grid(tileset).tile_limits(extent, 0);
+            if let Some(limit) = limits.get(zoom as usize) {
+                if xtile < limit.minx || xtile >= limit.maxx || y < limit.miny || y >= limit.maxy {
+                    debug!("{} - Skipping tile outside of tileset extent", path);
+                    return Ok(cached_tile(None, false));
+                }
+            }
+        }
+
+        // Tiles missing in the bitmap are empty
+        if let Some(bitmap) = self.tile_bitmaps.get(&ts.name) {
+            if bitmap.contains(zoom, xtile, y) == Some(false) {
+                debug!("{} - Skipping tile missing in tile bitmap", path);
+                return Ok(cached_tile(None, true));
+            }
         }
 
         let mut tile: Option<Vec<u8>> = None;
-        if ts.is_cachable_at(zoom) {
+        if !ts.is_cachable_at(zoom) {
+            debug!(
+                "Cache : read ignored for tileset {} at zoom {}",
+                ts.name, zoom
+            );
+        } else if self.cache_stale(&path, self.tileset_source_mtime(tileset)) {
+            debug!("{} - Regenerating tile older than datasource", path);
+        } else {
             self.cache.read(&path, |f| {
                 let mut data = Vec::new();
                 let _ = f.read_to_end(&mut data);
                 tile = Some(data);
             });
-        } else {
-            debug!(
-                "Cache : read ignored for tileset {} at zoom {}",
-                ts.name, zoom
-            );
         }
 
         // Return tile from cache
         if let Some(tilegz) = tile {
-            return Some(Tile::tile_content(tilegz, gzip));
+            return Ok(cached_tile(Some(Tile::tile_content(tilegz, gzip)), true));
         }
 
         // Request tile and write into cache
-        let mvt_tile = self.tile(tileset, xtile, y, zoom, stats);
+        let mvt_tile = self.tile(tileset, xtile, y, zoom, stats)?;
         // Spec: A Vector Tile SHOULD contain at least one layer.
-        if mvt_tile.get_layers().len() > 0 {
-            let tilegz = Tile::tile_bytevec_gz(&mvt_tile);
+        if mvt_tile.get_layers().len() > 0 || self.empty_tiles == EmptyTiles::Store {
+            let tilegz = Tile::tile_bytevec_compressed(&mvt_tile, self.compression_level);
             if ts.is_cachable_at(zoom) {
                 if let Err(ioerr) = self.cache.write(&path, &tilegz) {
                     error!("Error writing {}: {}", path, ioerr);
@@ -183,14 +711,97 @@ impl MvtService {
                     ts.name, zoom
                 );
             }
-            Some(Tile::tile_content(tilegz, gzip))
+            Ok(cached_tile(Some(Tile::tile_content(tilegz, gzip)), false))
         } else {
             // We don't save empty tiles
             // When serving from file cache return 204 No Content
             // Nginx: try_files $uri = 204;
             debug!("{} - Skipping empty tile", path);
-            None
+            Ok(cached_tile(None, false))
+        }
+    }
+    /// Decoded features of vector tile at x, y, z
+    pub fn dump_tile(
+        &self,
+        tileset: &str,
+        xtile: u32,
+        ytile: u32,
+        zoom: u8,
+        format: DumpFormat,
+    ) -> Result<String, String> {
+        let grid = self.tileset_grid(tileset);
+        let y = self.grid_ytile(tileset, ytile, zoom);
+        let extent = grid.tile_extent(xtile, y, zoom);
+        let mvt_tile = self.tile(tileset, xtile, y, zoom, None)?;
+        let mut features = Vec::new();
+        let mut lines = Vec::new();
+        for layer in mvt_tile.get_layers() {
+            for feature in layer.get_features() {
+                let geom = decode_commands(
+                    feature.get_geometry(),
+                    feature.get_field_type(),
+                    &extent,
+                    layer.get_extent(),
+                );
+                match format {
+                    DumpFormat::Wkt => {
+                        lines.push(format!("{}\t{}", layer.get_name(), geom.to_wkt()));
+                    }
+                    DumpFormat::GeoJson { antimeridian_split } => {
+                        let geometry = if antimeridian_split && grid.srid == 4326 {
+                            geom.to_geojson_antimeridian_split()
+                        } else {
+                            geom.to_geojson()
+                        };
+                        let mut properties = serde_json::Map::new();
+                        for tag in feature.get_tags().chunks(2) {
+                            let key = layer.get_keys().get(tag[0] as usize);
+                            let value = tag
+                                .get(1)
+                                .and_then(|idx| layer.get_values().get(*idx as usize));
+                            if let (Some(key), Some(value)) = (key, value) {
+                                properties.insert(key.clone(), tile_value_json(value));
+                            }
+                        }
+                        let mut json_feature = json!({
+                            "type": "Feature",
+                            "layer": layer.get_name(),
+                            "geometry": geometry,
+                            "properties": properties
+                        });
+                        if feature.has_id() {
+                            json_feature["id"] = json!(feature.get_id());
+                        }
+                        features.push(json_feature);
+                    }
+                }
+            }
         }
+        Ok(match format {
+            DumpFormat::Wkt => lines.join("\n"),
+            DumpFormat::GeoJson { .. } => serde_json::to_string_pretty(&json!({
+                "type": "FeatureCollection",
+                "features": features
+            }))
+            .unwrap(),
+        })
+    }
+    /// Modification time of cached tile, if supported by the cache
+    pub fn tile_modified(
+        &self,
+        tileset: &str,
+        xtile: u32,
+        ytile: u32,
+        zoom: u8,
+    ) -> Option<SystemTime> {
+        let path = format!(
+            "{}/{}/{}/{}.pbf",
+            self.tileset_cache_path(tileset),
+            zoom,
+            xtile,
+            ytile
+        );
+        self.cache.modified(&path)
     }
     fn progress_bar(&self, msg: &str, limits: &ExtentInt) -> ProgressBar<Stdout> {
         let tiles =
@@ -204,26 +815,94 @@ impl MvtService {
         pb
     }
     /// Projected extent in grid SRS
-    pub fn extent_from_input_extent(&self, extent: &Extent, extent_srid: Option<i32>) -> Extent {
+    pub fn extent_from_input_extent(
+        &self,
+        grid: &Grid,
+        extent: &Extent,
+        extent_srid: Option<i32>,
+    ) -> Extent {
         // TODO: use proj4 (directly)
         let extent_srid_unwrapped = extent_srid.unwrap_or(4326);
 
-        if self.grid.srid == 3857 && extent_srid_unwrapped == 4326 {
+        if grid.srid == 3857 && extent_srid_unwrapped == 4326 {
             // shortcut for Web Mercator
             extent_wgs84_to_merc(extent)
         } else {
             let ds = self.datasources.default().unwrap();
-            if self.grid.srid == extent_srid_unwrapped {
+            if grid.srid == extent_srid_unwrapped {
                 extent.clone()
             } else {
-                ds.reproject_extent(extent, self.grid.srid, extent_srid)
+                ds.reproject_extent(extent, grid.srid, extent_srid)
                     .expect(&format!(
                         "Error transforming {:?} to SRID {}",
-                        extent, self.grid.srid
+                        extent, grid.srid
                     ))
             }
         }
     }
+    /// Tile limits and zoom range of tileset within an optional extent
+    fn tileset_limits(
+        &self,
+        tileset: &Tileset,
+        extent: Option<&Extent>,
+        extent_srid: Option<i32>,
+        minzoom: Option<u8>,
+        maxzoom: Option<u8>,
+    ) -> (Vec<ExtentInt>, u8, u8) {
+        let grid = self.tileset_grid(&tileset.name);
+        // Convert extent to grid SRS
+        let input_extent = extent.or(tileset.extent.as_ref());
+        debug!("input extent: {:?}", input_extent);
+        let ext_proj = match input_extent {
+            // (-180 -90) throws error when projecting
+            Some(ext_wgs84) if *ext_wgs84 != WORLD_EXTENT => {
+                self.extent_from_input_extent(grid, ext_wgs84, extent_srid)
+            }
+            _ => {
+                warn!("Building cache for the full globe, please fill in the tileset extent");
+                grid.tile_extent(0, 0, 0)
+            }
+        };
+        debug!("tile limits: {:?}", ext_proj);
+
+        let tolerance = 0;
+        let limits = grid.tile_limits(ext_proj, tolerance);
+
+        let ts_minzoom = cmp::max(tileset.minzoom(), minzoom.unwrap_or(0));
+        let ts_maxzoom = *[tileset.maxzoom(), maxzoom.unwrap_or(99), grid.maxzoom()]
+            .iter()
+            .min()
+            .unwrap_or(&22);
+        if minzoom.is_some() && minzoom.unwrap() < ts_minzoom {
+            warn!("Skipping zoom levels <{}", ts_minzoom);
+        }
+        if maxzoom.is_some() && maxzoom.unwrap() > ts_maxzoom {
+            warn!("Skipping zoom levels >{}", ts_maxzoom);
+        }
+        (limits, ts_minzoom, ts_maxzoom)
+    }
+    /// Number of concurrent tile generation tasks. Defaults to twice the number of cores.
+    pub fn generate_task_queue_size(&self, threads: Option<usize>) -> Result<usize, String> {
+        let threads = match threads {
+            Some(0) => return Err("Number of generate threads must be at least 1".to_string()),
+            Some(threads) => threads,
+            // libspatialite has a max connection limit of 64 for now. libspatialite (4.4.0) when
+            // compiled on top of GEOS 3.5.0 is able to support an arbitrary number of threads
+            None => return Ok(cmp::min(num_cpus::get() * 2, 64)),
+        };
+        for (name, ds) in &self.datasources.datasources {
+            if let Datasource::Postgis(ref ds) = ds {
+                let pool_size = ds.pool_size.unwrap_or(8) as usize;
+                if threads > pool_size {
+                    warn!(
+                        "{} generate threads exceed pool size {} of datasource '{}'",
+                        threads, pool_size, name
+                    );
+                }
+            }
+        }
+        Ok(threads)
+    }
     /// Seed tile cache
     pub fn generate(
         &self,
@@ -236,8 +915,19 @@ impl MvtService {
         progress: bool,
         overwrite: bool,
         extent_srid: Option<i32>,
-    ) {
+        threads: Option<usize>,
+    ) -> Vec<GenerateSummary> {
+        let mut summaries = Vec::new();
+        let task_queue_size = match self.generate_task_queue_size(threads) {
+            Ok(size) => size,
+            Err(err) => {
+                error!("{}", err);
+                return summaries;
+            }
+        };
         let rt = tokio::runtime::Runtime::new().expect("Couldn't initialize tokio runtime");
+        let interrupted = Arc::new(AtomicBool::new(false));
+        rt.spawn(watch_interrupt(interrupted.clone()));
         self.init_cache();
         let nodes = nodes.unwrap_or(1) as u64;
         let nodeno = nodeno.unwrap_or(0) as u64;
@@ -246,44 +936,17 @@ impl MvtService {
             if tileset_name.is_some() && tileset_name.unwrap() != &tileset.name {
                 continue;
             }
+            if !tileset.cache {
+                info!("Tileset '{}': caching disabled, skipping", tileset.name);
+                continue;
+            }
             if progress {
                 println!("Generating tileset '{}'...", tileset.name);
             }
 
-            // Convert extent to grid SRS
-            let input_extent = extent.as_ref().or(tileset.extent.as_ref());
-            debug!("input extent: {:?}", input_extent);
-            let ext_proj = match input_extent {
-                // (-180 -90) throws error when projecting
-                Some(ext_wgs84) if *ext_wgs84 != WORLD_EXTENT => {
-                    self.extent_from_input_extent(ext_wgs84, extent_srid)
-                }
-                _ => {
-                    warn!("Building cache for the full globe, please fill in the tileset extent");
-                    self.grid.tile_extent(0, 0, 0)
-                }
-            };
-            debug!("tile limits: {:?}", ext_proj);
-
-            let tolerance = 0;
-            let limits = self.grid.tile_limits(ext_proj, tolerance);
-
-            let ts_minzoom = cmp::max(tileset.minzoom(), minzoom.unwrap_or(0));
-            let ts_maxzoom = *[
-                tileset.maxzoom(),
-                maxzoom.unwrap_or(99),
-                self.grid.maxzoom(),
-            ]
-            .iter()
-            .min()
-            .unwrap_or(&22);
-            if minzoom.is_some() && minzoom.unwrap() < ts_minzoom {
-                warn!("Skipping zoom levels <{}", ts_minzoom);
-            }
-            if maxzoom.is_some() && maxzoom.unwrap() > ts_maxzoom {
-                warn!("Skipping zoom levels >{}", ts_maxzoom);
-            }
-            rt.block_on(self.generate_tileset(
+            let (limits, ts_minzoom, ts_maxzoom) =
+                self.tileset_limits(tileset, extent.as_ref(), extent_srid, minzoom, maxzoom);
+            let summary = rt.block_on(self.generate_tileset(
                 limits,
                 &tileset.name,
                 ts_minzoom,
@@ -292,11 +955,111 @@ impl MvtService {
                 nodeno,
                 progress,
                 overwrite,
+                task_queue_size,
+                &interrupted,
             ));
+            let mut summary_line = format!(
+                "Tileset '{}': {} tiles generated, {} existing skipped, {} empty skipped",
+                summary.tileset, summary.generated, summary.skipped_existing, summary.skipped_empty
+            );
+            if summary.failed > 0 {
+                summary_line.push_str(&format!(", {} failed", summary.failed));
+            }
+            if let Some((zoom, xtile, ytile)) = summary.interrupted {
+                summary_line.push_str(&format!(
+                    ", interrupted at tile {}/{}/{}",
+                    zoom, xtile, ytile
+                ));
+            }
+            if progress {
+                // Start a new line after the progress bar
+                println!("\n{}", summary_line);
+            } else {
+                info!("{}", summary_line);
+            }
+            let stop = summary.interrupted.is_some();
+            summaries.push(summary);
+            if stop {
+                break;
+            }
         }
-        if progress {
-            println!("");
+        summaries
+    }
+    /// Remove cached tiles of tileset within optional extent and zoom range.
+    /// Returns the number of removed tiles or None if the tileset doesn't exist.
+    pub fn purge(
+        &self,
+        tileset_name: &str,
+        minzoom: Option<u8>,
+        maxzoom: Option<u8>,
+        extent: Option<Extent>,
+        extent_srid: Option<i32>,
+    ) -> Option<usize> {
+        let tileset = self.get_tileset(tileset_name)?;
+        let cache_path = self.tileset_cache_path(tileset_name);
+        let mut removed = 0;
+        if let Some(ref extent) = extent {
+            let (limits, ts_minzoom, ts_maxzoom) =
+                self.tileset_limits(tileset, Some(extent), extent_srid, minzoom, maxzoom);
+            let griditer = GridIterator::new(ts_minzoom, ts_maxzoom, limits);
+            for (zoom, xtile, ytile) in griditer {
+                let y = self.grid_ytile(tileset_name, ytile, zoom);
+                let path = format!("{}/{}/{}/{}.pbf", cache_path, zoom, xtile, y);
+                if self.cache.remove(&path) {
+                    removed += 1;
+                }
+            }
+        } else {
+            // Only zoom level directories, keeping metadata files of the tileset
+            let grid = self.tileset_grid(tileset_name);
+            for zoom in minzoom.unwrap_or(0)..=maxzoom.unwrap_or(grid.maxzoom()) {
+                removed += self.cache.purge(&format!("{}/{}/", cache_path, zoom));
+            }
+        }
+        // Purged tiles may be non-empty after regeneration
+        let bitmap_path = format!("{}/{}", cache_path, TILE_BITMAP_FILE);
+        if self.cache.remove(&bitmap_path) {
+            info!("Tileset '{}': tile bitmap removed", tileset_name);
         }
+        Some(removed)
+    }
+    /// Estimate cache size of tileset by generating random sample tiles
+    pub fn estimate(
+        &self,
+        tileset_name: &str,
+        minzoom: Option<u8>,
+        maxzoom: Option<u8>,
+        extent: Option<Extent>,
+        extent_srid: Option<i32>,
+        samples: u64,
+    ) -> Option<SizeEstimate> {
+        let tileset = self.get_tileset(tileset_name)?;
+        let (limits, ts_minzoom, ts_maxzoom) =
+            self.tileset_limits(tileset, extent.as_ref(), extent_srid, minzoom, maxzoom);
+        let total_bytes = estimate_size(
+            &limits,
+            ts_minzoom,
+            ts_maxzoom,
+            samples,
+            &mut rand::thread_rng(),
+            |zoom, xtile, ytile| match self.tile(tileset_name, xtile, ytile, zoom, None) {
+                // Empty tiles are not written into the cache
+                Ok(ref mvt_tile) if !mvt_tile.get_layers().is_empty() => {
+                    Tile::tile_bytevec_compressed(mvt_tile, self.compression_level).len() as u64
+                }
+                Ok(_) => 0,
+                Err(err) => {
+                    warn!("Sample tile {}/{}/{} failed: {}", zoom, xtile, ytile, err);
+                    0
+                }
+            },
+        );
+        Some(SizeEstimate {
+            tileset: tileset_name.to_string(),
+            tiles: tile_count(&limits, ts_minzoom, ts_maxzoom),
+            samples,
+            total_bytes,
+        })
     }
     /// Seed tile cache for tileset
     async fn generate_tileset(
@@ -309,17 +1072,28 @@ impl MvtService {
         nodeno: u64,
         progress: bool,
         overwrite: bool,
-    ) {
-        // Keep a queue of tasks waiting for parallel async execution (size >= #cores).
-        // libspatialite has a max connection limit of 64 for now. libspatialite (4.4.0) when
-        // compiled on top of GEOS 3.5.0 is able to support an arbitrary number of threads
-        let task_queue_size = cmp::min(num_cpus::get() * 2, 64);
+        task_queue_size: usize,
+        interrupted: &AtomicBool,
+    ) -> GenerateSummary {
+        let mut summary = GenerateSummary::new(tileset_name);
+        let mut bitmap = self.new_tile_bitmap(tileset_name, &limits, ts_minzoom, ts_maxzoom, nodes);
+        let cache_path = self.tileset_cache_path(tileset_name);
+        let source_mtime = self.tileset_source_mtime(tileset_name);
+        // Keep a queue of tasks waiting for parallel async execution
         let mut tasks = Vec::with_capacity(task_queue_size);
         let griditer = GridIterator::new(ts_minzoom, ts_maxzoom, limits.clone());
         let mut tileno: u64 = 0;
         let mut pb = ProgressBar::new(0);
         let mut pb_z = !ts_minzoom;
+        // Existing tiles of current z/x column
+        let mut column = None;
+        let mut existing_tiles = HashSet::new();
         for (zoom, xtile, ytile) in griditer {
+            // Stop scheduling new tiles, running tasks are finished below
+            if interrupted.load(Ordering::SeqCst) {
+                summary.interrupted = Some((zoom, xtile, ytile));
+                break;
+            }
             if progress && zoom != pb_z {
                 pb_z = zoom;
                 let ref limit = limits[zoom as usize];
@@ -334,18 +1108,27 @@ impl MvtService {
                 continue;
             }
 
-            // Store Mercator tiles in xyz scheme, others in TMS scheme.
-            let y = if self.grid.srid == 3857 {
-                self.grid.ytile_from_xyz(ytile, zoom)
-            } else {
-                ytile
-            };
-            let path = format!("{}/{}/{}/{}.pbf", tileset_name, zoom, xtile, y);
-            let cache_exists = self.cache.exists(&path);
-            if overwrite || !cache_exists {
-                // Entry doesn't exist, or overwrite is forced, so generate it
+            // Store tiles in adressing scheme of grid
+            let y = self.grid_ytile(tileset_name, ytile, zoom);
+            let path = format!("{}/{}/{}/{}.pbf", cache_path, zoom, xtile, y);
+            if column != Some((zoom, xtile)) {
+                column = Some((zoom, xtile));
+                existing_tiles = self.existing_column_tiles(
+                    tileset_name,
+                    &cache_path,
+                    zoom,
+                    xtile,
+                    &limits[zoom as usize],
+                );
+            }
+            let cache_exists = existing_tiles.contains(&ytile);
+            let regenerate = overwrite || (cache_exists && self.cache_stale(&path, source_mtime));
+            if regenerate || !cache_exists {
+                // Entry doesn't exist, is outdated or overwrite is forced, so generate it
                 let svc = self.clone();
                 let cache = self.cache.clone();
+                let compression_level = self.compression_level;
+                let store_empty = self.empty_tiles == EmptyTiles::Store;
                 let tileset_name = tileset_name.clone();
                 tasks.push(task::spawn(async move {
                     // rust-postgres starts its own Tokio runtime
@@ -355,17 +1138,35 @@ impl MvtService {
                     })
                     .await
                     .unwrap();
-                    if mvt_tile.get_layers().len() > 0 {
-                        let tilegz = Tile::tile_bytevec_gz(&mvt_tile);
+                    let mvt_tile = match mvt_tile {
+                        Ok(mvt_tile) => mvt_tile,
+                        // Keep a previously cached tile
+                        Err(err) => return (zoom, xtile, ytile, Err(err)),
+                    };
+                    if mvt_tile.get_layers().len() > 0 || store_empty {
+                        let tilegz = Tile::tile_bytevec_compressed(&mvt_tile, compression_level);
                         if let Err(ioerr) = cache.write(&path, &tilegz) {
                             error!("Error writing {}: {}", path, ioerr);
                         }
-                    } else if overwrite && cache_exists {
-                        cache.remove(&path);
+                        (zoom, xtile, ytile, Ok(true))
+                    } else {
+                        if regenerate && cache_exists {
+                            cache.remove(&path);
+                        }
+                        (zoom, xtile, ytile, Ok(false))
                     }
                 }));
                 if tasks.len() >= task_queue_size {
-                    tasks = await_one_task(tasks).await;
+                    let (result, remaining) = await_one_task(tasks).await;
+                    if let Some(result) = result {
+                        summary.add_generated(result, bitmap.as_mut());
+                    }
+                    tasks = remaining;
+                }
+            } else {
+                summary.skipped_existing += 1;
+                if let Some(ref mut bitmap) = bitmap {
+                    bitmap.set(zoom, xtile, ytile);
                 }
             }
 
@@ -374,35 +1175,155 @@ impl MvtService {
             }
         }
         // Finish remaining tasks
-        futures_util::future::join_all(tasks).await;
+        for result in futures_util::future::join_all(tasks).await {
+            if let Ok(result) = result {
+                summary.add_generated(result, bitmap.as_mut());
+            }
+        }
+        // An incomplete bitmap would hide tiles not generated yet
+        if let Some(mut bitmap) =
+            bitmap.filter(|_| summary.interrupted.is_none() && summary.failed == 0)
+        {
+            let path = format!("{}/{}", cache_path, TILE_BITMAP_FILE);
+            // Keep tiles of previous runs outside the zoom levels and extent of this run
+            let mut data = Vec::new();
+            if self.cache.read(&path, |f| {
+                let _ = f.read_to_end(&mut data);
+            }) {
+                match TileBitmap::from_bytes(&data) {
+                    Ok(existing) => bitmap.merge(&existing),
+                    Err(e) => warn!("Tileset '{}': {}", tileset_name, e),
+                }
+            }
+            if let Err(ioerr) = self.cache.write(&path, &bitmap.to_bytes()) {
+                error!("Error writing {}: {}", path, ioerr);
+            }
+        }
+        if let Err(ioerr) = self.cache.flush() {
+            error!("Error writing buffered tiles: {}", ioerr);
+        }
+        summary
+    }
+    /// Cached tiles (TMS ytile) of a z/x column within limits
+    fn existing_column_tiles(
+        &self,
+        tileset_name: &str,
+        cache_path: &str,
+        zoom: u8,
+        xtile: u32,
+        limit: &ExtentInt,
+    ) -> HashSet<u32> {
+        let paths: Vec<String> = (limit.miny..limit.maxy)
+            .map(|ytile| {
+                let y = self.grid_ytile(tileset_name, ytile, zoom);
+                format!("{}/{}/{}/{}.pbf", cache_path, zoom, xtile, y)
+            })
+            .collect();
+        (limit.miny..limit.maxy)
+            .zip(self.cache.exists_batch(&paths))
+            .filter_map(|(ytile, exists)| if exists { Some(ytile) } else { None })
+            .collect()
+    }
+    /// Empty tile bitmap for tilesets with `tile_bitmap` enabled
+    fn new_tile_bitmap(
+        &self,
+        tileset_name: &str,
+        limits: &[ExtentInt],
+        ts_minzoom: u8,
+        ts_maxzoom: u8,
+        nodes: u64,
+    ) -> Option<TileBitmap> {
+        match self.get_tileset(tileset_name) {
+            Some(ts) if ts.tile_bitmap => {
+                if nodes > 1 {
+                    warn!(
+                        "Tileset '{}': tile bitmap not written when generating on multiple nodes",
+                        tileset_name
+                    );
+                    return None;
+                }
+                let mut bitmap = TileBitmap::new();
+                for zoom in ts_minzoom..=ts_maxzoom {
+                    if let Some(limit) = limits.get(zoom as usize) {
+                        bitmap.add_level(zoom, limit);
+                    }
+                }
+                Some(bitmap)
+            }
+            _ => None,
+        }
+    }
+    /// Load tile bitmaps written by `generate`
+    pub fn load_tile_bitmaps(&mut self) {
+        for tileset in &self.tilesets {
+            if !tileset.tile_bitmap {
+                continue;
+            }
+            let path = format!(
+                "{}/{}",
+                self.tileset_cache_path(&tileset.name),
+                TILE_BITMAP_FILE
+            );
+            let mut data = Vec::new();
+            if !self.cache.read(&path, |f| {
+                let _ = f.read_to_end(&mut data);
+            }) {
+                warn!("Tileset '{}': tile bitmap not found", tileset.name);
+                continue;
+            }
+            match TileBitmap::from_bytes(&data) {
+                Ok(bitmap) => {
+                    info!(
+                        "Tileset '{}': tile bitmap with {} tiles loaded",
+                        tileset.name,
+                        bitmap.count()
+                    );
+                    self.tile_bitmaps.insert(tileset.name.clone(), bitmap);
+                }
+                Err(e) => error!("Tileset '{}': {}", tileset.name, e),
+            }
+        }
     }
     pub fn init_cache(&self) {
         info!("{}", &self.cache.info());
+        let tilecompression = if self.compression_level == 0 {
+            "none"
+        } else {
+            self.cache.tile_compression()
+        };
         for tileset in &self.tilesets {
+            if !tileset.cache {
+                continue;
+            }
+            // Tilesets with a named grid are written into the grid subdirectory
+            let (baseurl, prefix) = match tileset.grid {
+                Some(ref grid_name) => (
+                    format!("{}/{}", self.cache.baseurl(), grid_name),
+                    format!("{}/", grid_name),
+                ),
+                None => (self.cache.baseurl(), String::new()),
+            };
+            let grid = self.tileset_grid(&tileset.name);
             // :tileset.json
             let json = self
-                .get_tilejson(&self.cache.baseurl(), &tileset.name, &self.grid)
+                .get_tilejson(&baseurl, &tileset.name, grid, tilecompression)
                 .unwrap();
             let _ = self.cache.write(
-                &format!("{}.json", &tileset.name),
+                &format!("{}{}.json", prefix, &tileset.name),
                 &serde_json::to_vec(&json).unwrap(),
             );
 
             // :tileset.style.json
-            let json = self
-                .get_stylejson(&self.cache.baseurl(), &tileset.name)
-                .unwrap();
+            let json = self.get_stylejson(&baseurl, &tileset.name).unwrap();
             let _ = self.cache.write(
-                &format!("{}.style.json", &tileset.name),
+                &format!("{}{}.style.json", prefix, &tileset.name),
                 &serde_json::to_vec(&json).unwrap(),
             );
 
             // :tileset/metadata.json
-            let json = self
-                .get_mbtiles_metadata(&tileset.name, &self.grid)
-                .unwrap();
+            let json = self.get_mbtiles_metadata(&tileset.name, grid).unwrap();
             let _ = self.cache.write(
-                &format!("{}/metadata.json", &tileset.name),
+                &format!("{}{}/metadata.json", prefix, &tileset.name),
                 &serde_json::to_vec(&json).unwrap(),
             );
         }
@@ -424,7 +1345,32 @@ impl MvtService {
         maxzoom: Option<u8>,
         points: Vec<f64>,
         progress: bool,
+        count_only: bool,
+    ) -> Statistics {
+        let task_queue_size = cmp::min(num_cpus::get() * 2, 64);
+        self.drilldown_tasks(
+            tileset_name,
+            minzoom,
+            maxzoom,
+            points,
+            progress,
+            count_only,
+            task_queue_size,
+        )
+    }
+    /// Drilldown with tiles of up to `task_queue_size` points generated in parallel.
+    /// With `count_only`, features are counted without building tiles.
+    pub(crate) fn drilldown_tasks(
+        &self,
+        tileset_name: Option<&str>,
+        minzoom: Option<u8>,
+        maxzoom: Option<u8>,
+        points: Vec<f64>,
+        progress: bool,
+        count_only: bool,
+        task_queue_size: usize,
     ) -> Statistics {
+        let rt = tokio::runtime::Runtime::new().expect("Couldn't initialize tokio runtime");
         let mut stats = Statistics::new();
         for tileset in &self.tilesets {
             if tileset_name.is_some() && tileset_name.unwrap() != &tileset.name {
@@ -435,55 +1381,156 @@ impl MvtService {
             let ts_maxzoom = *[
                 tileset.maxzoom(),
                 maxzoom.unwrap_or(99),
-                self.grid.maxzoom(),
+                self.tileset_grid(&tileset.name).maxzoom(),
             ]
             .iter()
             .min()
             .unwrap_or(&22);
 
-            let mut pb =
-                self.progress_bar_drilldown(ts_maxzoom - ts_minzoom + 1, points.len() as u64 / 2);
-
-            for point in points.chunks(2) {
-                // Convert point to extent in grid SRS
-                let ext_wgs84 = Extent {
-                    minx: point[0],
-                    miny: point[1],
-                    maxx: point[0],
-                    maxy: point[1],
-                };
-                let ext_proj = self.extent_from_input_extent(&ext_wgs84, None);
-                debug!("point in grid SRS: {:?}", ext_proj);
-
-                let tolerance = 0;
-                let limits = self.grid.tile_limits(ext_proj, tolerance);
-                for zoom in ts_minzoom..=ts_maxzoom {
-                    let ref limit = limits[zoom as usize];
-                    debug!("level {}: {:?}", zoom, limit);
-                    let xtile = limit.minx;
-                    let ytile = limit.miny;
-                    let mvt_tile = self.tile(
-                        &tileset.name,
-                        xtile as u32,
-                        ytile as u32,
-                        zoom,
-                        Some(&mut stats),
-                    );
-                    stats.add(
-                        format!("tile_bytes.{}.total.{}", &tileset.name, zoom),
-                        Tile::size(&mvt_tile) as u64,
-                    );
-                    if progress {
-                        pb.inc();
-                    }
-                }
-            }
+            let ts_stats = rt.block_on(self.drilldown_tileset(
+                &tileset.name,
+                ts_minzoom,
+                ts_maxzoom,
+                &points,
+                progress,
+                count_only,
+                task_queue_size,
+            ));
+            stats.merge(ts_stats);
         }
         if progress {
             eprintln!("");
         }
         stats
     }
+    async fn drilldown_tileset(
+        &self,
+        tileset_name: &str,
+        ts_minzoom: u8,
+        ts_maxzoom: u8,
+        points: &[f64],
+        progress: bool,
+        count_only: bool,
+        task_queue_size: usize,
+    ) -> Statistics {
+        let mut stats = Statistics::new();
+        let zoomlevels = ts_maxzoom - ts_minzoom + 1;
+        let mut pb = self.progress_bar_drilldown(zoomlevels, points.len() as u64 / 2);
+        let mut tasks = Vec::with_capacity(task_queue_size);
+        for point in points.chunks(2) {
+            let svc = self.clone();
+            let tileset_name = tileset_name.to_string();
+            let (x, y) = (point[0], point[1]);
+            // Tile generation is blocking (see generate_tileset)
+            tasks.push(task::spawn_blocking(move || {
+                svc.drilldown_point(&tileset_name, x, y, ts_minzoom, ts_maxzoom, count_only)
+            }));
+            if tasks.len() >= task_queue_size {
+                let (result, remaining) = await_one_task(tasks).await;
+                if let Some(point_stats) = result {
+                    stats.merge(point_stats);
+                }
+                if progress {
+                    pb.add(zoomlevels as u64);
+                }
+                tasks = remaining;
+            }
+        }
+        // Finish remaining tasks
+        while !tasks.is_empty() {
+            let (result, remaining) = await_one_task(tasks).await;
+            if let Some(point_stats) = result {
+                stats.merge(point_stats);
+            }
+            if progress {
+                pb.add(zoomlevels as u64);
+            }
+            tasks = remaining;
+        }
+        stats
+    }
+    /// Statistics of tiles containing a WGS84 point
+    fn drilldown_point(
+        &self,
+        tileset_name: &str,
+        x: f64,
+        y: f64,
+        ts_minzoom: u8,
+        ts_maxzoom: u8,
+        count_only: bool,
+    ) -> Statistics {
+        let mut stats = Statistics::new();
+        // Convert point to extent in grid SRS
+        let ext_wgs84 = Extent {
+            minx: x,
+            miny: y,
+            maxx: x,
+            maxy: y,
+        };
+        let grid = self.tileset_grid(tileset_name);
+        let ext_proj = self.extent_from_input_extent(grid, &ext_wgs84, None);
+        debug!("point in grid SRS: {:?}", ext_proj);
+
+        let tolerance = 0;
+        let limits = grid.tile_limits(ext_proj, tolerance);
+        for zoom in ts_minzoom..=ts_maxzoom {
+            let ref limit = limits[zoom as usize];
+            debug!("level {}: {:?}", zoom, limit);
+            let xtile = limit.minx;
+            let ytile = limit.miny;
+            if count_only {
+                self.count_tile_features(
+                    tileset_name,
+                    xtile as u32,
+                    ytile as u32,
+                    zoom,
+                    &mut stats,
+                );
+                continue;
+            }
+            let mvt_tile = match self.tile(
+                tileset_name,
+                xtile as u32,
+                ytile as u32,
+                zoom,
+                Some(&mut stats),
+            ) {
+                Ok(mvt_tile) => mvt_tile,
+                Err(err) => {
+                    error!("Tile {}/{}/{}: {}", zoom, xtile, ytile, err);
+                    continue;
+                }
+            };
+            stats.add(
+                format!("tile_bytes.{}.total.{}", tileset_name, zoom),
+                Tile::size(&mvt_tile) as u64,
+            );
+        }
+        stats
+    }
+    /// Add feature counts of tile layers to statistics
+    fn count_tile_features(
+        &self,
+        tileset: &str,
+        xtile: u32,
+        ytile: u32,
+        zoom: u8,
+        stats: &mut Statistics,
+    ) {
+        let grid = self.tileset_grid(tileset);
+        let extent = grid.tile_extent(xtile, ytile, zoom);
+        for layer in self.get_tileset_layers(tileset) {
+            // Overzoomed layers are counted like in tiles of their data zoom level
+            if let Some(data_zoom) = layer.data_zoom(zoom, grid.maxzoom()) {
+                let ds = self.ds(layer).unwrap();
+                let num_features = ds.count_features(tileset, layer, &extent, data_zoom, grid);
+                stats.add(
+                    format!("feature_count.{}.{}.{}", tileset, layer.name, zoom),
+                    num_features,
+                );
+            }
+        }
+    }
     fn gen_layer_runtime_config(&self, layer: &Layer, grid_srid: i32) -> String {
         let ds = self.ds(layer).unwrap();
         let mut lines = vec!["\n[[tileset]]".to_string()];
@@ -519,10 +1566,66 @@ impl MvtService {
     }
 }
 
-async fn await_one_task<T>(tasks: Vec<task::JoinHandle<T>>) -> Vec<task::JoinHandle<T>> {
+/// Set `interrupted` on SIGINT or SIGTERM. A second signal exits immediately.
+async fn watch_interrupt(interrupted: Arc<AtomicBool>) {
+    #[cfg(unix)]
+    let mut sigterm = match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+        Ok(sigterm) => sigterm,
+        Err(e) => {
+            error!("Error installing signal handler: {}", e);
+            return;
+        }
+    };
+    loop {
+        #[cfg(unix)]
+        let exit_code = interrupt_signal(&mut sigterm).await;
+        #[cfg(not(unix))]
+        let exit_code = {
+            let _ = signal::ctrl_c().await;
+            130
+        };
+        if interrupted.swap(true, Ordering::SeqCst) {
+            process::exit(exit_code);
+        }
+        println!("\nInterrupted, waiting for running tasks to finish...");
+    }
+}
+
+/// Wait for SIGINT or SIGTERM. Returns the shell exit code for the received signal.
+#[cfg(unix)]
+async fn interrupt_signal(sigterm: &mut signal::unix::Signal) -> i32 {
+    tokio::select! {
+        _ = signal::ctrl_c() => 130,
+        _ = sigterm.recv() => 143,
+    }
+}
+
+async fn await_one_task<T>(
+    tasks: Vec<task::JoinHandle<T>>,
+) -> (Option<T>, Vec<task::JoinHandle<T>>) {
     match futures_util::future::select_all(tasks).await {
         // Ignoring all errors
-        (_result, _index, remaining) => remaining,
+        (result, _index, remaining) => (result.ok(), remaining),
+    }
+}
+
+fn tile_value_json(value: &vector_tile::Tile_Value) -> serde_json::Value {
+    if value.has_string_value() {
+        json!(value.get_string_value())
+    } else if value.has_double_value() {
+        json!(value.get_double_value())
+    } else if value.has_float_value() {
+        json!(value.get_float_value())
+    } else if value.has_int_value() {
+        json!(value.get_int_value())
+    } else if value.has_uint_value() {
+        json!(value.get_uint_value())
+    } else if value.has_sint_value() {
+        json!(value.get_sint_value())
+    } else if value.has_bool_value() {
+        json!(value.get_bool_value())
+    } else {
+        serde_json::Value::Null
     }
 }
 
@@ -530,18 +1633,77 @@ impl<'a> Config<'a, ApplicationCfg> for MvtService {
     fn from_config(config: &ApplicationCfg) -> Result<Self, String> {
         let datasources = Datasources::from_config(config)?;
         let grid = Grid::from_config(&config.grid)?;
-        let tilesets = config
+        let mut grids = HashMap::new();
+        let mut tile_schemes = HashMap::new();
+        if let Some(scheme) = config.grid.tile_scheme {
+            tile_schemes.insert(None, scheme);
+        }
+        for (name, grid_cfg) in &config.grids {
+            let named_grid =
+                Grid::from_config(grid_cfg).map_err(|e| format!("Grid '{}': {}", name, e))?;
+            grids.insert(name.clone(), named_grid);
+            if let Some(scheme) = grid_cfg.tile_scheme {
+                tile_schemes.insert(Some(name.clone()), scheme);
+            }
+        }
+        let tilesets: Vec<Tileset> = config
             .tilesets
             .iter()
-            .map(|ts_cfg| Tileset::from_config(ts_cfg).unwrap())
-            .collect();
+            .filter(|ts_cfg| ts_cfg.tileset_type == TilesetType::Vector)
+            .map(|ts_cfg| Tileset::from_config(ts_cfg))
+            .collect::<Result<_, _>>()?;
+        for tileset in &tilesets {
+            if let Some(ref grid_name) = tileset.grid {
+                if !grids.contains_key(grid_name) {
+                    return Err(format!(
+                        "Tileset '{}': unknown grid '{}'",
+                        tileset.name, grid_name
+                    ));
+                }
+            }
+            // Geometries of other datasources are in map units, not in tile coordinates
+            for layer in tileset.layers.iter().filter(|l| l.use_st_asmvtgeom) {
+                match datasources.datasource(&layer.datasource) {
+                    Some(&Datasource::Postgis(_)) | None => {}
+                    Some(_) => return Err(format!(
+                        "Layer '{}': use_st_asmvtgeom is only supported for PostGIS datasources",
+                        layer.name
+                    )),
+                }
+            }
+        }
         let cache = Tilecache::from_config(&config)?;
-        Ok(MvtService {
+        let compression_level = config
+            .cache
+            .as_ref()
+            .and_then(|cache| cache.compression_level)
+            .unwrap_or(DEFAULT_COMPRESSION_LEVEL);
+        if compression_level > 9 {
+            return Err(format!(
+                "Invalid cache compression_level {} (expected 0-9)",
+                compression_level
+            ));
+        }
+        let mut service = MvtService {
             datasources,
             grid,
+            grids,
+            tile_schemes,
             tilesets,
             cache,
-        })
+            tile_bitmaps: HashMap::new(),
+            compression_level,
+            empty_tiles: config.service.mvt.empty_tiles,
+            sprite: config.webserver.sprite_dir.is_some(),
+            version_cache: VersionCache::default(),
+        };
+        for msg in service.clamp_zoom_levels() {
+            if config.strict_config {
+                return Err(msg);
+            }
+            warn!("{}", msg);
+        }
+        Ok(service)
     }
     fn gen_config() -> String {
         let mut config = String::new();
@@ -558,8 +1720,9 @@ impl<'a> Config<'a, ApplicationCfg> for MvtService {
         config.push_str(&self.datasources.gen_runtime_config());
         config.push_str(&self.grid.gen_runtime_config());
         for tileset in &self.tilesets {
+            let grid_srid = self.tileset_grid(&tileset.name).srid;
             for layer in &tileset.layers {
-                config.push_str(&self.gen_layer_runtime_config(layer, self.grid.srid));
+                config.push_str(&self.gen_layer_runtime_config(layer, grid_srid));
             }
         }
         config.push_str(&self.cache.gen_runtime_config());
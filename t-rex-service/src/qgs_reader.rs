@@ -155,6 +155,7 @@ pub fn read_qgs(fname: &str) -> (Datasources, Tileset) {
     let mut datasources = Datasources::new();
     let mut tileset = Tileset {
         name: qgs_name.to_string(),
+        grid: None,
         minzoom: None,
         maxzoom: None,
         attribution: None,
@@ -163,6 +164,12 @@ pub fn read_qgs(fname: &str) -> (Datasources, Tileset) {
         start_zoom: None,
         layers: Vec::new(),
         cache_limits: None,
+        detect_extent: false,
+        placeholder_tile: None,
+        cache: true,
+        tile_bitmap: false,
+        version: None,
+        version_query: None,
     };
     for qgslayer in projectlayers.find_all("maplayer") {
         let layertype = qgslayer.get_attr("type").expect("Missing attribute 'type'");
@@ -0,0 +1,149 @@
+//
+// Copyright (c) Pirmin Kalberer. All rights reserved.
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+//
+
+use crate::core::config::AccessLogFormat;
+use actix_web::body::{BodySize, MessageBody};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::Error;
+use futures::future::{ready, LocalBoxFuture, Ready};
+use std::rc::Rc;
+use std::time::Instant;
+
+/// Cache status of a tile response, reported in the access log
+pub struct TileCacheHit(pub bool);
+
+/// Receiver of formatted access log lines
+pub type AccessLogSink = Rc<dyn Fn(String)>;
+
+/// Middleware logging one line per request
+pub struct AccessLog {
+    format: Option<AccessLogFormat>,
+    sink: AccessLogSink,
+}
+
+impl AccessLog {
+    /// Access log written with target `t_rex::access`. No logging without format.
+    pub fn new(format: Option<AccessLogFormat>) -> Self {
+        Self::with_sink(
+            format,
+            Rc::new(|line| info!(target: "t_rex::access", "{}", line)),
+        )
+    }
+    pub fn with_sink(format: Option<AccessLogFormat>, sink: AccessLogSink) -> Self {
+        AccessLog { format, sink }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for AccessLog
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = AccessLogMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AccessLogMiddleware {
+            service,
+            format: self.format,
+            sink: self.sink.clone(),
+        }))
+    }
+}
+
+pub struct AccessLogMiddleware<S> {
+    service: S,
+    format: Option<AccessLogFormat>,
+    sink: AccessLogSink,
+}
+
+impl<S, B> Service<ServiceRequest> for AccessLogMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let format = match self.format {
+            Some(format) => format,
+            None => return Box::pin(self.service.call(req)),
+        };
+        let start = Instant::now();
+        let remote_addr = req
+            .connection_info()
+            .realip_remote_addr()
+            .unwrap_or("-")
+            .to_string();
+        let request_line = format!("{} {} {:?}", req.method(), req.uri(), req.version());
+        let fut = self.service.call(req);
+        let sink = self.sink.clone();
+        Box::pin(async move {
+            let res = fut.await?;
+            let line = match format {
+                AccessLogFormat::Json => json_line(&res, start),
+                AccessLogFormat::Clf => clf_line(&res, &remote_addr, &request_line),
+            };
+            sink(line);
+            Ok(res)
+        })
+    }
+}
+
+fn body_bytes<B: MessageBody>(res: &ServiceResponse<B>) -> u64 {
+    match res.response().body().size() {
+        BodySize::Sized(size) => size,
+        _ => 0,
+    }
+}
+
+fn json_line<B: MessageBody>(res: &ServiceResponse<B>, start: Instant) -> String {
+    let req = res.request();
+    let path_param = |name| req.match_info().get(name);
+    let tile_coord = |name| path_param(name).and_then(|v| v.parse::<u32>().ok());
+    let cache = res
+        .response()
+        .extensions()
+        .get::<TileCacheHit>()
+        .map(|hit| if hit.0 { "hit" } else { "miss" });
+    serde_json::json!({
+        "method": req.method().as_str(),
+        "path": req.path(),
+        "tileset": path_param("tileset"),
+        "z": tile_coord("z"),
+        "x": tile_coord("x"),
+        "y": tile_coord("y"),
+        "status": res.status().as_u16(),
+        "duration_ms": start.elapsed().as_millis() as u64,
+        "bytes": body_bytes(res),
+        "cache": cache,
+    })
+    .to_string()
+}
+
+fn clf_line<B: MessageBody>(
+    res: &ServiceResponse<B>,
+    remote_addr: &str,
+    request_line: &str,
+) -> String {
+    let timestamp = time::strftime("%d/%b/%Y:%H:%M:%S %z", &time::now()).unwrap();
+    format!(
+        "{} - - [{}] \"{}\" {} {}",
+        remote_addr,
+        timestamp,
+        request_line,
+        res.status().as_u16(),
+        body_bytes(res)
+    )
+}
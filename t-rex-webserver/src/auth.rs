@@ -0,0 +1,162 @@
+//
+// Copyright (c) Pirmin Kalberer. All rights reserved.
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+//
+
+use crate::core::config::ApplicationCfg;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header;
+use actix_web::{Error, HttpResponse};
+use futures::future::{ready, LocalBoxFuture, Ready};
+use std::sync::Arc;
+
+/// Expected `Authorization` header values and protected paths
+struct AuthRules {
+    /// `Bearer <token>`
+    bearer: Option<String>,
+    /// `Basic <base64(username:password)>`
+    basic: Option<String>,
+    paths: Vec<String>,
+}
+
+/// Middleware requiring credentials for configured path prefixes.
+/// All other paths stay public.
+#[derive(Clone)]
+pub struct Auth {
+    rules: Option<Arc<AuthRules>>,
+}
+
+impl Auth {
+    pub fn from_config(config: &ApplicationCfg) -> Result<Auth, String> {
+        let auth_cfg = match config.webserver.auth {
+            Some(ref auth_cfg) => auth_cfg,
+            None => return Ok(Auth { rules: None }),
+        };
+        let basic = match (&auth_cfg.username, &auth_cfg.password) {
+            (Some(username), Some(password)) => Some(format!(
+                "Basic {}",
+                base64::encode(format!("{}:{}", username, password))
+            )),
+            (None, None) => None,
+            _ => {
+                return Err(
+                    "webserver.auth: `username` and `password` must be set together".to_string(),
+                )
+            }
+        };
+        let bearer = auth_cfg
+            .token
+            .as_ref()
+            .map(|token| format!("Bearer {}", token));
+        if bearer.is_none() && basic.is_none() {
+            return Err(
+                "webserver.auth: `token` or `username` and `password` required".to_string(),
+            );
+        }
+        Ok(Auth {
+            rules: Some(Arc::new(AuthRules {
+                bearer,
+                basic,
+                paths: auth_cfg.paths.clone(),
+            })),
+        })
+    }
+    /// Credentials are required for `path`
+    pub fn protects(&self, path: &str) -> bool {
+        match self.rules {
+            Some(ref rules) => rules.is_protected(path),
+            None => false,
+        }
+    }
+}
+
+impl AuthRules {
+    fn is_protected(&self, path: &str) -> bool {
+        self.paths.iter().any(|prefix| {
+            let prefix = prefix.trim_end_matches('/');
+            path == prefix || path.starts_with(&format!("{}/", prefix))
+        })
+    }
+    fn is_authorized(&self, authorization: &[u8]) -> bool {
+        [&self.bearer, &self.basic]
+            .iter()
+            .filter_map(|expected| expected.as_ref())
+            .any(|expected| constant_time_eq(expected.as_bytes(), authorization))
+    }
+    fn challenge(&self) -> &'static str {
+        if self.basic.is_some() {
+            "Basic realm=\"t-rex\""
+        } else {
+            "Bearer"
+        }
+    }
+}
+
+/// Compare secrets without leaking the position of the first difference
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Auth
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = AuthMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AuthMiddleware {
+            service,
+            rules: self.rules.clone(),
+        }))
+    }
+}
+
+pub struct AuthMiddleware<S> {
+    service: S,
+    rules: Option<Arc<AuthRules>>,
+}
+
+impl<S, B> Service<ServiceRequest> for AuthMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if let Some(ref rules) = self.rules {
+            // Routing matches the percent-decoded path (e.g. `/%6Detrics` is `/metrics`)
+            if rules.is_protected(req.match_info().as_str()) {
+                let resp = match req.headers().get(header::AUTHORIZATION) {
+                    None => Some(
+                        HttpResponse::Unauthorized()
+                            .insert_header((header::WWW_AUTHENTICATE, rules.challenge()))
+                            .finish(),
+                    ),
+                    Some(value) if !rules.is_authorized(value.as_bytes()) => {
+                        Some(HttpResponse::Forbidden().finish())
+                    }
+                    Some(_) => None,
+                };
+                if let Some(resp) = resp {
+                    info!("{} - Unauthorized request", req.path());
+                    return Box::pin(ready(Ok(req.into_response(resp).map_into_right_body())));
+                }
+            }
+        }
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) })
+    }
+}
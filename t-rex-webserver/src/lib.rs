@@ -14,6 +14,11 @@ extern crate tile_grid;
 use t_rex_core::{cache, core, datasource, service};
 use t_rex_service::{datasources, mvt_service, read_qgs};
 
+mod access_log;
+mod auth;
+mod rate_limit;
+mod reload;
+mod raster_proxy;
 mod runtime_config;
 mod server;
 mod static_files;
@@ -0,0 +1,136 @@
+//
+// Copyright (c) Pirmin Kalberer. All rights reserved.
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+//
+
+//! Passthrough of raster tiles from upstream tile servers
+
+use crate::cache::{Cache, MemoryCache};
+use crate::core::config::{ApplicationCfg, TilesetType};
+use curl::easy::Easy;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Maximal total size of cached raster tiles
+const CACHE_MAX_BYTES: usize = 16 * 1024 * 1024;
+
+/// Raster tilesets proxied from upstream tile servers
+#[derive(Clone)]
+pub struct RasterProxy {
+    /// URL templates by tileset name
+    urls: HashMap<String, String>,
+    cache: MemoryCache,
+}
+
+impl RasterProxy {
+    pub fn from_config(config: &ApplicationCfg) -> Result<RasterProxy, String> {
+        let mut urls = HashMap::new();
+        for ts_cfg in &config.tilesets {
+            if ts_cfg.tileset_type != TilesetType::Raster {
+                continue;
+            }
+            let url = ts_cfg
+                .url
+                .clone()
+                .ok_or(format!("Raster tileset '{}': missing `url`", ts_cfg.name))?;
+            urls.insert(ts_cfg.name.clone(), url);
+        }
+        Ok(RasterProxy {
+            urls,
+            cache: MemoryCache::new(CACHE_MAX_BYTES),
+        })
+    }
+    pub fn is_empty(&self) -> bool {
+        self.urls.is_empty()
+    }
+    pub fn contains(&self, tileset: &str) -> bool {
+        self.urls.contains_key(tileset)
+    }
+    /// Upstream URL of tile. `{y}` is replaced with the XYZ row, `{-y}` with the TMS row.
+    pub fn tile_url(
+        &self,
+        tileset: &str,
+        zoom: u8,
+        xtile: u32,
+        ytile_xyz: u32,
+        ytile_tms: u32,
+    ) -> Option<String> {
+        self.urls.get(tileset).map(|url| {
+            url.replace("{z}", &zoom.to_string())
+                .replace("{x}", &xtile.to_string())
+                .replace("{y}", &ytile_xyz.to_string())
+                .replace("{-y}", &ytile_tms.to_string())
+        })
+    }
+    /// Content type derived from the file extension of the URL template
+    pub fn content_type(&self, tileset: &str) -> &'static str {
+        let url = self.urls.get(tileset).map(|url| url.as_str()).unwrap_or("");
+        let path = url.split('?').next().unwrap_or("").to_lowercase();
+        if path.ends_with(".png") {
+            "image/png"
+        } else if path.ends_with(".jpg") || path.ends_with(".jpeg") {
+            "image/jpeg"
+        } else if path.ends_with(".webp") {
+            "image/webp"
+        } else {
+            "application/octet-stream"
+        }
+    }
+    /// Tile from cache or upstream server. Returns None for unknown tilesets.
+    pub fn tile(
+        &self,
+        tileset: &str,
+        zoom: u8,
+        xtile: u32,
+        ytile_xyz: u32,
+        ytile_tms: u32,
+    ) -> Option<Result<Vec<u8>, String>> {
+        let url = self.tile_url(tileset, zoom, xtile, ytile_xyz, ytile_tms)?;
+        let path = format!("{}/{}/{}/{}", tileset, zoom, xtile, ytile_xyz);
+        let mut cached = None;
+        self.cache.read(&path, |f| {
+            let mut data = Vec::new();
+            if f.read_to_end(&mut data).is_ok() {
+                cached = Some(data);
+            }
+        });
+        if let Some(data) = cached {
+            return Some(Ok(data));
+        }
+        debug!("Fetching raster tile {}", url);
+        let result = fetch(&url);
+        if let Ok(ref data) = result {
+            let _ = self.cache.write(&path, data);
+        }
+        Some(result)
+    }
+}
+
+fn fetch(url: &str) -> Result<Vec<u8>, String> {
+    let mut data = Vec::new();
+    let mut easy = Easy::new();
+    easy.url(url).map_err(|e| e.to_string())?;
+    easy.follow_location(true).map_err(|e| e.to_string())?;
+    easy.timeout(Duration::from_secs(30))
+        .map_err(|e| e.to_string())?;
+    {
+        let mut transfer = easy.transfer();
+        transfer
+            .write_function(|chunk| {
+                data.extend_from_slice(chunk);
+                Ok(chunk.len())
+            })
+            .map_err(|e| e.to_string())?;
+        transfer
+            .perform()
+            .map_err(|e| format!("Upstream tile request {} failed: {}", url, e))?;
+    }
+    match easy.response_code() {
+        Ok(200) => Ok(data),
+        Ok(code) => Err(format!(
+            "Upstream tile request {} failed with status {}",
+            url, code
+        )),
+        Err(e) => Err(e.to_string()),
+    }
+}
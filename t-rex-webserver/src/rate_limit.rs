@@ -0,0 +1,296 @@
+//
+// Copyright (c) Pirmin Kalberer. All rights reserved.
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+//
+
+use crate::core::config::ApplicationCfg;
+use actix_web::body::{EitherBody, MessageBody};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{self, HeaderMap};
+use actix_web::{Error, HttpResponse};
+use futures::future::{ready, LocalBoxFuture, Ready};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Maximal number of tracked clients. Further clients share one bucket.
+const MAX_CLIENTS: usize = 10000;
+/// Bucket of clients exceeding `MAX_CLIENTS`
+const OVERFLOW_CLIENT: &str = "";
+/// Minimal interval between removals of idle buckets
+const SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+struct Buckets {
+    clients: HashMap<String, Bucket>,
+    last_sweep: Option<Instant>,
+}
+
+/// Token buckets by client IP
+struct RateLimiter {
+    rps: f64,
+    burst: f64,
+    buckets: Mutex<Buckets>,
+    trusted_proxies: Vec<IpAddr>,
+}
+
+impl RateLimiter {
+    /// Take a token from the client bucket. Returns seconds until the next token otherwise.
+    fn acquire(&self, client: &str, now: Instant) -> Result<(), u64> {
+        let mut buckets = self.buckets.lock().unwrap();
+        let mut key = client;
+        if buckets.clients.len() >= MAX_CLIENTS && !buckets.clients.contains_key(client) {
+            let sweep_due = match buckets.last_sweep {
+                Some(last) => now.duration_since(last) >= SWEEP_INTERVAL,
+                None => true,
+            };
+            if sweep_due {
+                // Buckets refilled to burst size are equivalent to new ones
+                let refill_secs = self.burst / self.rps;
+                buckets
+                    .clients
+                    .retain(|_, b| now.duration_since(b.updated).as_secs_f64() < refill_secs);
+                buckets.last_sweep = Some(now);
+            }
+            if buckets.clients.len() >= MAX_CLIENTS {
+                key = OVERFLOW_CLIENT;
+            }
+        }
+        let bucket = buckets.clients.entry(key.to_string()).or_insert(Bucket {
+            tokens: self.burst,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rps).min(self.burst);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(((1.0 - bucket.tokens) / self.rps).ceil() as u64)
+        }
+    }
+}
+
+/// Client IP of a request. Forwarded headers are only used for requests from trusted proxies.
+fn client_ip(peer: Option<IpAddr>, headers: &HeaderMap, trusted_proxies: &[IpAddr]) -> String {
+    let peer = match peer {
+        Some(peer) => peer,
+        None => return "-".to_string(),
+    };
+    if !trusted_proxies.contains(&peer) {
+        return peer.to_string();
+    }
+    // Each proxy appends the address of its peer, so the last untrusted address is the client
+    forwarded_for(headers)
+        .into_iter()
+        .rev()
+        .find(|ip| !trusted_proxies.contains(ip))
+        .unwrap_or(peer)
+        .to_string()
+}
+
+/// Addresses of `Forwarded: for=` or `X-Forwarded-For` headers in hop order
+fn forwarded_for(headers: &HeaderMap) -> Vec<IpAddr> {
+    let header_values = |name| {
+        headers
+            .get_all(name)
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .collect::<Vec<_>>()
+    };
+    let forwarded = header_values(header::FORWARDED)
+        .into_iter()
+        .filter_map(|element| {
+            element.split(';').find_map(|pair| {
+                let mut kv = pair.trim().splitn(2, '=');
+                match (kv.next(), kv.next()) {
+                    (Some(key), Some(node)) if key.eq_ignore_ascii_case("for") => {
+                        forwarded_node_ip(node)
+                    }
+                    _ => None,
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    if !forwarded.is_empty() {
+        return forwarded;
+    }
+    header_values(header::X_FORWARDED_FOR)
+        .into_iter()
+        .filter_map(|addr| addr.parse().ok())
+        .collect()
+}
+
+/// IP of a `Forwarded` node like `192.0.2.43`, `"192.0.2.43:47011"` or `"[2001:db8::17]:4711"`
+fn forwarded_node_ip(node: &str) -> Option<IpAddr> {
+    let node = node.trim_matches('"');
+    if let Some(node) = node.strip_prefix('[') {
+        node.split(']').next()?.parse().ok()
+    } else {
+        node.split(':').next()?.parse().ok()
+    }
+}
+
+/// Middleware limiting requests per client IP with a token bucket.
+/// The client IP is taken from `Forwarded` or `X-Forwarded-For` headers
+/// of requests from `trusted_proxies`.
+#[derive(Clone)]
+pub struct RateLimit {
+    limiter: Option<Arc<RateLimiter>>,
+}
+
+impl RateLimit {
+    pub fn from_config(config: &ApplicationCfg) -> Result<RateLimit, String> {
+        let rps = match config.webserver.rate_limit_rps {
+            Some(rps) if rps > 0.0 => rps,
+            Some(rps) => return Err(format!("Invalid rate_limit_rps {} (expected > 0)", rps)),
+            None => return Ok(RateLimit { limiter: None }),
+        };
+        let burst = match config.webserver.burst {
+            Some(0) => return Err("Invalid burst 0 (expected >= 1)".to_string()),
+            Some(burst) => burst as f64,
+            None => rps.ceil(),
+        };
+        let trusted_proxies = config
+            .webserver
+            .trusted_proxies
+            .iter()
+            .map(|addr| {
+                addr.parse::<IpAddr>()
+                    .map_err(|_| format!("Invalid trusted_proxies address '{}'", addr))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(RateLimit {
+            limiter: Some(Arc::new(RateLimiter {
+                rps,
+                burst,
+                buckets: Mutex::new(Buckets {
+                    clients: HashMap::new(),
+                    last_sweep: None,
+                }),
+                trusted_proxies,
+            })),
+        })
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RateLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service,
+            limiter: self.limiter.clone(),
+        }))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: S,
+    limiter: Option<Arc<RateLimiter>>,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        if let Some(ref limiter) = self.limiter {
+            let client = client_ip(
+                req.peer_addr().map(|addr| addr.ip()),
+                req.headers(),
+                &limiter.trusted_proxies,
+            );
+            if let Err(retry_after) = limiter.acquire(&client, Instant::now()) {
+                debug!("{} - Rate limit exceeded by {}", req.path(), client);
+                let resp = HttpResponse::TooManyRequests()
+                    .insert_header((header::RETRY_AFTER, retry_after.to_string()))
+                    .finish();
+                return Box::pin(ready(Ok(req.into_response(resp).map_into_right_body())));
+            }
+        }
+        let fut = self.service.call(req);
+        Box::pin(async move { fut.await.map(ServiceResponse::map_into_left_body) })
+    }
+}
+
+#[test]
+fn test_client_ip() {
+    use actix_web::http::header::HeaderValue;
+
+    let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+    let peer: IpAddr = "192.0.2.1".parse().unwrap();
+    let trusted = vec![proxy];
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::X_FORWARDED_FOR,
+        HeaderValue::from_static("203.0.113.9, 192.0.2.43"),
+    );
+    // Forwarded headers of untrusted peers are ignored
+    assert_eq!(client_ip(Some(peer), &headers, &trusted), "192.0.2.1");
+    // The last address not belonging to a trusted proxy is the client
+    assert_eq!(client_ip(Some(proxy), &headers, &trusted), "192.0.2.43");
+    headers.insert(
+        header::X_FORWARDED_FOR,
+        HeaderValue::from_static("192.0.2.43, 10.0.0.1"),
+    );
+    assert_eq!(client_ip(Some(proxy), &headers, &trusted), "192.0.2.43");
+    headers.insert(
+        header::FORWARDED,
+        HeaderValue::from_static(r#"for=192.0.2.60;proto=http, for="[2001:db8::17]:4711""#),
+    );
+    assert_eq!(client_ip(Some(proxy), &headers, &trusted), "2001:db8::17");
+    assert_eq!(client_ip(None, &headers, &trusted), "-");
+}
+
+#[test]
+fn test_max_clients() {
+    let limiter = RateLimiter {
+        rps: 1.0,
+        burst: 1.0,
+        buckets: Mutex::new(Buckets {
+            clients: HashMap::new(),
+            last_sweep: None,
+        }),
+        trusted_proxies: Vec::new(),
+    };
+    let now = Instant::now();
+    for i in 0..MAX_CLIENTS {
+        assert_eq!(limiter.acquire(&i.to_string(), now), Ok(()));
+    }
+    // New clients share a bucket while all buckets are in use
+    assert_eq!(limiter.acquire("new1", now), Ok(()));
+    assert_eq!(limiter.acquire("new2", now), Err(1));
+    assert_eq!(
+        limiter.buckets.lock().unwrap().clients.len(),
+        MAX_CLIENTS + 1
+    );
+    // Idle buckets are removed
+    let later = now + Duration::from_secs(2);
+    assert_eq!(limiter.acquire("new3", later), Ok(()));
+    assert_eq!(limiter.buckets.lock().unwrap().clients.len(), 1);
+}
@@ -0,0 +1,148 @@
+//
+// Copyright (c) Pirmin Kalberer. All rights reserved.
+// Licensed under the MIT License. See LICENSE file in the project root for full license information.
+//
+
+use crate::core::config::ApplicationCfg;
+use crate::core::{read_config, Config};
+use crate::mvt_service::MvtService;
+use actix_web::dev::{
+    forward_ready, Extensions, Service, ServiceRequest, ServiceResponse, Transform,
+};
+use actix_web::web::Data;
+use actix_web::Error;
+use futures::future::{ready, Ready};
+use std::cell::RefCell;
+use std::mem;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// Prepare a service with connected datasources for serving tiles
+pub fn prepare_service(mut service: MvtService) -> Result<MvtService, String> {
+    service.prepare_feature_queries();
+    service.check_srids()?;
+    service.check_versions();
+    service.init_cache();
+    service.load_tile_bitmaps();
+    Ok(service)
+}
+
+/// Configuration and service replaced on reload.
+/// Settings of the web server itself (bind address, middlewares, static files)
+/// and raster tilesets require a restart.
+pub struct ReloadableState {
+    config_path: Option<String>,
+    /// Number of successful reloads
+    generation: AtomicU64,
+    current: RwLock<(Data<ApplicationCfg>, Data<MvtService>)>,
+}
+
+impl ReloadableState {
+    pub fn new(config_path: Option<&str>, config: ApplicationCfg, service: MvtService) -> Self {
+        ReloadableState {
+            config_path: config_path.map(|path| path.to_string()),
+            generation: AtomicU64::new(0),
+            current: RwLock::new((Data::new(config), Data::new(service))),
+        }
+    }
+    /// Re-read configuration file and replace service. Keeps the current service on errors.
+    pub fn reload(&self) -> Result<(), String> {
+        let config_path = self
+            .config_path
+            .as_ref()
+            .ok_or("Reload requires a configuration file".to_string())?;
+        let config: ApplicationCfg = read_config(config_path)?;
+        let mut service = MvtService::from_config(&config)?;
+        // Keep the connection pools of unchanged datasources
+        let current = self.current.read().unwrap().1.clone();
+        let datasources = mem::replace(&mut service.datasources, current.datasources.clone());
+        service.reload_datasources(datasources);
+        service.detect_extents();
+        let service = prepare_service(service)?;
+        *self.current.write().unwrap() = (Data::new(config), Data::new(service));
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+/// Middleware providing the current configuration and service of a [ReloadableState]
+/// as app data. Before the first reload, the app data of the application is used.
+pub struct ReloadData {
+    state: Arc<ReloadableState>,
+}
+
+impl ReloadData {
+    pub fn new(state: Arc<ReloadableState>) -> Self {
+        ReloadData { state }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for ReloadData
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = ReloadDataMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ReloadDataMiddleware {
+            service,
+            state: self.state.clone(),
+            data: RefCell::new(None),
+        }))
+    }
+}
+
+pub struct ReloadDataMiddleware<S> {
+    service: S,
+    state: Arc<ReloadableState>,
+    /// App data of worker with its reload generation
+    data: RefCell<Option<(u64, Rc<Extensions>)>>,
+}
+
+impl<S> ReloadDataMiddleware<S> {
+    fn current_data(&self) -> Option<Rc<Extensions>> {
+        let generation = self.state.generation.load(Ordering::SeqCst);
+        if generation == 0 {
+            return None;
+        }
+        let mut data = self.data.borrow_mut();
+        match *data {
+            Some((data_generation, ref extensions)) if data_generation == generation => {
+                Some(extensions.clone())
+            }
+            _ => {
+                let (config, service) = self.state.current.read().unwrap().clone();
+                let mut extensions = Extensions::new();
+                extensions.insert(config);
+                extensions.insert(service);
+                let extensions = Rc::new(extensions);
+                *data = Some((generation, extensions.clone()));
+                Some(extensions)
+            }
+        }
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for ReloadDataMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = S::Future;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        if let Some(extensions) = self.current_data() {
+            // Containers added last take precedence over the application data
+            req.add_data_container(extensions);
+        }
+        self.service.call(req)
+    }
+}
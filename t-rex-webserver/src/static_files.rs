@@ -77,3 +77,64 @@ impl StaticFiles {
         self.files.get(&key as &str)
     }
 }
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// HTML list of tilesets from MVT service metadata
+pub fn tileset_index_html(mvt_metadata: &serde_json::Value, viewer: bool) -> String {
+    let mut rows = Vec::new();
+    let tilesets = mvt_metadata["tilesets"]
+        .as_array()
+        .map(|sets| sets.as_slice())
+        .unwrap_or(&[]);
+    for tileset in tilesets {
+        let name = html_escape(tileset["name"].as_str().unwrap_or(""));
+        let bounds = tileset["bounds"]
+            .as_array()
+            .map(|b| {
+                b.iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .unwrap_or_default();
+        let mut links = vec![
+            format!("<a href=\"/{}.json\">TileJSON</a>", name),
+            format!("<a href=\"/{}.style.json\">Style</a>", name),
+        ];
+        if viewer {
+            links.push("<a href=\"/index.html\">Viewer</a>".to_string());
+        }
+        rows.push(format!(
+            "<tr><td>{}</td><td>{}</td><td>{}-{}</td><td>{}</td></tr>",
+            name,
+            bounds,
+            tileset["minzoom"],
+            tileset["maxzoom"],
+            links.join(" ")
+        ));
+    }
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>t-rex tilesets</title>
+</head>
+<body>
+<h1>Tilesets</h1>
+<table>
+<tr><th>Name</th><th>Bounds</th><th>Zoom levels</th><th>Links</th></tr>
+{}
+</table>
+</body>
+</html>
+"#,
+        rows.join("\n")
+    )
+}